@@ -0,0 +1,137 @@
+// Nodes per second for the MinMax and AlphaBeta searches at fixed depths, before and after
+// they made and unmade moves in place instead of cloning every child. The "before" searches
+// are the clone-based ones they replaced, counting the positions they visit. The library's
+// searches visit the same positions in the same order, which the analyses agreeing checks,
+// so the counts hold for both. Run with `cargo bench`.
+extern crate kalaha;
+
+use std::time::Instant;
+
+use kalaha::ai::{AI, AlphaBeta, Analysis, MinMax};
+use kalaha::{Kalaha, Move, Player};
+
+const MINMAX_DEPTHS: [u32; 3] = [5, 6, 7];
+const ALPHA_BETA_DEPTHS: [u32; 3] = [8, 10, 12];
+
+// the start and a few positions further into a game, where the trees look different
+fn positions() -> Vec<Kalaha> {
+    let mut positions = vec![Kalaha::new()];
+    let mut game = Kalaha::new();
+    for ply in 0..12 {
+        let moves = game.legal_moves();
+        game.choose(moves[ply * 7 % moves.len()]);
+        if game.is_finished() {
+            break;
+        }
+        if ply % 4 == 3 {
+            positions.push(game.clone());
+        }
+    }
+    positions
+}
+
+fn score(game: &Kalaha, player: &Player) -> i32 {
+    game.bank(player) as i32 - game.bank(&player.next()) as i32
+}
+
+fn side_after(mv: Move, player: &Player) -> Player {
+    match mv {
+        Move::Swap => player.next(),
+        Move::Pond(_) | Move::Directed(..) => player.clone(),
+    }
+}
+
+// MinMax as it was, returning the best score and move
+fn cloned_minmax(game: &Kalaha, player: &Player, depth: u32, nodes: &mut u64) -> (i32, Move) {
+    let maximise = player == game.current_player();
+    let mut best: Option<(i32, Move)> = None;
+    for (mv, child) in game.children() {
+        *nodes += 1;
+        let side = side_after(mv, player);
+        let score = if depth <= 1 || child.is_finished() {
+            score(&child, &side)
+        } else {
+            cloned_minmax(&child, &side, depth - 1, nodes).0
+        };
+        best = Some(match best {
+            Some(best) if maximise => best.max((score, mv)),
+            Some(best) => best.min((score, mv)),
+            None => (score, mv),
+        });
+    }
+    best.expect("an unfinished game has moves")
+}
+
+// AlphaBeta as it was, trying the later ponds first
+fn cloned_alpha_beta(game: &Kalaha, player: &Player, depth: u32, mut alpha: i32, mut beta: i32, nodes: &mut u64)
+    -> (i32, Move)
+{
+    let maximise = player == game.current_player();
+    let mut best = (if maximise { i32::MIN } else { i32::MAX }, Move::Pond(6));
+    for (mv, child) in game.children().rev() {
+        *nodes += 1;
+        let side = side_after(mv, player);
+        let score = if depth <= 1 || child.is_finished() {
+            score(&child, &side)
+        } else {
+            cloned_alpha_beta(&child, &side, depth - 1, alpha, beta, nodes).0
+        };
+        if maximise {
+            best = best.max((score, mv));
+            alpha = alpha.max(best.0);
+        } else {
+            best = best.min((score, mv));
+            beta = beta.min(best.0);
+        }
+        if beta <= alpha {
+            break;
+        }
+    }
+    best
+}
+
+// seconds per run, repeating runs that are too quick to time once
+fn time(run: &mut dyn FnMut()) -> f64 {
+    let start = Instant::now();
+    let mut runs = 0;
+    while runs == 0 || start.elapsed().as_millis() < 200 {
+        run();
+        runs += 1;
+    }
+    start.elapsed().as_secs_f64() / f64::from(runs)
+}
+
+// Times the clone-based search against the library's from each position, checking they
+// come to the same analysis
+fn compare(name: &str, depth: u32, positions: &[Kalaha], cloned: &dyn Fn(&Kalaha, &mut u64) -> (i32, Move),
+           ai: &dyn AI) {
+    let mut nodes = 0;
+    for game in positions {
+        let (score, mv) = cloned(game, &mut nodes);
+        assert_eq!(ai.analyse(game), Ok(Analysis { mv, score: Some(score) }), "{} disagrees on\n{}", name, game);
+    }
+    let before = time(&mut || for game in positions {
+        cloned(game, &mut 0);
+    });
+    let after = time(&mut || for game in positions {
+        ai.analyse(game).unwrap();
+    });
+    println!("{} to depth {}: {} nodes from {} positions", name, depth, nodes, positions.len());
+    for &(label, secs) in &[("clone", before), ("make/unmake", after)] {
+        println!("{:>14}: {:.4}s, {:.0} nodes/s", label, secs, nodes as f64 / secs);
+    }
+}
+
+fn main() {
+    let positions = positions();
+    for &depth in &MINMAX_DEPTHS {
+        let cloned = |game: &Kalaha, nodes: &mut u64| cloned_minmax(game, game.current_player(), depth, nodes);
+        compare("MinMax", depth, &positions, &cloned, &MinMax::new(depth));
+    }
+    for &depth in &ALPHA_BETA_DEPTHS {
+        let cloned = |game: &Kalaha, nodes: &mut u64| {
+            cloned_alpha_beta(game, game.current_player(), depth, i32::MIN, i32::MAX, nodes)
+        };
+        compare("AlphaBeta", depth, &positions, &cloned, &AlphaBeta::new(depth));
+    }
+}
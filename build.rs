@@ -0,0 +1,22 @@
+// Generates the C API's header into OUT_DIR when building with the ffi feature. The copy in
+// include/kalaha.h is checked against it by tests/ffi.rs, which updates it on request.
+#[cfg(feature = "ffi")]
+extern crate cbindgen;
+
+#[cfg(feature = "ffi")]
+fn main() {
+    let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out = std::env::var("OUT_DIR").unwrap();
+    println!("cargo:rerun-if-changed=src/ffi/mod.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", dir)).expect("Can't read cbindgen.toml");
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(format!("{}/src/ffi/mod.rs", dir))
+        .generate()
+        .expect("Can't generate the C header")
+        .write_to_file(format!("{}/kalaha.h", out));
+}
+
+#[cfg(not(feature = "ffi"))]
+fn main() {}
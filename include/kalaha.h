@@ -0,0 +1,128 @@
+/* Generated by cbindgen from src/ffi/mod.rs, don't edit by hand */
+
+#ifndef KALAHA_H
+#define KALAHA_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#define KALAHA_PLAYER_A 0
+
+#define KALAHA_PLAYER_B 1
+
+#define KALAHA_PLAYER_NONE 2
+
+#define KALAHA_MOVE_POND 0
+
+#define KALAHA_MOVE_CLOCKWISE 1
+
+#define KALAHA_MOVE_COUNTER_CLOCKWISE 2
+
+#define KALAHA_MOVE_SWAP 3
+
+typedef enum {
+  KALAHA_STATUS_OK = 0,
+  KALAHA_STATUS_EMPTY_POOL,
+  KALAHA_STATUS_INVALID_INDEX,
+  KALAHA_STATUS_GAME_FINISHED,
+  KALAHA_STATUS_MUST_FEED,
+  KALAHA_STATUS_CANNOT_SWAP,
+  KALAHA_STATUS_WRONG_DIRECTION,
+  KALAHA_STATUS_NOT_IMPLEMENTED,
+  KALAHA_STATUS_NULL_POINTER,
+  KALAHA_STATUS_INVALID_ARGUMENT,
+  KALAHA_STATUS_NOT_FINISHED,
+  KALAHA_STATUS_AI_FAILED,
+} KalahaStatus;
+
+typedef struct KalahaGame KalahaGame;
+
+typedef struct {
+  uint32_t kind;
+  uint32_t pond;
+} KalahaMove;
+
+typedef struct {
+  bool extra_turn;
+  uint32_t captured;
+  uint32_t laps;
+} KalahaOutcome;
+
+typedef struct {
+  uint32_t winner;
+  uint32_t score_a;
+  uint32_t score_b;
+  bool forfeit;
+} KalahaResult;
+
+// A new game of Kalah from the usual start.
+KalahaGame *kalaha_new(void);
+
+// A new game under rules written as for the command line, e.g. "oware pie", or NULL if
+// they can't be read.
+//
+// # Safety
+// rules must be NULL or a NUL terminated string.
+KalahaGame *kalaha_with_rules(const char *rules);
+
+// Frees a game. NULL is ignored.
+//
+// # Safety
+// game must be NULL or a handle that hasn't been freed.
+void kalaha_free(KalahaGame *game);
+
+// KALAHA_STATUS_OK if the player to move may make mv, or why not.
+//
+// # Safety
+// game must be NULL or a live handle.
+KalahaStatus kalaha_valid_move(const KalahaGame *game, KalahaMove mv);
+
+// Makes mv if it's valid, filling in outcome unless it's NULL.
+//
+// # Safety
+// game must be NULL or a live handle, and outcome NULL or writable.
+KalahaStatus kalaha_choose(KalahaGame *game, KalahaMove mv, KalahaOutcome *outcome);
+
+// Copies the player's six ponds, from their first, into ponds.
+//
+// # Safety
+// game must be NULL or a live handle, and ponds NULL or room for six counts.
+KalahaStatus kalaha_ponds(const KalahaGame *game, uint32_t side, uint32_t *ponds);
+
+// The seeds in the player's bank, or 0 for a NULL game or unknown player.
+//
+// # Safety
+// game must be NULL or a live handle.
+uint32_t kalaha_bank(const KalahaGame *game, uint32_t side);
+
+// KALAHA_PLAYER_A or KALAHA_PLAYER_B, or KALAHA_PLAYER_NONE once the game has finished.
+//
+// # Safety
+// game must be NULL or a live handle.
+uint32_t kalaha_current_player(const KalahaGame *game);
+
+// Whether the game has finished. A NULL game counts as finished.
+//
+// # Safety
+// game must be NULL or a live handle.
+bool kalaha_is_finished(const KalahaGame *game);
+
+// Fills in result once the game has finished.
+//
+// # Safety
+// game must be NULL or a live handle, and result NULL or writable.
+KalahaStatus kalaha_result(const KalahaGame *game, KalahaResult *result);
+
+// Asks an AI ("first", "last", "minmax", "alphabeta" or "caching") searching to depth for
+// a move, without making it.
+//
+// # Safety
+// game must be NULL or a live handle, ai NULL or a NUL terminated string and mv NULL or
+// writable.
+KalahaStatus kalaha_ai_move(const KalahaGame *game, const char *ai, uint32_t depth, KalahaMove *mv);
+
+// A description of a status, which lives as long as the program. Values that aren't a
+// KalahaStatus get "unknown status".
+const char *kalaha_status_message(uint32_t status);
+
+#endif  /* KALAHA_H */
@@ -1,25 +1,186 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 use super::Kalaha;
+use super::multi::MultiKalaha;
+use super::moves::Move;
 use super::player::Player;
 
 #[cfg(test)]
 mod tests;
 
+#[derive(Debug, PartialEq, Clone)]
+pub enum AIError {
+    NoValidMoves,
+    GameFinished,
+    // the search passed its deadline, see Until
+    OutOfTime,
+    // the search was told to stop, see Until
+    Stopped,
+    Failed(String),
+}
+
+impl fmt::Display for AIError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            AIError::NoValidMoves => f.write_str("No valid moves"),
+            AIError::GameFinished => f.write_str("Game has finished"),
+            AIError::OutOfTime => f.write_str("Ran out of time"),
+            AIError::Stopped => f.write_str("Search was stopped"),
+            AIError::Failed(ref reason) => f.write_str(&format!("AI failed: {}", reason)),
+        }
+    }
+}
+
 pub trait AI: fmt::Debug {
-    fn choose(&self, game: &Kalaha) -> usize;
+    fn choose(&self, game: &Kalaha) -> Result<Move, AIError>;
+
+    // The move along with what the AI expects it to lead to, for AIs that search
+    fn analyse(&self, game: &Kalaha) -> Result<Analysis, AIError> {
+        self.choose(game).map(|mv| Analysis { mv, score: None })
+    }
+
+    // Like analyse, along with the line of play the AI expects, starting with its move.
+    // Searches give up as until says. AIs that don't search only know their own move.
+    fn analyse_until(&self, game: &Kalaha, _until: &Until) -> Result<(Analysis, Vec<Move>), AIError> {
+        self.analyse(game).map(|analysis| (analysis, vec![analysis.mv]))
+    }
+}
+
+// When a search gives up: with AIError::OutOfTime once past the deadline, and with
+// AIError::Stopped as soon as stop is set from another thread, say because the position
+// being searched was taken back. The default never gives up.
+#[derive(Debug, Default, Clone)]
+pub struct Until {
+    pub deadline: Option<Instant>,
+    pub stop: Option<Arc<AtomicBool>>,
+}
+
+impl Until {
+    pub fn deadline(deadline: Option<Instant>) -> Until {
+        Until { deadline, stop: None }
+    }
+
+    fn never(&self) -> bool {
+        self.deadline.is_none() && self.stop.is_none()
+    }
+
+    fn check(&self) -> Result<(), AIError> {
+        if self.stop.as_ref().is_some_and(|stop| stop.load(Ordering::Relaxed)) {
+            Err(AIError::Stopped)
+        } else if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            Err(AIError::OutOfTime)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Analysis {
+    pub mv: Move,
+    // the bank difference the player to move expects to end the search with
+    pub score: Option<i32>,
+}
+
+// An AI for games with any number of seats, returning the pond to sow from
+pub trait MultiAI: fmt::Debug {
+    fn choose(&self, game: &MultiKalaha) -> Result<usize, AIError>;
+}
+
+// One of the two player AIs by its name on the command line, searching to depth if it searches
+pub fn by_name(name: &str, depth: u32) -> Option<Box<dyn AI>> {
+    let ai: Box<dyn AI> = match name {
+        "first" => Box::new(FirstValid {}),
+        "last" => Box::new(LastValid {}),
+        "minmax" => Box::new(MinMax::new(depth)),
+        "alphabeta" => Box::new(AlphaBeta::new(depth)),
+        "caching" => Box::new(CachingMinMax::new(depth)),
+        _ => return None,
+    };
+    Some(ai)
+}
+
+// nodes searched between looks at the clock
+const CLOCK_INTERVAL: u32 = 1024;
+
+// What a search needs besides the position: when to give up, and whether to keep the line
+// of play it expects, which analyse has no use for
+struct Search<'a> {
+    until: &'a Until,
+    lines: bool,
+    // nodes left until the clock is looked at again
+    countdown: Cell<u32>,
+}
+
+impl<'a> Search<'a> {
+    fn new(until: &'a Until, lines: bool) -> Search<'a> {
+        Search { until, lines, countdown: Cell::new(CLOCK_INTERVAL) }
+    }
+
+    // an error once until says to give up, found out within CLOCK_INTERVAL nodes
+    fn check_clock(&self) -> Result<(), AIError> {
+        if self.until.never() {
+            return Ok(());
+        }
+        let countdown = self.countdown.get();
+        if countdown > 0 {
+            self.countdown.set(countdown - 1);
+            return Ok(());
+        }
+        self.countdown.set(CLOCK_INTERVAL);
+        self.until.check()
+    }
+
+    // the line after the last move searched, empty if lines are kept
+    fn leaf(&self) -> Option<Vec<Move>> {
+        if self.lines {
+            Some(Vec::new())
+        } else {
+            None
+        }
+    }
+}
+
+// mv and then the line that follows it
+fn line_from(mv: Move, line: Option<Vec<Move>>) -> Option<Vec<Move>> {
+    line.map(|mut line| {
+        line.insert(0, mv);
+        line
+    })
+}
+
+fn no_move(game: &Kalaha) -> AIError {
+    if game.is_finished() {
+        AIError::GameFinished
+    } else {
+        AIError::NoValidMoves
+    }
+}
+
+fn no_multi_move(game: &MultiKalaha) -> AIError {
+    if game.is_finished() {
+        AIError::GameFinished
+    } else {
+        AIError::NoValidMoves
+    }
 }
 
 #[derive(Debug)]
 pub struct FirstValid {}
 
 impl AI for FirstValid {
-    fn choose(&self, game: &Kalaha) -> usize {
-        for i in 0..super::PONDS_PER_PLAYER {
-            if game.valid_move(i).is_ok() {
-                return i;
-            }
-        }
-        panic!("No valid moves");
+    fn choose(&self, game: &Kalaha) -> Result<Move, AIError> {
+        game.legal_moves().first().cloned().ok_or_else(|| no_move(game))
+    }
+}
+
+impl MultiAI for FirstValid {
+    fn choose(&self, game: &MultiKalaha) -> Result<usize, AIError> {
+        game.legal_moves().first().and_then(Move::pond).ok_or_else(|| no_multi_move(game))
     }
 }
 
@@ -27,13 +188,14 @@ impl AI for FirstValid {
 pub struct LastValid {}
 
 impl AI for LastValid {
-    fn choose(&self, game: &Kalaha) -> usize {
-        for i in (0..super::PONDS_PER_PLAYER).rev() {
-            if game.valid_move(i).is_ok() {
-                return i;
-            }
-        }
-        panic!("No valid moves");
+    fn choose(&self, game: &Kalaha) -> Result<Move, AIError> {
+        game.legal_moves().last().cloned().ok_or_else(|| no_move(game))
+    }
+}
+
+impl MultiAI for LastValid {
+    fn choose(&self, game: &MultiKalaha) -> Result<usize, AIError> {
+        game.legal_moves().last().and_then(Move::pond).ok_or_else(|| no_multi_move(game))
     }
 }
 
@@ -42,17 +204,29 @@ pub struct MinMax {
     depth: u32,
 }
 
-// sorts by score, then by pond
+// sorts by score, then by move
 // note that choosing higher pond values will generally give a better result
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 struct ScoredMove {
     score: i32,
-    pond: usize,
+    mv: Move,
 }
 
 impl ScoredMove {
-    fn new(pond: usize, score: i32) -> ScoredMove {
-        ScoredMove { pond, score }
+    fn new(mv: Move, score: i32) -> ScoredMove {
+        ScoredMove { mv, score }
+    }
+
+    fn analysis(self) -> Analysis {
+        Analysis { mv: self.mv, score: Some(self.score) }
+    }
+}
+
+// Swapping sides hands the player we're scoring for the other side of the board
+fn side_after(mv: Move, player: &Player) -> Player {
+    match mv {
+        Move::Swap => player.next(),
+        Move::Pond(_) | Move::Directed(..) => player.clone(),
     }
 }
 
@@ -61,24 +235,42 @@ impl MinMax {
         MinMax { depth }
     }
 
-    fn choose_depth(&self, game: &Kalaha, player: &Player, depth: u32) -> ScoredMove {
-        let mut scores = Vec::new();
-        for i in 0..super::PONDS_PER_PLAYER {
-            if game.valid_move(i).is_ok() {
-                let mut game2 = game.clone();
-                game2.choose(i);
-                if depth <= 1 || game2.is_finished() {
-                    scores.push(ScoredMove::new(i, self.score(&game2, player)));
-                } else {
-                    scores.push(ScoredMove::new(i, self.choose_depth(&game2, player, depth - 1).score));
-                }
+    fn choose_depth(&self, game: &mut Kalaha, player: &Player, depth: u32, search: &Search)
+        -> Result<(ScoredMove, Option<Vec<Move>>), AIError>
+    {
+        search.check_clock()?;
+        let maximise = player == game.current_player();
+        let mut best: Option<(ScoredMove, Option<Vec<Move>>)> = None;
+        for mv in game.legal_moves() {
+            let (_, undo) = game.make_move(mv);
+            let side = side_after(mv, player);
+            let score = if depth <= 1 || game.is_finished() {
+                Ok((self.score(game, &side), search.leaf()))
+            } else {
+                self.choose_depth(game, &side, depth - 1, search).map(|(scored, line)| (scored.score, line))
+            };
+            game.unmake_move(undo);
+            let (score, line) = score?;
+            let scored = ScoredMove::new(mv, score);
+            let better = match best {
+                Some((best, _)) if maximise => scored > best,
+                Some((best, _)) => scored < best,
+                None => true,
+            };
+            if better {
+                best = Some((scored, line));
             }
         }
-        if player == game.current_player() {
-            *scores.iter().max().expect("No valid moves")
-        } else {
-            *scores.iter().min().expect("No valid moves")
+        best.map(|(scored, line)| (scored, line_from(scored.mv, line))).ok_or_else(|| no_move(game))
+    }
+
+    fn search(&self, game: &Kalaha, search: &Search) -> Result<(Analysis, Option<Vec<Move>>), AIError> {
+        if game.is_finished() {
+            return Err(AIError::GameFinished);
         }
+        let player = game.current_player().clone();
+        self.choose_depth(&mut game.clone(), &player, self.depth, search)
+            .map(|(scored, line)| (scored.analysis(), line))
     }
 
     fn score(&self, game: &Kalaha, player: &Player) -> i32 {
@@ -87,8 +279,16 @@ impl MinMax {
 }
 
 impl AI for MinMax {
-    fn choose(&self, game: &Kalaha) -> usize {
-        self.choose_depth(game, game.current_player(), self.depth).pond
+    fn choose(&self, game: &Kalaha) -> Result<Move, AIError> {
+        self.analyse(game).map(|analysis| analysis.mv)
+    }
+
+    fn analyse(&self, game: &Kalaha) -> Result<Analysis, AIError> {
+        self.search(game, &Search::new(&Until::default(), false)).map(|(analysis, _)| analysis)
+    }
+
+    fn analyse_until(&self, game: &Kalaha, until: &Until) -> Result<(Analysis, Vec<Move>), AIError> {
+        self.search(game, &Search::new(until, true)).map(|(analysis, line)| (analysis, line.unwrap_or_default()))
     }
 }
 
@@ -103,73 +303,245 @@ impl AlphaBeta {
     }
 
     // See https://en.wikipedia.org/wiki/Alpha%E2%80%93beta_pruning
-    fn alpha_beta(&self, game: &Kalaha, player: &Player, depth: u32, alpha: i32, beta: i32) -> ScoredMove {
+    fn alpha_beta(&self, game: &mut Kalaha, player: &Player, depth: u32, alpha: i32, beta: i32, search: &Search)
+        -> Result<(ScoredMove, Option<Vec<Move>>), AIError>
+    {
+        search.check_clock()?;
+        let moves = game.legal_moves();
+        if moves.is_empty() {
+            return Err(no_move(game));
+        }
         let mut best_guess;
+        let mut best_line = None;
         let mut alpha = alpha;
         let mut beta = beta;
+        // Reverse the order because generally the later ponds are a better choice
+        // and we want to prune asap
         if game.current_player() == player {
-            best_guess = ScoredMove::new(6, i32::min_value());
-            for (pond, game2) in self.all_children(game) {
-                best_guess = best_guess.max(
-                    ScoredMove::new(
-                        pond,
-                        self.score_for(&game2, player, pond, depth, alpha, beta).score
-                    )
-                );
+            best_guess = ScoredMove::new(Move::Pond(super::PONDS_PER_PLAYER), i32::MIN);
+            for mv in moves.into_iter().rev() {
+                let (score, line) = self.score_for(game, player, mv, depth, (alpha, beta), search)?;
+                let scored = ScoredMove::new(mv, score);
+                if scored > best_guess {
+                    best_guess = scored;
+                    best_line = line;
+                }
                 alpha = best_guess.score.max(alpha);
                 if beta <= alpha {
                     break
                 }
             }
         } else {
-            best_guess = ScoredMove::new(6, i32::max_value());
-            for (pond, game2) in self.all_children(game) {
-                best_guess = best_guess.min(
-                    ScoredMove::new(
-                        pond,
-                        self.score_for(&game2, player, pond, depth, alpha, beta).score
-                    )
-                );
+            best_guess = ScoredMove::new(Move::Pond(super::PONDS_PER_PLAYER), i32::MAX);
+            for mv in moves.into_iter().rev() {
+                let (score, line) = self.score_for(game, player, mv, depth, (alpha, beta), search)?;
+                let scored = ScoredMove::new(mv, score);
+                if scored < best_guess {
+                    best_guess = scored;
+                    best_line = line;
+                }
                 beta = best_guess.score.min(beta);
                 if beta <= alpha {
                     break
                 }
             }
         }
-        best_guess
+        Ok((best_guess, line_from(best_guess.mv, best_line)))
     }
 
-    fn score_for(&self, game: &Kalaha, player: &Player, pond: usize, depth: u32, alpha: i32, beta: i32) -> ScoredMove {
-        if depth <= 1 || game.is_finished() {
-            ScoredMove::new(pond, self.score(game, player))
+    // the score after making mv and the line that follows, leaving game as it was
+    fn score_for(&self, game: &mut Kalaha, player: &Player, mv: Move, depth: u32, (alpha, beta): (i32, i32),
+                 search: &Search) -> Result<(i32, Option<Vec<Move>>), AIError>
+    {
+        let (_, undo) = game.make_move(mv);
+        let side = side_after(mv, player);
+        let score = if depth <= 1 || game.is_finished() {
+            Ok((self.score(game, &side), search.leaf()))
         } else {
-            self.alpha_beta(game, player, depth - 1, alpha, beta)
+            self.alpha_beta(game, &side, depth - 1, alpha, beta, search).map(|(scored, line)| (scored.score, line))
+        };
+        game.unmake_move(undo);
+        score
+    }
+
+    fn score(&self, game: &Kalaha, player: &Player) -> i32 {
+        game.bank(player) as i32 - game.bank(&player.next()) as i32
+    }
+
+    fn search(&self, game: &Kalaha, search: &Search) -> Result<(Analysis, Option<Vec<Move>>), AIError> {
+        if game.is_finished() {
+            return Err(AIError::GameFinished);
+        }
+        let player = game.current_player().clone();
+        self.alpha_beta(&mut game.clone(), &player, self.depth, i32::MIN, i32::MAX, search)
+            .map(|(scored, line)| (scored.analysis(), line))
+    }
+}
+
+impl AI for AlphaBeta {
+    fn choose(&self, game: &Kalaha) -> Result<Move, AIError> {
+        self.analyse(game).map(|analysis| analysis.mv)
+    }
+
+    fn analyse(&self, game: &Kalaha) -> Result<Analysis, AIError> {
+        self.search(game, &Search::new(&Until::default(), false)).map(|(analysis, _)| analysis)
+    }
+
+    fn analyse_until(&self, game: &Kalaha, until: &Until) -> Result<(Analysis, Vec<Move>), AIError> {
+        self.search(game, &Search::new(until, true)).map(|(analysis, line)| (analysis, line.unwrap_or_default()))
+    }
+}
+
+// Minimax that remembers the value of every position it has searched, so positions reached
+// by different move orders, or by the mirrored moves of the other player, are only
+// searched once. Picks the same moves as MinMax.
+pub struct CachingMinMax {
+    depth: u32,
+    // best moves and their values to the player to move in canonical positions, by depth
+    // searched. Pond numbers are the same in the mirror, so the moves are too.
+    cache: RefCell<HashMap<(Kalaha, u32), ScoredMove>>,
+}
+
+impl CachingMinMax {
+    pub fn new(depth: u32) -> CachingMinMax {
+        CachingMinMax { depth, cache: RefCell::new(HashMap::new()) }
+    }
+
+    // the best score the player to move can get, searching depth moves ahead
+    fn value(&self, game: &mut Kalaha, depth: u32, search: &Search) -> Result<i32, AIError> {
+        // the mirror's player to move is the other player on the other side, in the same spot
+        let (key, _) = game.canonical();
+        if let Some(best) = self.cache.borrow().get(&(key.clone(), depth)) {
+            return Ok(best.score);
         }
+        search.check_clock()?;
+        let best = self.scored_moves(game, depth, search)?.into_iter().max().ok_or_else(|| no_move(game))?;
+        self.cache.borrow_mut().insert((key, depth), best);
+        Ok(best.score)
+    }
+
+    // every legal move with its score to the player making it
+    fn scored_moves(&self, game: &mut Kalaha, depth: u32, search: &Search) -> Result<Vec<ScoredMove>, AIError> {
+        let player = game.current_player().clone();
+        let mut scored = Vec::new();
+        for mv in game.legal_moves() {
+            let (_, undo) = game.make_move(mv);
+            let side = side_after(mv, &player);
+            let score = if depth <= 1 || game.is_finished() {
+                Ok(self.score(game, &side))
+            } else {
+                let next_player = game.current_player().clone();
+                self.value(game, depth - 1, search).map(|value| if next_player == side { value } else { -value })
+            };
+            game.unmake_move(undo);
+            scored.push(ScoredMove::new(mv, score?));
+        }
+        Ok(scored)
     }
 
     fn score(&self, game: &Kalaha, player: &Player) -> i32 {
         game.bank(player) as i32 - game.bank(&player.next()) as i32
     }
 
-    fn all_children(&self, game: &Kalaha) -> Vec<(usize, Kalaha)> {
-        let mut ret = Vec::new();
-        // Reverse the order because generally the later ponds are a better choice
-        // and we want to prune asap
-        for i in (0..super::PONDS_PER_PLAYER).rev() {
-            if game.valid_move(i).is_ok() {
-                let mut game2 = game.clone();
-                game2.choose(i);
-                ret.push((i, game2));
+    fn search(&self, game: &Kalaha, search: &Search) -> Result<ScoredMove, AIError> {
+        if game.is_finished() {
+            return Err(AIError::GameFinished);
+        }
+        self.cache.borrow_mut().clear();
+        let best = self.scored_moves(&mut game.clone(), self.depth, search)?.into_iter().max();
+        best.ok_or_else(|| no_move(game))
+    }
+
+    // The line of play after first, following the best moves the last search cached
+    fn cached_line(&self, game: &Kalaha, first: Move) -> Vec<Move> {
+        let mut line = vec![first];
+        let mut game = game.clone();
+        game.choose(first);
+        let cache = self.cache.borrow();
+        for depth in (1..self.depth).rev() {
+            let (key, _) = game.canonical();
+            match cache.get(&(key, depth)) {
+                Some(best) => {
+                    game.choose(best.mv);
+                    line.push(best.mv);
+                },
+                None => break,
             }
         }
-        ret
+        line
     }
 }
 
-impl AI for AlphaBeta {
-    fn choose(&self, game: &Kalaha) -> usize {
-        self.alpha_beta(
-            game, game.current_player(), self.depth, i32::min_value(), i32::max_value()
-        ).pond
+// leaves out the cache, which can be big
+impl fmt::Debug for CachingMinMax {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("CachingMinMax").field("depth", &self.depth).finish()
+    }
+}
+
+impl AI for CachingMinMax {
+    fn choose(&self, game: &Kalaha) -> Result<Move, AIError> {
+        self.analyse(game).map(|analysis| analysis.mv)
+    }
+
+    fn analyse(&self, game: &Kalaha) -> Result<Analysis, AIError> {
+        self.search(game, &Search::new(&Until::default(), false)).map(ScoredMove::analysis)
+    }
+
+    fn analyse_until(&self, game: &Kalaha, until: &Until) -> Result<(Analysis, Vec<Move>), AIError> {
+        let best = self.search(game, &Search::new(until, false))?;
+        Ok((best.analysis(), self.cached_line(game, best.mv)))
+    }
+}
+
+// Max-n search for any number of seats: every seat is assumed to play whatever is best for
+// itself, judging a position by its own bank less the biggest bank of anyone else.
+#[derive(Debug)]
+pub struct MaxN {
+    depth: u32,
+}
+
+impl MaxN {
+    pub fn new(depth: u32) -> MaxN {
+        MaxN { depth }
+    }
+
+    // the pond to play and the banks it leads to
+    fn search(&self, game: &MultiKalaha, depth: u32) -> Result<(usize, Vec<u32>), AIError> {
+        let seat = game.current_seat();
+        let mut best: Option<(i32, usize, Vec<u32>)> = None;
+        for pond in game.legal_moves().iter().filter_map(Move::pond) {
+            let mut child = game.clone();
+            child.choose(pond);
+            let banks = if depth <= 1 || child.is_finished() {
+                child.banks()
+            } else {
+                self.search(&child, depth - 1)?.1
+            };
+            let score = self.score(&banks, seat);
+            // ties go to the later pond, as with the two player AIs
+            if best.as_ref().is_none_or(|&(best_score, _, _)| score >= best_score) {
+                best = Some((score, pond, banks));
+            }
+        }
+        best.map(|(_, pond, banks)| (pond, banks)).ok_or_else(|| no_multi_move(game))
+    }
+
+    fn score(&self, banks: &[u32], seat: usize) -> i32 {
+        let best_other = banks.iter().enumerate()
+            .filter(|&(other, _)| other != seat)
+            .map(|(_, &bank)| bank)
+            .max()
+            .unwrap_or(0);
+        banks[seat] as i32 - best_other as i32
+    }
+}
+
+impl MultiAI for MaxN {
+    fn choose(&self, game: &MultiKalaha) -> Result<usize, AIError> {
+        if game.is_finished() {
+            return Err(AIError::GameFinished);
+        }
+        Ok(self.search(game, self.depth)?.0)
     }
 }
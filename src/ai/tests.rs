@@ -1,14 +1,22 @@
 use super::*;
+use std::thread;
+use std::time::Duration;
 
 fn assert_depth_eq(depth: u32) {
-    let game = Kalaha::new();
+    assert_depth_eq_for(Kalaha::new(), depth);
+}
+
+fn assert_depth_eq_for(mut game: Kalaha, depth: u32) {
+    let player = game.current_player().clone();
     // Do comparison based on score rather than pond to allow the algorithms to choose different
     // ponds with the same score
+    let until = Until::default();
+    let search = Search::new(&until, false);
     assert_eq!(
-        MinMax { depth }.choose_depth(&game,game.current_player(), depth).score,
+        MinMax { depth }.choose_depth(&mut game, &player, depth, &search).unwrap().0.score,
         AlphaBeta { depth }.alpha_beta(
-            &game, game.current_player(), depth, i32::min_value(), i32::max_value()
-        ).score
+            &mut game, &player, depth, i32::MIN, i32::MAX, &search
+        ).unwrap().0.score
     );
 }
 
@@ -36,3 +44,228 @@ fn minmax_eq_alphabeta_4() {
 fn minmax_eq_alphabeta_5() {
     assert_depth_eq(5);
 }
+
+#[test]
+fn minmax_eq_alphabeta_oware() {
+    assert_depth_eq_for(Kalaha::with_rules(::Rules::oware()), 5);
+}
+
+#[test]
+fn minmax_eq_alphabeta_pie() {
+    assert_depth_eq_for(Kalaha::with_rules(::Rules::kalah().with_pie_rule(true)), 5);
+}
+
+#[test]
+fn minmax_eq_alphabeta_player_choice() {
+    let rules = ::Rules::oware().with_sowing(::SowingPolicy::PlayerChoice);
+    assert_depth_eq_for(Kalaha::with_rules(rules), 4);
+}
+
+// Player A has banked two seeds with their first move, which player B can take over
+fn swap_offered() -> Kalaha {
+    let mut game = Kalaha::with_rules(::Rules::kalah().with_pie_rule(true));
+    game.choose(0);
+    game.choose(1);
+    game
+}
+
+#[test]
+fn minmax_swaps() {
+    assert_eq!(MinMax::new(1).choose(&swap_offered()), Ok(Move::Swap));
+}
+
+#[test]
+fn alphabeta_swaps() {
+    assert_eq!(AlphaBeta::new(1).choose(&swap_offered()), Ok(Move::Swap));
+}
+
+#[test]
+fn alphabeta_plays_oware() {
+    let mut game = Kalaha::with_rules(::Rules::oware());
+    let report = game.play(&AlphaBeta::new(4), &FirstValid {}, ::IllegalMovePolicy::Forfeit, false);
+    assert!(report.incidents.is_empty());
+    assert!(game.is_finished());
+}
+
+fn finished_game() -> Kalaha {
+    let mut game = Kalaha::new();
+    game.play(&FirstValid {}, &LastValid {}, ::IllegalMovePolicy::Forfeit, false);
+    game
+}
+
+#[test]
+fn first_valid_finished() {
+    assert_eq!(AI::choose(&FirstValid {}, &finished_game()), Err(AIError::GameFinished));
+}
+
+#[test]
+fn last_valid_finished() {
+    assert_eq!(AI::choose(&LastValid {}, &finished_game()), Err(AIError::GameFinished));
+}
+
+#[test]
+fn minmax_finished() {
+    assert_eq!(MinMax::new(3).choose(&finished_game()), Err(AIError::GameFinished));
+}
+
+#[test]
+fn alphabeta_finished() {
+    assert_eq!(AlphaBeta::new(3).choose(&finished_game()), Err(AIError::GameFinished));
+}
+
+// With two seats max-n is minimax, so both pick the same pond
+#[test]
+fn maxn_matches_minmax_with_two_seats() {
+    for depth in 1..5 {
+        let multi = MaxN::new(depth).choose(&MultiKalaha::new(2)).unwrap();
+        assert_eq!(Move::Pond(multi), AI::choose(&MinMax::new(depth), &Kalaha::new()).unwrap());
+    }
+}
+
+#[test]
+fn maxn_takes_extra_turn() {
+    // every first move banks a seed, but only pond 0 gets to move again
+    assert_eq!(MaxN::new(1).choose(&MultiKalaha::new(3)), Ok(5));
+    assert_eq!(MaxN::new(2).choose(&MultiKalaha::new(3)), Ok(0));
+}
+
+#[test]
+fn maxn_plays_four_seats() {
+    let mut game = MultiKalaha::new(4);
+    let maxn = MaxN::new(3);
+    let ranking = game.play(&[&maxn, &FirstValid {}, &LastValid {}, &FirstValid {}], false).unwrap();
+    assert_eq!(ranking.standings.len(), 4);
+    assert!(game.is_finished());
+}
+
+#[test]
+fn maxn_finished() {
+    let mut game = MultiKalaha::new(3);
+    game.play(&[&FirstValid {}, &FirstValid {}, &FirstValid {}], false).unwrap();
+    assert_eq!(MaxN::new(3).choose(&game), Err(AIError::GameFinished));
+}
+
+// a position some way into a random game, with the game still going
+fn random_position(seed: u64, rules: ::Rules) -> Kalaha {
+    let mut rng = ::rng::Rng::new(seed);
+    let mut game = Kalaha::with_rules(rules);
+    for _ in 0..rng.below(20) {
+        let moves = game.legal_moves();
+        let mut child = game.clone();
+        child.choose(moves[rng.below(moves.len())]);
+        if child.is_finished() {
+            break;
+        }
+        game = child;
+    }
+    game
+}
+
+#[test]
+fn caching_minmax_eq_minmax() {
+    for seed in 0..20 {
+        let game = random_position(seed, ::Rules::kalah());
+        for depth in 1..5 {
+            assert_eq!(CachingMinMax::new(depth).choose(&game), MinMax::new(depth).choose(&game));
+        }
+    }
+}
+
+#[test]
+fn caching_minmax_eq_minmax_pie() {
+    let game = Kalaha::with_rules(::Rules::kalah().with_pie_rule(true));
+    assert_eq!(CachingMinMax::new(4).choose(&game), MinMax::new(4).choose(&game));
+    assert_eq!(CachingMinMax::new(3).choose(&swap_offered()), MinMax::new(3).choose(&swap_offered()));
+}
+
+#[test]
+fn caching_minmax_shares_mirrored_positions() {
+    let ai = CachingMinMax::new(4);
+    ai.choose(&Kalaha::new()).unwrap();
+    let cache = ai.cache.borrow();
+    assert!(cache.keys().all(|(game, _)| game.is_finished() || *game.current_player() == Player::A));
+}
+
+#[test]
+fn caching_minmax_finished() {
+    assert_eq!(CachingMinMax::new(3).choose(&finished_game()), Err(AIError::GameFinished));
+}
+
+// The player to move does as well in a position as the other player would in its mirror
+#[test]
+fn alphabeta_mirror_invariant() {
+    for &rules in &[::Rules::kalah(), ::Rules::oware()] {
+        for seed in 0..20 {
+            let mut game = random_position(seed, rules);
+            let mut mirror = game.mirrored();
+            let until = Until::default();
+    let search = Search::new(&until, false);
+            for depth in 1..5 {
+                let player = game.current_player().clone();
+                let score = AlphaBeta { depth }.alpha_beta(&mut game, &player, depth, i32::MIN, i32::MAX, &search);
+                let mirror_player = mirror.current_player().clone();
+                let mirror_score =
+                    AlphaBeta { depth }.alpha_beta(&mut mirror, &mirror_player, depth, i32::MIN, i32::MAX, &search);
+                assert_eq!(mirror_player, player.next());
+                assert_eq!(score.unwrap().0.score, mirror_score.unwrap().0.score);
+            }
+        }
+    }
+}
+
+#[test]
+fn analyse_scores() {
+    let game = swap_offered();
+    let minmax = MinMax::new(4).analyse(&game).unwrap();
+    assert_eq!(minmax.mv, MinMax::new(4).choose(&game).unwrap());
+    assert_eq!(AlphaBeta::new(4).analyse(&game).unwrap().score, minmax.score);
+    assert_eq!(CachingMinMax::new(4).analyse(&game).unwrap().score, minmax.score);
+    assert_eq!(AI::analyse(&FirstValid {}, &game), Ok(Analysis { mv: Move::Pond(0), score: None }));
+}
+
+// Playing out the line a search expects ends up with the score it gave
+#[test]
+fn lines_reach_their_scores() {
+    let ais: [&dyn AI; 3] = [&MinMax::new(4), &AlphaBeta::new(4), &CachingMinMax::new(4)];
+    for seed in 0..20 {
+        let game = random_position(seed, ::Rules::kalah());
+        for ai in &ais {
+            let (analysis, line) = ai.analyse_until(&game, &Until::default()).unwrap();
+            assert_eq!(analysis, ai.analyse(&game).unwrap());
+            assert_eq!(line[0], analysis.mv);
+            let player = game.current_player().clone();
+            let mut end = game.clone();
+            for &mv in &line {
+                end.choose(mv);
+            }
+            assert!(line.len() == 4 || end.is_finished(), "{:?} {:?}", ai, line);
+            let score = end.bank(&player) as i32 - end.bank(&player.next()) as i32;
+            assert_eq!(Some(score), analysis.score, "{:?} {:?}", ai, line);
+        }
+    }
+    let (_, line) = AI::analyse_until(&FirstValid {}, &Kalaha::new(), &Until::default()).unwrap();
+    assert_eq!(line, vec![Move::Pond(0)]);
+}
+
+#[test]
+fn searches_stop_at_the_deadline() {
+    let until = Until::deadline(Some(Instant::now()));
+    let ais: [&dyn AI; 3] = [&MinMax::new(12), &AlphaBeta::new(20), &CachingMinMax::new(20)];
+    for ai in &ais {
+        assert_eq!(ai.analyse_until(&Kalaha::new(), &until), Err(AIError::OutOfTime), "{:?}", ai);
+    }
+}
+
+#[test]
+fn searches_stop_when_told() {
+    let stop = Arc::new(AtomicBool::new(false));
+    let until = Until { deadline: None, stop: Some(Arc::clone(&stop)) };
+    let stopper = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        stop.store(true, Ordering::Relaxed);
+    });
+    let start = Instant::now();
+    assert_eq!(MinMax::new(30).analyse_until(&Kalaha::new(), &until), Err(AIError::Stopped));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    stopper.join().unwrap();
+}
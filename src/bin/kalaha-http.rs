@@ -0,0 +1,29 @@
+// Serves the HTTP/JSON API, see src/http/mod.rs
+extern crate clap;
+extern crate kalaha;
+
+use std::process;
+
+use clap::{Arg, App};
+use kalaha::http::HttpServer;
+
+fn main() {
+    let matches = App::new("Kalaha HTTP API")
+        .arg(Arg::with_name("addr")
+            .long("addr")
+            .value_name("address")
+            .help("address to listen on (default 127.0.0.1:8080)"))
+        .get_matches();
+
+    let addr = matches.value_of("addr").unwrap_or("127.0.0.1:8080");
+    let server = HttpServer::bind(addr).unwrap_or_else(|error| {
+        eprintln!("Couldn't listen on {}: {}", addr, error);
+        process::exit(1);
+    });
+    // tests ask for port 0 and read the port from here
+    println!("Listening on http://{}", server.local_addr().unwrap());
+    if let Err(error) = server.run() {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,141 @@
+// Plays in a full-screen terminal UI, see src/tui/mod.rs
+extern crate clap;
+extern crate kalaha;
+
+use std::process;
+use std::time::Duration;
+
+use clap::{Arg, App, ArgMatches};
+use kalaha::engine::SearchLimit;
+use kalaha::tui::{self, Seat};
+use kalaha::{Kalaha, Rules, Setup};
+
+fn seat(name: &str, limit: SearchLimit) -> Seat {
+    if name == "human" {
+        return Seat::Human;
+    }
+    if kalaha::ai::by_name(name, 1).is_none() {
+        eprintln!("Unknown AI '{}', use human, first, last, minmax, alphabeta or caching", name);
+        process::exit(1);
+    }
+    Seat::AI(name.to_string(), limit)
+}
+
+// The rules from the same flags as the kalaha binary takes
+fn rules_from_args(matches: &ArgMatches) -> Rules {
+    let mut words = vec![matches.value_of("rules").unwrap_or("kalah").to_string()];
+    for &name in &["capture", "sweep", "sowing"] {
+        if let Some(value) = matches.value_of(name) {
+            words.push(format!("{}={}", name, value));
+        }
+    }
+    for &name in &["relay", "majority", "pie"] {
+        if matches.is_present(name) {
+            words.push(name.to_string());
+        }
+    }
+    words.join(" ").parse().unwrap_or_else(|error: String| {
+        eprintln!("{}", error);
+        process::exit(1);
+    })
+}
+
+// A game under the rules from the arguments, starting from --setup if given
+fn game_from_args(matches: &ArgMatches) -> Kalaha {
+    let rules = rules_from_args(matches);
+    let setup = match matches.value_of("setup") {
+        Some(setup) => setup.parse::<Setup>(),
+        None => return Kalaha::with_rules(rules),
+    };
+    let game = setup.and_then(|setup| if matches.is_present("any_total") {
+        Kalaha::from_setup_any_total(rules, &setup)
+    } else {
+        Kalaha::from_setup(rules, &setup)
+    });
+    game.unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    })
+}
+
+fn main() {
+    let matches = App::new("Kalaha terminal UI")
+        .arg(Arg::with_name("a")
+            .short("a")
+            .value_name("PLAYER")
+            .help("who starts as player A: human or an AI (default human)"))
+        .arg(Arg::with_name("b")
+            .short("b")
+            .value_name("PLAYER")
+            .help("who starts as player B: human or an AI (default alphabeta)"))
+        .arg(Arg::with_name("depth")
+            .short("d")
+            .long("depth")
+            .value_name("DEPTH")
+            .help("how deep the AIs search (default 8)"))
+        .arg(Arg::with_name("movetime")
+            .long("movetime")
+            .value_name("MS")
+            .conflicts_with("depth")
+            .help("how long the AIs think about each move, searching deeper until it's up"))
+        .arg(Arg::with_name("rules")
+            .long("rules")
+            .value_name("RULES")
+            .possible_values(&["kalah", "oware"])
+            .help("rules to play by (default kalah)"))
+        .arg(Arg::with_name("capture")
+            .long("capture")
+            .value_name("POLICY")
+            .possible_values(&["always", "opposite", "never"])
+            .help("when kalah captures happen: always, only if the opposite pond has seeds, or never \
+                   (default always)"))
+        .arg(Arg::with_name("sweep")
+            .long("sweep")
+            .value_name("POLICY")
+            .possible_values(&["own", "ran_out"])
+            .help("who gets the seeds left when a kalah player runs out: the player they belong to, \
+                   or the player who ran out (default own)"))
+        .arg(Arg::with_name("majority")
+            .long("majority")
+            .help("end the game as soon as a player has banked more than half the seeds"))
+        .arg(Arg::with_name("sowing")
+            .long("sowing")
+            .value_name("DIRECTION")
+            .possible_values(&["ccw", "cw", "choice"])
+            .help("which way seeds are sown: counter-clockwise, clockwise, or the player picks each move \
+                   (default ccw)"))
+        .arg(Arg::with_name("relay")
+            .long("relay")
+            .help("keep sowing from the last kalah pond when it wasn't empty"))
+        .arg(Arg::with_name("pie")
+            .long("pie")
+            .help("let player B swap sides after player A's first move"))
+        .arg(Arg::with_name("setup")
+            .long("setup")
+            .value_name("POSITION")
+            .help("start from this position instead, written as A's ponds/A's bank/B's ponds/B's bank/player \
+                   to move, e.g. 6,6,6,6,6,6/0/6,6,6,6,6,6/0/A"))
+        .arg(Arg::with_name("any_total")
+            .long("any_total")
+            .help("allow a --setup with a different number of seeds than the rules start with"))
+        .arg(Arg::with_name("step")
+            .long("step")
+            .value_name("MS")
+            .help("how long each seed takes to sow (default 150)"))
+        .get_matches();
+
+    let game = game_from_args(&matches);
+    let limit = match matches.value_of("movetime") {
+        Some(ms) => SearchLimit::MoveTime(Duration::from_millis(ms.parse().unwrap())),
+        None => SearchLimit::Depth(matches.value_of("depth").map_or(8, |s| s.parse().unwrap())),
+    };
+    let seats = [
+        seat(matches.value_of("a").unwrap_or("human"), limit),
+        seat(matches.value_of("b").unwrap_or("alphabeta"), limit),
+    ];
+    let step = Duration::from_millis(matches.value_of("step").map_or(150, |s| s.parse().unwrap()));
+    if let Err(error) = tui::run(game, seats, step) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
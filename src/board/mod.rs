@@ -3,36 +3,106 @@ use std::ops;
 
 use super::error::Error;
 use super::player::Player;
-use super::pool::{Pool, Bank};
-use super::turn::Turn;
+use super::rules::{Rules, Variant, SweepPolicy, Direction};
+use super::turn::{Turn, Ending, MoveOutcome};
 
+mod oware;
+#[cfg(test)]
+pub mod reference;
 #[cfg(test)]
 mod tests;
 
+const BANK_A: usize = 6;
+const BANK_B: usize = 13;
+
+// Everything needed to take back a move, see Board::make_move.
+// The packed board is small enough that copying it beats replaying the move backwards.
 #[derive(Debug, PartialEq, Clone)]
+pub struct Undo {
+    board: Board,
+}
+
+// How a position was changed to get its canonical form, see Board::canonical
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Transform {
+    Identity,
+    // the sides swapped, so A's ponds and bank became B's and the other way round.
+    // Pond numbers are per player so moves don't change.
+    Mirror,
+}
+
+impl Transform {
+    pub fn player(&self, player: &Player) -> Player {
+        match *self {
+            Transform::Identity => player.clone(),
+            Transform::Mirror => player.next(),
+        }
+    }
+}
+
+// One step of sowing a move, see Board::sowing
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Sow {
+    // every seed is taken out of the pit
+    PickUp(usize),
+    // one seed is dropped into the pit
+    Drop(usize),
+}
+
+// Seed counts for every pool, with ownership derived from the index:
+// 0-5 are player A's ponds, 6 is A's bank, 7-12 are B's ponds and 13 is B's bank.
+// Counts fit in a u8 as long as there are fewer than 256 seeds on the board.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "::serialize::BoardRepr", try_from = "::serialize::BoardRepr"))]
 pub struct Board {
-    pools: [Pool; super::TOTAL_POOLS]
+    pits: [u8; super::TOTAL_POOLS],
+    rules: Rules,
+    // moves since the last capture, only counted under Oware rules
+    quiet_moves: u8,
 }
 
 impl Board {
-    pub fn new() -> Board {
-        let pools = [
-            Pool::new_pond(Player::A),
-            Pool::new_pond(Player::A),
-            Pool::new_pond(Player::A),
-            Pool::new_pond(Player::A),
-            Pool::new_pond(Player::A),
-            Pool::new_pond(Player::A),
-            Pool::new_bank(Player::A),
-            Pool::new_pond(Player::B),
-            Pool::new_pond(Player::B),
-            Pool::new_pond(Player::B),
-            Pool::new_pond(Player::B),
-            Pool::new_pond(Player::B),
-            Pool::new_pond(Player::B),
-            Pool::new_bank(Player::B),
-        ];
-        Board { pools }
+    pub fn with_rules(rules: Rules) -> Board {
+        let mut pits = [rules.seeds_per_pond() as u8; super::TOTAL_POOLS];
+        pits[BANK_A] = 0;
+        pits[BANK_B] = 0;
+        Board { pits, rules, quiet_moves: 0 }
+    }
+
+    // counts in the same order as pits, checked by the caller
+    pub fn from_pits(rules: Rules, pits: [u8; super::TOTAL_POOLS]) -> Board {
+        Board { pits, rules, quiet_moves: 0 }
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    // moves since the last Oware capture
+    pub fn quiet_moves(&self) -> u8 {
+        self.quiet_moves
+    }
+
+    pub fn set_quiet_moves(&mut self, quiet_moves: u8) {
+        self.quiet_moves = quiet_moves;
+    }
+
+    // The same position seen from the other side of the table
+    pub fn mirrored(&self) -> Board {
+        let mut pits = self.pits;
+        pits[..BANK_A + 1].copy_from_slice(&self.pits[BANK_A + 1..]);
+        pits[BANK_A + 1..].copy_from_slice(&self.pits[..BANK_A + 1]);
+        Board { pits, ..*self }
+    }
+
+    // The rules treat both sides alike, so a position and its mirror play the same with
+    // the players swapped. The canonical form has player A to move.
+    pub fn canonical(&self, to_move: &Player) -> (Board, Transform) {
+        match *to_move {
+            Player::A => (*self, Transform::Identity),
+            Player::B => (self.mirrored(), Transform::Mirror),
+        }
     }
 
     fn pool_idx(&self, player: &Player, pond: usize) -> usize {
@@ -49,23 +119,25 @@ impl Board {
 
     fn bank_idx(&self, player: &Player) -> usize {
         match *player {
-            Player::A => 6,
-            Player::B => 13,
+            Player::A => BANK_A,
+            Player::B => BANK_B,
         }
     }
 
-    pub fn bank(&self, player: &Player) -> &Bank {
-        match self.pools[self.bank_idx(player)] {
-            Pool::Bank(ref bank) => bank,
-            _ => panic!("Not a bank")
+    fn owner(&self, idx: usize) -> Player {
+        if idx <= BANK_A {
+            Player::A
+        } else {
+            Player::B
         }
     }
 
-    fn bank_mut(&mut self, player: &Player) -> &mut Bank {
-        match self.pools[self.bank_idx(player)] {
-            Pool::Bank(ref mut bank) => bank,
-            _ => panic!("Not a bank")
-        }
+    fn is_bank(&self, idx: usize) -> bool {
+        idx == BANK_A || idx == BANK_B
+    }
+
+    pub fn bank(&self, player: &Player) -> u32 {
+        u32::from(self.pits[self.bank_idx(player)])
     }
 
     fn pond_idxs(&self, player: &Player) -> ops::Range<usize> {
@@ -75,121 +147,252 @@ impl Board {
         }
     }
 
+    // every pit's count, indexed like pits
+    pub fn counts(&self) -> [u32; super::TOTAL_POOLS] {
+        let mut counts = [0; super::TOTAL_POOLS];
+        for (count, &pit) in counts.iter_mut().zip(self.pits.iter()) {
+            *count = u32::from(pit);
+        }
+        counts
+    }
+
     pub fn pond_counts(&self, player: &Player) -> [u32; 6] {
         let mut res = [0; 6];
         for (i, pond_idx) in self.pond_idxs(player).enumerate() {
-            res[i] = self.pools[pond_idx].count();
+            res[i] = u32::from(self.pits[pond_idx]);
         }
         res
     }
 
-    pub fn valid_move(&self, player: &Player, pond: usize) -> Result<(), Error> {
+    pub fn valid_move(&self, player: &Player, pond: usize, direction: Direction) -> Result<(), Error> {
         if pond >= super::PONDS_PER_PLAYER {
             Err(Error::InvalidIndex)
-        } else if self.pools[self.pool_idx(player, pond)].count() == 0 {
+        } else if !self.rules.directions().contains(&direction) {
+            Err(Error::WrongDirection)
+        } else if self.pits[self.pool_idx(player, pond)] == 0 {
             Err(Error::EmptyPool)
         } else {
-            Ok(())
-        }
-    }
-
-    // panics if pond is not a valid_move
-    pub fn choose(&mut self, player: &Player, pond: usize) -> Turn {
-        self.valid_move(player, pond).expect("Invalid move");
-        let mut idx = self.pool_idx(&player, pond);
-        let mut count = self.pools[idx].take();
-        while count > 0 {
-            idx = (idx + 1) % super::TOTAL_POOLS;
-            match self.pools[idx] {
-                Pool::Pond(ref mut pond) => pond.count += 1,
-                Pool::Bank(ref mut bank) => if bank.player == *player {
-                    bank.count += 1;
-                } else {
-                    continue;
-                }
-            };
-            count -= 1;
-        }
-        let capture = match self.pools[idx] {
-            Pool::Pond(ref mut pond) => {
-                // if we ended on our side if the board, in an empty space
-                if pond.player == *player && pond.count == 1 {
-                    pond.take()
-                } else {
-                    0
-                }
-            },
-            Pool::Bank(_) => 0
-        };
-        if capture > 0 {
-            self.bank_mut(player).count += capture + self.pools[self.opposite_idx(idx)].take();
+            match self.rules.variant {
+                Variant::Kalah => Ok(()),
+                Variant::Oware => self.feeds_opponent(player, pond, direction),
+            }
         }
-        let finished = self.handle_finish();
-        self.next_turn(player, idx, finished)
     }
 
-    fn handle_finish(&mut self) -> bool {
-        if self.pools[0..6].iter().fold(0, |count, pool| count + pool.count()) == 0 {
-            let mut add_to_bank = 0;
-            for pool in &mut self.pools[7..13] {
-                add_to_bank += pool.take();
-            };
-            if add_to_bank > 0 {
-                self.bank_mut(&Player::B).count += add_to_bank;
-            }
-            true
-        } else if self.pools[7..13].iter().fold(0, |count, pool| count + pool.count()) == 0 {
-            let mut add_to_bank = 0;
-            for pool in &mut self.pools[0..6] {
-                add_to_bank += pool.take();
+    // Like choose, but also returns what is needed to take the move back with unmake_move
+    // panics if the move is not a valid_move
+    pub fn make_move(&mut self, player: &Player, pond: usize, direction: Direction) -> (MoveOutcome, Undo) {
+        let undo = Undo { board: *self };
+        (self.choose(player, pond, direction), undo)
+    }
+
+    // panics if the move is not a valid_move
+    pub fn choose(&mut self, player: &Player, pond: usize, direction: Direction) -> MoveOutcome {
+        self.valid_move(player, pond, direction).expect("Invalid move");
+        match self.rules.variant {
+            Variant::Kalah => self.choose_kalah(player, pond, direction),
+            Variant::Oware => self.choose_oware(player, pond, direction),
+        }
+    }
+
+    // The pits a move picks seeds up from and drops them into, in order, for showing it seed
+    // by seed. Captures and the end of the game aren't included.
+    // panics if the move is not a valid_move
+    pub fn sowing(&self, player: &Player, pond: usize, direction: Direction) -> Vec<Sow> {
+        self.valid_move(player, pond, direction).expect("Invalid move");
+        match self.rules.variant {
+            Variant::Kalah => self.sowing_kalah(player, pond, direction),
+            Variant::Oware => self.sowing_oware(player, pond, direction),
+        }
+    }
+
+    // sows as choose_kalah does, on a copy
+    fn sowing_kalah(&self, player: &Player, pond: usize, direction: Direction) -> Vec<Sow> {
+        let (start, skip) = (self.pool_idx(player, pond), self.bank_idx(&player.next()));
+        let mut pits = self.pits;
+        let mut steps = Vec::new();
+        sow_kalah(&mut pits, start, direction, self.rules.max_laps,
+                  |idx| idx == BANK_A || idx == BANK_B, |idx| idx == skip, |sow| steps.push(sow));
+        steps
+    }
+
+    fn choose_kalah(&mut self, player: &Player, pond: usize, direction: Direction) -> MoveOutcome {
+        let (start, skip) = (self.pool_idx(player, pond), self.bank_idx(&player.next()));
+        let (idx, laps) = sow_kalah(&mut self.pits, start, direction, self.rules.max_laps,
+                                    |idx| idx == BANK_A || idx == BANK_B, |idx| idx == skip, |_| ());
+        let mut captured = 0;
+        // if we ended on our side if the board, in an empty space
+        if !self.is_bank(idx) && self.owner(idx) == *player && self.pits[idx] == 1
+            && self.rules.capture.captures(u32::from(self.pits[self.opposite_idx(idx)])) {
+            let opposite = self.opposite_idx(idx);
+            let bank = self.bank_idx(player);
+            captured = 1 + self.pits[opposite];
+            self.pits[bank] += captured;
+            self.pits[idx] = 0;
+            self.pits[opposite] = 0;
+        }
+        let ending = self.handle_finish();
+        MoveOutcome {
+            extra_turn: ending.is_none() && self.is_bank(idx),
+            turn: self.next_turn(player, idx, ending),
+            captured: u32::from(captured),
+            laps,
+        }
+    }
+
+    // How the game ends here with to_move to move, if it does, checked as choose checks
+    // after every move
+    pub fn ending(&self, to_move: &Player) -> Option<Ending> {
+        let mut board = *self;
+        match self.rules.variant {
+            Variant::Kalah => board.handle_finish(),
+            Variant::Oware => board.handle_finish_oware(to_move),
+        }
+    }
+
+    // Reverts the move that produced undo. Moves must be unmade in reverse order.
+    pub fn unmake_move(&mut self, undo: Undo) {
+        *self = undo.board;
+    }
+
+    fn handle_finish(&mut self) -> Option<Ending> {
+        let ran_out = if self.pits[0..6].iter().all(|&count| count == 0) {
+            Some(Player::A)
+        } else if self.pits[7..13].iter().all(|&count| count == 0) {
+            Some(Player::B)
+        } else {
+            None
+        };
+        if let Some(ran_out) = ran_out {
+            let to = match self.rules.sweep {
+                SweepPolicy::OwnSide => ran_out.next(),
+                SweepPolicy::PlayerWhoRanOut => ran_out.clone(),
             };
-            if add_to_bank > 0 {
-                self.bank_mut(&Player::A).count += add_to_bank;
-            }
-            true
+            self.sweep_into(&ran_out.next(), &to);
+            Some(Ending::SideEmpty)
+        } else if self.rules.end_on_majority && self.has_majority() {
+            Some(Ending::Majority)
         } else {
-            false
+            None
         }
     }
 
-    fn next_turn(&self, current_player: &Player, last_idx: usize, finished: bool) -> Turn {
-        if finished {
-            let a_count = self.bank(&Player::A).count;
-            let b_count = self.bank(&Player::B).count;
-            Turn::new_finished(a_count, b_count)
+    fn has_majority(&self) -> bool {
+        let total: u32 = self.pits.iter().map(|&count| u32::from(count)).sum();
+        self.bank(&Player::A) * 2 > total || self.bank(&Player::B) * 2 > total
+    }
+
+    fn sweep(&mut self, player: &Player) {
+        self.sweep_into(player, player);
+    }
+
+    // moves everything in side's ponds into to's bank
+    fn sweep_into(&mut self, side: &Player, to: &Player) {
+        let mut add_to_bank = 0;
+        for idx in self.pond_idxs(side) {
+            add_to_bank += self.pits[idx];
+            self.pits[idx] = 0;
+        }
+        let bank = self.bank_idx(to);
+        self.pits[bank] += add_to_bank;
+    }
+
+    fn finished(&self, ending: Ending) -> Turn {
+        Turn::new_finished(self.bank(&Player::A), self.bank(&Player::B), ending)
+    }
+
+    fn next_turn(&self, current_player: &Player, last_idx: usize, ending: Option<Ending>) -> Turn {
+        if let Some(ending) = ending {
+            self.finished(ending)
+        } else if self.is_bank(last_idx) {
+            Turn::Player(current_player.clone())
         } else {
-            let next_player = match self.pools[last_idx] {
-                Pool::Pond(_) => current_player.next(),
-                Pool::Bank(_) => current_player.clone(),
+            Turn::Player(current_player.next())
+        }
+    }
+}
+
+// Kalah sowing of the seeds in pits[idx] round a ring of pits, leaving out the ones skip
+// picks. While the last seed lands in a pond that wasn't empty, relay sowing picks it up and
+// carries on, for at most max_laps laps in all. Each pick-up and drop is passed to step as
+// it happens, which is how Board::sowing shows a move. Returns the pit the last seed landed
+// in and the laps sown. MultiKalaha sows its longer ring with this too.
+pub fn sow_kalah<T>(pits: &mut [T], mut idx: usize, direction: Direction, max_laps: u32,
+                    is_bank: impl Fn(usize) -> bool, skip: impl Fn(usize) -> bool, mut step: impl FnMut(Sow))
+                    -> (usize, u32)
+    where T: Copy + PartialEq + From<u8> + ops::AddAssign + ops::SubAssign
+{
+    let (empty, seed) = (T::from(0), T::from(1));
+    let len = pits.len();
+    let mut count = pits[idx];
+    pits[idx] = empty;
+    step(Sow::PickUp(idx));
+    let mut laps = 0;
+    loop {
+        laps += 1;
+        while count != empty {
+            idx = match direction {
+                Direction::CounterClockwise => (idx + 1) % len,
+                Direction::Clockwise => (idx + len - 1) % len,
             };
-            Turn::Player(next_player)
+            if !skip(idx) {
+                pits[idx] += seed;
+                count -= seed;
+                step(Sow::Drop(idx));
+            }
+        }
+        // relay sowing carries on from a pond that wasn't empty
+        if laps >= max_laps || is_bank(idx) || pits[idx] == seed {
+            return (idx, laps);
         }
+        count = pits[idx];
+        pits[idx] = empty;
+        step(Sow::PickUp(idx));
+    }
+}
+
+// A row of pond numbers, with an arrow at the end(s) the seeds are sown towards
+fn pond_labels(ponds: &[usize], left: bool, right: bool) -> String {
+    let mut line = String::from(if left { "<" } else { " " });
+    line.push(' ');
+    for pond in ponds {
+        line.push_str(&format!(" {:2}", pond));
+    }
+    if right {
+        line.push_str("  >");
     }
+    line.trim_end().to_string() + "\n"
 }
 
 impl fmt::Display for Board {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let directions = self.rules.directions();
+        // on screen counter-clockwise is right to left along B's ponds and left to right along A's
+        let ccw = directions.contains(&Direction::CounterClockwise);
+        let cw = directions.contains(&Direction::Clockwise);
         f.write_str("        Player B\n")?;
+        f.write_str(&pond_labels(&[5, 4, 3, 2, 1, 0], ccw, cw))?;
         f.write_str(&format!("   {:2} {:2} {:2} {:2} {:2} {:2}\n",
-                             self.pools[12].count(),
-                             self.pools[11].count(),
-                             self.pools[10].count(),
-                             self.pools[9].count(),
-                             self.pools[8].count(),
-                             self.pools[7].count(),
+                             self.pits[12],
+                             self.pits[11],
+                             self.pits[10],
+                             self.pits[9],
+                             self.pits[8],
+                             self.pits[7],
         ))?;
         f.write_str(&format!("{:2}                   {:2}\n",
-                             self.pools[13].count(),
-                             self.pools[6].count(),
+                             self.pits[13],
+                             self.pits[6],
         ))?;
         f.write_str(&format!("   {:2} {:2} {:2} {:2} {:2} {:2}\n",
-                             self.pools[0].count(),
-                             self.pools[1].count(),
-                             self.pools[2].count(),
-                             self.pools[3].count(),
-                             self.pools[4].count(),
-                             self.pools[5].count(),
+                             self.pits[0],
+                             self.pits[1],
+                             self.pits[2],
+                             self.pits[3],
+                             self.pits[4],
+                             self.pits[5],
         ))?;
+        f.write_str(&pond_labels(&[0, 1, 2, 3, 4, 5], cw, ccw))?;
         f.write_str("        Player A\n")
     }
 }
@@ -0,0 +1,157 @@
+// Oware (Abapa rules) on the same board as Kalah. The banks are only used to hold
+// captured seeds and are skipped when sowing.
+use error::Error;
+use player::Player;
+use rules::Direction;
+use turn::{Turn, Ending, MoveOutcome};
+
+use super::{Board, Sow, BANK_A, BANK_B};
+
+#[cfg(test)]
+mod tests;
+
+// After this many moves without a capture the seeds are probably just going around in
+// circles, so the game ends and each player takes the seeds on their own side
+const QUIET_MOVE_LIMIT: u8 = 100;
+
+impl Board {
+    // If the opponent has no seeds, every move must give them some
+    pub(super) fn feeds_opponent(&self, player: &Player, pond: usize, direction: Direction) -> Result<(), Error> {
+        if self.side_count(&player.next()) > 0 {
+            return Ok(());
+        }
+        // the banks are skipped, so it takes 6 - pond seeds to reach the other side going
+        // counter-clockwise, or pond + 1 going clockwise
+        let seeds = self.pits[self.pool_idx(player, pond)] as usize;
+        let needed = match direction {
+            Direction::CounterClockwise => ::PONDS_PER_PLAYER - pond,
+            Direction::Clockwise => pond + 1,
+        };
+        if seeds >= needed {
+            Ok(())
+        } else {
+            Err(Error::MustFeed)
+        }
+    }
+
+    pub(super) fn choose_oware(&mut self, player: &Player, pond: usize, direction: Direction) -> MoveOutcome {
+        let start = self.pool_idx(player, pond);
+        let idx = sow_oware(&mut self.pits, start, direction, |_| ());
+        let captured = self.capture_oware(player, idx, direction);
+        if captured > 0 {
+            self.quiet_moves = 0;
+        } else {
+            self.quiet_moves = self.quiet_moves.saturating_add(1);
+        }
+        let turn = match self.handle_finish_oware(&player.next()) {
+            Some(ending) => self.finished(ending),
+            None => Turn::Player(player.next()),
+        };
+        MoveOutcome { turn, extra_turn: false, captured: u32::from(captured), laps: 1 }
+    }
+
+    // sows as choose_oware does, on a copy
+    pub(super) fn sowing_oware(&self, player: &Player, pond: usize, direction: Direction) -> Vec<Sow> {
+        let mut pits = self.pits;
+        let mut steps = Vec::new();
+        sow_oware(&mut pits, self.pool_idx(player, pond), direction, |sow| steps.push(sow));
+        steps
+    }
+
+    // Landing on the opponent's side to make 2 or 3 captures that pond, and the ponds
+    // sown before it for as long as they also hold 2 or 3. Capturing every seed the
+    // opponent has (a grand slam) is allowed but captures nothing.
+    fn capture_oware(&mut self, player: &Player, last_idx: usize, direction: Direction) -> u8 {
+        let opponent_ponds = self.pond_idxs(&player.next());
+        if !opponent_ponds.contains(&last_idx) {
+            return 0;
+        }
+        let is_capture = |count: u8| count == 2 || count == 3;
+        if !is_capture(self.pits[last_idx]) {
+            return 0;
+        }
+        // the range of ponds captured, working back against the direction of sowing
+        let (mut first, mut last) = (last_idx, last_idx + 1);
+        match direction {
+            Direction::CounterClockwise => {
+                while first > opponent_ponds.start && is_capture(self.pits[first - 1]) {
+                    first -= 1;
+                }
+            }
+            Direction::Clockwise => {
+                while last < opponent_ponds.end && is_capture(self.pits[last]) {
+                    last += 1;
+                }
+            }
+        }
+        let captured: u8 = self.pits[first..last].iter().sum();
+        if u32::from(captured) == self.side_count(&player.next()) {
+            return 0;
+        }
+        for idx in first..last {
+            self.pits[idx] = 0;
+        }
+        let bank = self.bank_idx(player);
+        self.pits[bank] += captured;
+        captured
+    }
+
+    pub(super) fn handle_finish_oware(&mut self, next_player: &Player) -> Option<Ending> {
+        if self.has_majority() {
+            return Some(Ending::Majority);
+        }
+        let directions = self.rules.directions();
+        let no_moves = (0..::PONDS_PER_PLAYER)
+            .all(|pond| directions.iter().all(|&direction| self.valid_move(next_player, pond, direction).is_err()));
+        let ending = if no_moves {
+            // covers not being able to feed the opponent, where all the seeds left are
+            // on the side of the player to move
+            Ending::NoMoves
+        } else if self.quiet_moves >= QUIET_MOVE_LIMIT {
+            Ending::NoCaptures
+        } else {
+            return None;
+        };
+        self.sweep(&Player::A);
+        self.sweep(&Player::B);
+        Some(ending)
+    }
+
+    fn side_count(&self, player: &Player) -> u32 {
+        self.pits[self.pond_idxs(player)].iter().map(|&count| u32::from(count)).sum()
+    }
+}
+
+// Oware sowing of the seeds in pits[start] round the ponds, passing each pick-up and drop
+// to step. Returns the pond the last seed landed in.
+fn sow_oware(pits: &mut [u8], start: usize, direction: Direction, mut step: impl FnMut(Sow)) -> usize {
+    let mut count = pits[start];
+    pits[start] = 0;
+    step(Sow::PickUp(start));
+    let mut idx = start;
+    while count > 0 {
+        idx = next_pond(idx, direction);
+        // a lap of 12 or more seeds skips the pond it started from
+        if idx != start {
+            pits[idx] += 1;
+            count -= 1;
+            step(Sow::Drop(idx));
+        }
+    }
+    idx
+}
+
+fn next_pond(idx: usize, direction: Direction) -> usize {
+    match direction {
+        Direction::CounterClockwise => match idx + 1 {
+            BANK_A => BANK_A + 1,
+            BANK_B => 0,
+            next => next,
+        },
+        Direction::Clockwise => match idx.checked_sub(1) {
+            Some(BANK_A) => BANK_A - 1,
+            Some(prev) => prev,
+            None => BANK_B - 1,
+        },
+    }
+}
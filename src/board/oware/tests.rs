@@ -0,0 +1,141 @@
+use super::*;
+use rules::{Rules, SowingPolicy};
+use turn::GameResult;
+
+const CCW: Direction = Direction::CounterClockwise;
+const CW: Direction = Direction::Clockwise;
+
+fn board_from_counts(counts: &[u32]) -> Board {
+    let mut board = Board::with_rules(Rules::oware());
+    for (pit, &count) in board.pits.iter_mut().zip(counts) {
+        *pit = count as u8;
+    }
+    board
+}
+
+fn assert_counts(board: &Board, counts: &[u8]) {
+    assert_eq!(&board.pits[..], counts);
+}
+
+#[test]
+fn new_board() {
+    let board = Board::with_rules(Rules::oware());
+    assert_counts(&board, &[4,4,4,4,4,4,0,4,4,4,4,4,4,0]);
+}
+
+#[test]
+fn choose_normal() {
+    let mut board = Board::with_rules(Rules::oware());
+    let outcome = board.choose(&Player::A, 2, CCW);
+    assert_eq!(outcome.turn, Turn::Player(Player::B));
+    assert!(!outcome.extra_turn);
+    assert_counts(&board, &[4,4,0,5,5,5,0,5,4,4,4,4,4,0]);
+}
+
+#[test]
+fn choose_skips_banks() {
+    let mut board = Board::with_rules(Rules::oware());
+    assert_eq!(board.choose(&Player::B, 5, CCW).turn, Turn::Player(Player::A));
+    assert_counts(&board, &[5,5,5,5,4,4,0,4,4,4,4,4,0,0]);
+}
+
+#[test]
+fn choose_lap_skips_start() {
+    let mut board = board_from_counts(&[12,1,1,1,1,1,0,1,1,1,1,1,1,0]);
+    board.choose(&Player::A, 0, CCW);
+    assert_counts(&board, &[0,3,2,2,2,2,0,2,2,2,2,2,2,0]);
+}
+
+#[test]
+fn choose_capture_chain() {
+    let mut board = board_from_counts(&[1,1,1,1,1,3,0,1,2,1,4,4,5,0]);
+    let outcome = board.choose(&Player::A, 5, CCW);
+    assert_eq!(outcome.captured, 7);
+    assert_eq!(outcome.turn, Turn::Player(Player::B));
+    assert_counts(&board, &[1,1,1,1,1,0,7,0,0,0,4,4,5,0]);
+}
+
+#[test]
+fn choose_capture_chain_stops() {
+    let mut board = board_from_counts(&[1,1,1,1,1,3,0,4,2,1,4,4,5,0]);
+    assert_eq!(board.choose(&Player::A, 5, CCW).captured, 5);
+    assert_counts(&board, &[1,1,1,1,1,0,5,5,0,0,4,4,5,0]);
+}
+
+#[test]
+fn choose_no_capture_on_own_side() {
+    let mut board = board_from_counts(&[1,1,1,1,0,1,0,4,4,4,4,4,4,0]);
+    assert_eq!(board.choose(&Player::A, 3, CCW).captured, 0);
+    assert_counts(&board, &[1,1,1,0,1,1,0,4,4,4,4,4,4,0]);
+}
+
+#[test]
+fn choose_grand_slam_captures_nothing() {
+    let mut board = board_from_counts(&[1,1,1,1,1,3,0,1,1,1,0,0,0,0]);
+    assert_eq!(board.choose(&Player::A, 5, CCW).captured, 0);
+    assert_counts(&board, &[1,1,1,1,1,0,0,2,2,2,0,0,0,0]);
+}
+
+#[test]
+fn valid_move_must_feed() {
+    let board = board_from_counts(&[1,0,0,0,0,1,20,0,0,0,0,0,0,26]);
+    assert_eq!(board.valid_move(&Player::A, 0, CCW), Err(Error::MustFeed));
+    assert_eq!(board.valid_move(&Player::A, 5, CCW), Ok(()));
+}
+
+#[test]
+fn finish_cannot_feed() {
+    let mut board = board_from_counts(&[2,0,0,0,0,0,22,0,0,0,0,0,1,23]);
+    // 3 seeds can't make it round to B, and capturing them would be a grand slam
+    assert_eq!(
+        board.choose(&Player::B, 5, CCW).turn,
+        Turn::Finished(GameResult::Winner { player: Player::A, score_a: 25, score_b: 23, ending: Ending::NoMoves })
+    );
+    assert_counts(&board, &[0,0,0,0,0,0,25,0,0,0,0,0,0,23]);
+}
+
+#[test]
+fn finish_majority() {
+    let mut board = board_from_counts(&[1,1,1,1,1,1,23,1,1,1,4,4,4,4]);
+    let turn = board.choose(&Player::A, 5, CCW).turn;
+    assert_eq!(
+        turn,
+        Turn::Finished(GameResult::Winner { player: Player::A, score_a: 25, score_b: 4, ending: Ending::Majority })
+    );
+}
+
+#[test]
+fn finish_quiet_moves() {
+    let mut board = board_from_counts(&[1,1,1,1,1,1,20,4,4,4,4,4,0,0]);
+    board.quiet_moves = QUIET_MOVE_LIMIT - 1;
+    assert_eq!(
+        board.choose(&Player::A, 0, CCW).turn,
+        Turn::Finished(GameResult::Winner { player: Player::A, score_a: 26, score_b: 20, ending: Ending::NoCaptures })
+    );
+}
+
+#[test]
+fn next_pond_clockwise() {
+    assert_eq!(next_pond(0, CW), 12);
+    assert_eq!(next_pond(7, CW), 5);
+    assert_eq!(next_pond(12, CW), 11);
+    assert_eq!(next_pond(5, CCW), 7);
+    assert_eq!(next_pond(12, CCW), 0);
+}
+
+#[test]
+fn must_feed_clockwise() {
+    let mut board = board_from_counts(&[1,0,0,0,0,0,0,0,0,0,0,0,0,0]);
+    board.rules = board.rules.with_sowing(SowingPolicy::PlayerChoice);
+    assert_eq!(board.valid_move(&Player::A, 0, CCW), Err(Error::MustFeed));
+    assert_eq!(board.valid_move(&Player::A, 0, CW), Ok(()));
+}
+
+#[test]
+fn capture_clockwise() {
+    let mut board = board_from_counts(&[2,4,0,0,0,0,0,4,0,0,0,2,1,0]);
+    board.rules = board.rules.with_sowing(SowingPolicy::Fixed(CW));
+    // lands on B's pond 4 and works back towards B's pond 5
+    assert_eq!(board.choose(&Player::A, 0, CW).captured, 5);
+    assert_counts(&board, &[0,4,0,0,0,0,5,4,0,0,0,0,0,0]);
+}
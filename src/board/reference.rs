@@ -0,0 +1,259 @@
+// The original board representation, where every pool knows its owner. It is
+// slower than the packed Board but easier to check, so tests compare the two.
+use std::fmt;
+use std::ops;
+
+use error::Error;
+use player::Player;
+use pool::{Pool, Bank};
+use turn::{Turn, Ending};
+
+// Everything needed to take back a move, see Board::make_move
+#[derive(Debug, PartialEq, Clone)]
+pub struct Undo {
+    player: Player,
+    start: usize,
+    seeds: u32,
+    // index the last seed landed in and the seeds taken from the opposite pond
+    captured: Option<(usize, u32)>,
+    sweep: Option<(Player, [u32; 6])>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Board {
+    pools: [Pool; ::TOTAL_POOLS]
+}
+
+impl Board {
+    pub fn new() -> Board {
+        let pools = [
+            Pool::new_pond(Player::A),
+            Pool::new_pond(Player::A),
+            Pool::new_pond(Player::A),
+            Pool::new_pond(Player::A),
+            Pool::new_pond(Player::A),
+            Pool::new_pond(Player::A),
+            Pool::new_bank(Player::A),
+            Pool::new_pond(Player::B),
+            Pool::new_pond(Player::B),
+            Pool::new_pond(Player::B),
+            Pool::new_pond(Player::B),
+            Pool::new_pond(Player::B),
+            Pool::new_pond(Player::B),
+            Pool::new_bank(Player::B),
+        ];
+        Board { pools }
+    }
+
+    // counts in the packed board's order, A's ponds and bank then B's
+    pub fn from_counts(counts: &[u32; ::TOTAL_POOLS]) -> Board {
+        let mut board = Board::new();
+        for (pool, &count) in board.pools.iter_mut().zip(counts) {
+            *pool.count_mut() = count;
+        }
+        board
+    }
+
+    fn pool_idx(&self, player: &Player, pond: usize) -> usize {
+        match *player {
+            Player::A => pond,
+            Player::B => pond + 7,
+        }
+    }
+
+    fn opposite_idx(&self, pond: usize) -> usize {
+        // trust me, it is
+        12 - pond
+    }
+
+    fn bank_idx(&self, player: &Player) -> usize {
+        match *player {
+            Player::A => 6,
+            Player::B => 13,
+        }
+    }
+
+    pub fn bank(&self, player: &Player) -> &Bank {
+        match self.pools[self.bank_idx(player)] {
+            Pool::Bank(ref bank) => bank,
+            _ => panic!("Not a bank")
+        }
+    }
+
+    fn bank_mut(&mut self, player: &Player) -> &mut Bank {
+        match self.pools[self.bank_idx(player)] {
+            Pool::Bank(ref mut bank) => bank,
+            _ => panic!("Not a bank")
+        }
+    }
+
+    fn pond_idxs(&self, player: &Player) -> ops::Range<usize> {
+        match *player {
+            Player::A => 0..6,
+            Player::B => 7..13,
+        }
+    }
+
+    pub fn pond_counts(&self, player: &Player) -> [u32; 6] {
+        let mut res = [0; 6];
+        for (i, pond_idx) in self.pond_idxs(player).enumerate() {
+            res[i] = self.pools[pond_idx].count();
+        }
+        res
+    }
+
+    pub fn valid_move(&self, player: &Player, pond: usize) -> Result<(), Error> {
+        if pond >= ::PONDS_PER_PLAYER {
+            Err(Error::InvalidIndex)
+        } else if self.pools[self.pool_idx(player, pond)].count() == 0 {
+            Err(Error::EmptyPool)
+        } else {
+            Ok(())
+        }
+    }
+
+    // panics if pond is not a valid_move
+    pub fn choose(&mut self, player: &Player, pond: usize) -> Turn {
+        self.make_move(player, pond).0
+    }
+
+    // Like choose, but also returns what is needed to take the move back with unmake_move
+    // panics if pond is not a valid_move
+    pub fn make_move(&mut self, player: &Player, pond: usize) -> (Turn, Undo) {
+        self.valid_move(player, pond).expect("Invalid move");
+        let start = self.pool_idx(player, pond);
+        let seeds = self.pools[start].take();
+        let mut idx = start;
+        let mut count = seeds;
+        while count > 0 {
+            idx = (idx + 1) % ::TOTAL_POOLS;
+            match self.pools[idx] {
+                Pool::Pond(ref mut pond) => pond.count += 1,
+                Pool::Bank(ref mut bank) => if bank.player == *player {
+                    bank.count += 1;
+                } else {
+                    continue;
+                }
+            };
+            count -= 1;
+        }
+        let capture = match self.pools[idx] {
+            Pool::Pond(ref mut pond) => {
+                // if we ended on our side if the board, in an empty space
+                if pond.player == *player && pond.count == 1 {
+                    pond.take()
+                } else {
+                    0
+                }
+            },
+            Pool::Bank(_) => 0
+        };
+        let captured = if capture > 0 {
+            let opposite = self.pools[self.opposite_idx(idx)].take();
+            self.bank_mut(player).count += capture + opposite;
+            Some((idx, opposite))
+        } else {
+            None
+        };
+        let sweep = self.handle_finish();
+        let turn = self.next_turn(player, idx, sweep.is_some());
+        (turn, Undo { player: player.clone(), start, seeds, captured, sweep })
+    }
+
+    // Reverts the move that produced undo. Moves must be unmade in reverse order.
+    pub fn unmake_move(&mut self, undo: Undo) {
+        if let Some((ref player, ref counts)) = undo.sweep {
+            let mut swept = 0;
+            let idxs = self.pond_idxs(player);
+            for (pool, &count) in self.pools[idxs].iter_mut().zip(counts) {
+                *pool.count_mut() = count;
+                swept += count;
+            }
+            self.bank_mut(player).count -= swept;
+        }
+        if let Some((idx, opposite)) = undo.captured {
+            *self.pools[idx].count_mut() = 1;
+            *self.pools[self.opposite_idx(idx)].count_mut() = opposite;
+            self.bank_mut(&undo.player).count -= opposite + 1;
+        }
+        let mut idx = undo.start;
+        let mut count = undo.seeds;
+        while count > 0 {
+            idx = (idx + 1) % ::TOTAL_POOLS;
+            if let Pool::Bank(ref bank) = self.pools[idx] {
+                if bank.player != undo.player {
+                    continue;
+                }
+            }
+            *self.pools[idx].count_mut() -= 1;
+            count -= 1;
+        }
+        *self.pools[undo.start].count_mut() = undo.seeds;
+    }
+
+    // Returns None if the game isn't finished, otherwise which side was swept into its bank
+    // along with the pond counts before sweeping.
+    fn handle_finish(&mut self) -> Option<(Player, [u32; 6])> {
+        if self.pools[0..6].iter().fold(0, |count, pool| count + pool.count()) == 0 {
+            Some(self.sweep(&Player::B))
+        } else if self.pools[7..13].iter().fold(0, |count, pool| count + pool.count()) == 0 {
+            Some(self.sweep(&Player::A))
+        } else {
+            None
+        }
+    }
+
+    fn sweep(&mut self, player: &Player) -> (Player, [u32; 6]) {
+        let counts = self.pond_counts(player);
+        let mut add_to_bank = 0;
+        let idxs = self.pond_idxs(player);
+        for pool in &mut self.pools[idxs] {
+            add_to_bank += pool.take();
+        };
+        if add_to_bank > 0 {
+            self.bank_mut(player).count += add_to_bank;
+        }
+        (player.clone(), counts)
+    }
+
+    fn next_turn(&self, current_player: &Player, last_idx: usize, finished: bool) -> Turn {
+        if finished {
+            let a_count = self.bank(&Player::A).count;
+            let b_count = self.bank(&Player::B).count;
+            Turn::new_finished(a_count, b_count, Ending::SideEmpty)
+        } else {
+            let next_player = match self.pools[last_idx] {
+                Pool::Pond(_) => current_player.next(),
+                Pool::Bank(_) => current_player.clone(),
+            };
+            Turn::Player(next_player)
+        }
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.write_str("        Player B\n")?;
+        f.write_str(&format!("   {:2} {:2} {:2} {:2} {:2} {:2}\n",
+                             self.pools[12].count(),
+                             self.pools[11].count(),
+                             self.pools[10].count(),
+                             self.pools[9].count(),
+                             self.pools[8].count(),
+                             self.pools[7].count(),
+        ))?;
+        f.write_str(&format!("{:2}                   {:2}\n",
+                             self.pools[13].count(),
+                             self.pools[6].count(),
+        ))?;
+        f.write_str(&format!("   {:2} {:2} {:2} {:2} {:2} {:2}\n",
+                             self.pools[0].count(),
+                             self.pools[1].count(),
+                             self.pools[2].count(),
+                             self.pools[3].count(),
+                             self.pools[4].count(),
+                             self.pools[5].count(),
+        ))?;
+        f.write_str("        Player A\n")
+    }
+}
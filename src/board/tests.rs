@@ -1,62 +1,54 @@
 use super::*;
-use pool::{Pond, Bank};
-use turn::GameResult;
+use super::reference;
+use rng::Rng;
+use turn::{GameResult, Ending};
+use rules::{CapturePolicy, SowingPolicy};
+
+const CCW: Direction = Direction::CounterClockwise;
+const CW: Direction = Direction::Clockwise;
 
 fn board_from_counts(counts: &[u32]) -> Board {
-    Board {
-        pools: [
-            Pool::Pond(Pond { player: Player::A, count: counts[0] }),
-            Pool::Pond(Pond { player: Player::A, count: counts[1] }),
-            Pool::Pond(Pond { player: Player::A, count: counts[2] }),
-            Pool::Pond(Pond { player: Player::A, count: counts[3] }),
-            Pool::Pond(Pond { player: Player::A, count: counts[4] }),
-            Pool::Pond(Pond { player: Player::A, count: counts[5] }),
-            Pool::Bank(Bank { player: Player::A, count: counts[6] }),
-            Pool::Pond(Pond { player: Player::B, count: counts[7] }),
-            Pool::Pond(Pond { player: Player::B, count: counts[8] }),
-            Pool::Pond(Pond { player: Player::B, count: counts[9] }),
-            Pool::Pond(Pond { player: Player::B, count: counts[10] }),
-            Pool::Pond(Pond { player: Player::B, count: counts[11] }),
-            Pool::Pond(Pond { player: Player::B, count: counts[12] }),
-            Pool::Bank(Bank { player: Player::B, count: counts[13] }),
-        ]
+    board_with_rules(counts, Rules::kalah())
+}
+
+fn board_with_rules(counts: &[u32], rules: Rules) -> Board {
+    let mut pits = [0; ::TOTAL_POOLS];
+    for (pit, &count) in pits.iter_mut().zip(counts) {
+        *pit = count as u8;
     }
+    Board { pits, rules, quiet_moves: 0 }
 }
 
 #[test]
 fn new_banks_start_empty() {
-    let board = Board::new();
-    assert_eq!(board.pools[board.bank_idx(&Player::A)].count(), 0);
-    assert_eq!(board.pools[board.bank_idx(&Player::B)].count(), 0);
+    let board = Board::with_rules(Rules::kalah());
+    assert_eq!(board.bank(&Player::A), 0);
+    assert_eq!(board.bank(&Player::B), 0);
 }
 
 #[test]
 fn new_pools_start_full() {
-    let board = Board::new();
-    for ref pool in board.pools[0..6].iter() {
-        assert_eq!(pool.count(), ::INIT_COUNT);
-    }
-    for ref pool in board.pools[7..13].iter() {
-        assert_eq!(pool.count(), ::INIT_COUNT);
-    }
+    let board = Board::with_rules(Rules::kalah());
+    assert_eq!(board.pond_counts(&Player::A), [::INIT_COUNT; 6]);
+    assert_eq!(board.pond_counts(&Player::B), [::INIT_COUNT; 6]);
 }
 
 #[test]
 fn valid_move_too_big() {
-    assert_eq!(Board::new().valid_move(&Player::A, 6), Err(Error::InvalidIndex));
+    assert_eq!(Board::with_rules(Rules::kalah()).valid_move(&Player::A, 6, CCW), Err(Error::InvalidIndex));
 }
 
 #[test]
 fn valid_move_pond_empty() {
     let board = board_from_counts(&[0,0,0,0,0,3,18,6,2,0,1,0,0,42]);
-    assert_eq!(board.valid_move(&Player::A, 0), Err(Error::EmptyPool));
+    assert_eq!(board.valid_move(&Player::A, 0, CCW), Err(Error::EmptyPool));
 }
 
 #[test]
 fn bank() {
     let board = board_from_counts(&[0,0,0,0,0,3,18,6,2,0,1,0,0,42]);
-    assert_eq!(board.bank(&Player::A).count, 18);
-    assert_eq!(board.bank(&Player::B).count, 42);
+    assert_eq!(board.bank(&Player::A), 18);
+    assert_eq!(board.bank(&Player::B), 42);
 }
 
 #[test]
@@ -68,8 +60,8 @@ fn pond_counts() {
 
 #[test]
 fn choose_normal() {
-    let mut board = Board::new();
-    assert_eq!(board.choose(&Player::A, 1), Turn::Player(Player::B));
+    let mut board = Board::with_rules(Rules::kalah());
+    assert_eq!(board.choose(&Player::A, 1, CCW).turn, Turn::Player(Player::B));
     assert_eq!(
         board,
         board_from_counts(&[6,0,7,7,7,7,1,7,6,6,6,6,6,0])
@@ -78,8 +70,8 @@ fn choose_normal() {
 
 #[test]
 fn choose_go_again() {
-    let mut board = Board::new();
-    assert_eq!(board.choose(&Player::A, 0), Turn::Player(Player::A));
+    let mut board = Board::with_rules(Rules::kalah());
+    assert_eq!(board.choose(&Player::A, 0, CCW).turn, Turn::Player(Player::A));
     assert_eq!(
         board,
         board_from_counts(&[0,7,7,7,7,7,1,6,6,6,6,6,6,0])
@@ -89,19 +81,62 @@ fn choose_go_again() {
 #[test]
 fn choose_capture() {
     let mut board = board_from_counts(&[0,0,0,0,1,8,0,1,1,1,1,1,1,0]);
-    assert_eq!(board.choose(&Player::A, 5), Turn::Player(Player::B));
+    assert_eq!(board.choose(&Player::A, 5, CCW).turn, Turn::Player(Player::B));
     assert_eq!(
         board,
         board_from_counts(&[0,0,0,0,1,0,4,2,2,2,2,2,0,0])
     );
 }
 
+#[test]
+fn choose_capture_opposite_empty() {
+    let mut board = board_from_counts(&[0,2,0,0,1,0,0,1,1,0,1,0,1,0]);
+    assert_eq!(board.choose(&Player::A, 1, CCW).captured, 1);
+    assert_eq!(
+        board,
+        board_from_counts(&[0,0,1,0,1,0,1,1,1,0,1,0,1,0])
+    );
+}
+
+#[test]
+fn choose_capture_require_opposite() {
+    let rules = Rules::kalah().with_capture(CapturePolicy::OppositeNotEmpty);
+    let mut board = board_with_rules(&[0,0,0,0,1,8,0,1,1,1,1,1,1,0], rules);
+    assert_eq!(board.choose(&Player::A, 5, CCW).captured, 3);
+    assert_eq!(
+        board,
+        board_with_rules(&[0,0,0,0,1,0,4,2,2,2,2,2,0,0], rules)
+    );
+}
+
+#[test]
+fn choose_capture_require_opposite_empty() {
+    let rules = Rules::kalah().with_capture(CapturePolicy::OppositeNotEmpty);
+    let mut board = board_with_rules(&[0,2,0,0,1,0,0,1,1,0,1,0,1,0], rules);
+    assert_eq!(board.choose(&Player::A, 1, CCW).captured, 0);
+    assert_eq!(
+        board,
+        board_with_rules(&[0,0,1,1,1,0,0,1,1,0,1,0,1,0], rules)
+    );
+}
+
+#[test]
+fn choose_capture_never() {
+    let rules = Rules::kalah().with_capture(CapturePolicy::Never);
+    let mut board = board_with_rules(&[0,0,0,0,1,8,0,1,1,1,1,1,1,0], rules);
+    assert_eq!(board.choose(&Player::A, 5, CCW).captured, 0);
+    assert_eq!(
+        board,
+        board_with_rules(&[1,0,0,0,1,0,1,2,2,2,2,2,2,0], rules)
+    );
+}
+
 #[test]
 fn choose_finish_a_cleared() {
     let mut board = board_from_counts(&[0,0,0,0,0,3,18,6,2,0,1,0,0,42]);
     assert_eq!(
-        board.choose(&Player::A, 5),
-        Turn::Finished( GameResult::Winner { player: Player::B, score_a: 19, score_b: 53 })
+        board.choose(&Player::A, 5, CCW).turn,
+        Turn::Finished( GameResult::Winner { player: Player::B, score_a: 19, score_b: 53, ending: Ending::SideEmpty })
     );
     assert_eq!(
         board,
@@ -113,8 +148,8 @@ fn choose_finish_a_cleared() {
 fn choose_finish_b_cleared() {
     let mut board = board_from_counts(&[6,2,0,1,0,0,18,0,0,0,0,0,3,42]);
     assert_eq!(
-        board.choose(&Player::B, 5),
-        Turn::Finished( GameResult::Winner { player: Player::B, score_a: 29, score_b: 43 })
+        board.choose(&Player::B, 5, CCW).turn,
+        Turn::Finished( GameResult::Winner { player: Player::B, score_a: 29, score_b: 43, ending: Ending::SideEmpty })
     );
     assert_eq!(
         board,
@@ -122,9 +157,326 @@ fn choose_finish_b_cleared() {
     );
 }
 
+#[test]
+fn choose_finish_sweep_to_player_who_ran_out() {
+    let rules = Rules::kalah().with_sweep(SweepPolicy::PlayerWhoRanOut);
+    let mut board = board_with_rules(&[0,0,0,0,0,3,18,6,2,0,1,0,0,42], rules);
+    assert_eq!(
+        board.choose(&Player::A, 5, CCW).turn,
+        Turn::Finished( GameResult::Winner { player: Player::B, score_a: 30, score_b: 42, ending: Ending::SideEmpty })
+    );
+    assert_eq!(
+        board,
+        board_with_rules(&[0,0,0,0,0,0,30,0,0,0,0,0,0,42], rules)
+    );
+}
+
+#[test]
+fn choose_finish_majority() {
+    let rules = Rules::kalah().with_end_on_majority(true);
+    let mut board = board_with_rules(&[1,0,0,0,1,0,35,3,3,3,3,3,3,17], rules);
+    assert_eq!(
+        board.choose(&Player::A, 4, CCW).turn,
+        Turn::Finished( GameResult::Winner { player: Player::A, score_a: 39, score_b: 17, ending: Ending::Majority })
+    );
+    assert_eq!(
+        board,
+        board_with_rules(&[1,0,0,0,0,0,39,0,3,3,3,3,3,17], rules)
+    );
+}
+
+#[test]
+fn choose_majority_ignored_by_default() {
+    let mut board = board_from_counts(&[1,0,0,0,1,0,35,3,3,3,3,3,3,17]);
+    assert_eq!(board.choose(&Player::A, 4, CCW).turn, Turn::Player(Player::B));
+}
+
 #[test]
 #[should_panic(expected = "Invalid move")]
 fn choose_invalid() {
     let mut board = board_from_counts(&[0,0,0,0,0,3,18,6,2,0,1,0,0,42]);
-    board.choose(&Player::A, 0);
+    board.choose(&Player::A, 0, CCW);
+}
+
+fn assert_unmake_restores(counts: &[u32], player: &Player, pond: usize) {
+    let mut board = board_from_counts(counts);
+    let (_, undo) = board.make_move(player, pond, CCW);
+    assert!(board != board_from_counts(counts));
+    board.unmake_move(undo);
+    assert_eq!(board, board_from_counts(counts));
+}
+
+#[test]
+fn unmake_normal() {
+    assert_unmake_restores(&[6,6,6,6,6,6,0,6,6,6,6,6,6,0], &Player::A, 1);
+}
+
+#[test]
+fn unmake_go_again() {
+    assert_unmake_restores(&[6,6,6,6,6,6,0,6,6,6,6,6,6,0], &Player::A, 0);
+}
+
+#[test]
+fn unmake_capture() {
+    assert_unmake_restores(&[0,0,0,0,1,8,0,1,1,1,1,1,1,0], &Player::A, 5);
+}
+
+#[test]
+fn unmake_lap_skips_opponent_bank() {
+    assert_unmake_restores(&[0,0,0,0,0,15,3,1,1,1,1,1,1,4], &Player::A, 5);
+}
+
+#[test]
+fn unmake_finish_a_cleared() {
+    assert_unmake_restores(&[0,0,0,0,0,3,18,6,2,0,1,0,0,42], &Player::A, 5);
+}
+
+#[test]
+fn unmake_finish_b_cleared() {
+    assert_unmake_restores(&[6,2,0,1,0,0,18,0,0,0,0,0,3,42], &Player::B, 5);
+}
+
+fn assert_same_position(board: &Board, reference: &reference::Board) {
+    for player in &[Player::A, Player::B] {
+        assert_eq!(board.pond_counts(player), reference.pond_counts(player));
+        assert_eq!(board.bank(player), reference.bank(player).count);
+    }
+}
+
+// Plays random games on both representations, trying out and taking back moves
+// now and then, and checks they agree after every step
+#[test]
+fn same_as_reference_random_games() {
+    for seed in 1..500 {
+        let mut rng = Rng::new(seed);
+        let mut board = Board::with_rules(Rules::kalah());
+        let mut expected = reference::Board::new();
+        let mut turn = Turn::Player(Player::A);
+        while !turn.is_finished() {
+            let player = turn.player().clone();
+            let ponds: Vec<usize> = (0..::PONDS_PER_PLAYER)
+                .filter(|&pond| expected.valid_move(&player, pond).is_ok())
+                .collect();
+            for pond in 0..::PONDS_PER_PLAYER {
+                assert_eq!(board.valid_move(&player, pond, CCW), expected.valid_move(&player, pond));
+            }
+            if rng.below(4) == 0 {
+                let pond = ponds[rng.below(ponds.len())];
+                let (tried, undo) = board.make_move(&player, pond, CCW);
+                let (expected_tried, expected_undo) = expected.make_move(&player, pond);
+                assert_eq!(tried.turn, expected_tried);
+                assert_same_position(&board, &expected);
+                board.unmake_move(undo);
+                expected.unmake_move(expected_undo);
+                assert_same_position(&board, &expected);
+            }
+            let pond = ponds[rng.below(ponds.len())];
+            turn = board.choose(&player, pond, CCW).turn;
+            assert_eq!(turn, expected.choose(&player, pond));
+            assert_same_position(&board, &expected);
+        }
+    }
+}
+
+#[test]
+fn choose_outcome() {
+    let mut board = Board::with_rules(Rules::kalah());
+    let outcome = board.choose(&Player::A, 0, CCW);
+    assert!(outcome.extra_turn);
+    assert_eq!(outcome.captured, 0);
+    let mut board = board_from_counts(&[0,0,0,0,1,8,0,1,1,1,1,1,1,0]);
+    let outcome = board.choose(&Player::A, 5, CCW);
+    assert!(!outcome.extra_turn);
+    assert_eq!(outcome.captured, 3);
+}
+
+fn clockwise() -> Rules {
+    Rules::kalah().with_sowing(SowingPolicy::Fixed(CW))
+}
+
+#[test]
+fn wrong_direction() {
+    let board = Board::with_rules(Rules::kalah());
+    assert_eq!(board.valid_move(&Player::A, 0, CW), Err(Error::WrongDirection));
+    let board = Board::with_rules(clockwise());
+    assert_eq!(board.valid_move(&Player::A, 0, CCW), Err(Error::WrongDirection));
+    let board = Board::with_rules(Rules::kalah().with_sowing(SowingPolicy::PlayerChoice));
+    assert_eq!(board.valid_move(&Player::A, 0, CW), Ok(()));
+    assert_eq!(board.valid_move(&Player::A, 0, CCW), Ok(()));
+}
+
+#[test]
+fn choose_clockwise() {
+    let mut board = Board::with_rules(clockwise());
+    // skips B's bank on the way round
+    assert_eq!(board.choose(&Player::A, 2, CW).turn, Turn::Player(Player::B));
+    assert_eq!(board.pits, [7,7,0,6,6,6,0,6,6,7,7,7,7,0]);
+}
+
+#[test]
+fn choose_clockwise_extra_turn() {
+    let mut board = board_with_rules(&[7,1,0,0,0,0,0,1,1,1,1,1,1,0], clockwise());
+    let outcome = board.choose(&Player::A, 0, CW);
+    assert!(outcome.extra_turn);
+    assert_eq!(board.pits, [0,1,0,0,0,0,1,2,2,2,2,2,2,0]);
+}
+
+#[test]
+fn choose_clockwise_capture() {
+    let mut board = board_with_rules(&[1,0,0,2,0,0,0,1,1,1,1,4,1,0], clockwise());
+    assert_eq!(board.choose(&Player::A, 3, CW).captured, 5);
+    assert_eq!(board.pits, [1,0,1,0,0,0,5,1,1,1,1,0,1,0]);
+}
+
+#[test]
+fn choose_clockwise_b() {
+    let mut board = board_with_rules(&[1,1,1,1,1,1,0,3,0,0,0,0,1,0], clockwise());
+    // from B's first pond clockwise heads straight onto A's side, skipping A's bank
+    assert_eq!(board.choose(&Player::B, 0, CW).turn, Turn::Player(Player::A));
+    assert_eq!(board.pits, [1,1,1,2,2,2,0,0,0,0,0,0,1,0]);
+}
+
+#[test]
+fn display_labels_direction() {
+    let board = Board::with_rules(Rules::kalah());
+    assert_eq!(board.to_string(), "        Player B\n\
+                                   <   5  4  3  2  1  0\n\
+                                   \x20   6  6  6  6  6  6\n\
+                                   \x200                    0\n\
+                                   \x20   6  6  6  6  6  6\n\
+                                   \x20   0  1  2  3  4  5  >\n\
+                                   \x20       Player A\n");
+    let board = Board::with_rules(Rules::kalah().with_sowing(SowingPolicy::PlayerChoice));
+    let lines: Vec<String> = board.to_string().lines().map(String::from).collect();
+    assert_eq!(lines[1], "<   5  4  3  2  1  0  >");
+    assert_eq!(lines[5], "<   0  1  2  3  4  5  >");
+}
+
+fn relay() -> Rules {
+    Rules::kalah().with_relay()
+}
+
+#[test]
+fn relay_into_bank() {
+    let mut board = board_with_rules(&[0,0,1,2,0,0,0,1,1,1,1,1,1,0], relay());
+    let outcome = board.choose(&Player::A, 2, CCW);
+    assert_eq!(outcome.laps, 2);
+    assert!(outcome.extra_turn);
+    assert_eq!(board.pits, [0,0,0,0,1,1,1,1,1,1,1,1,1,0]);
+}
+
+#[test]
+fn relay_then_capture() {
+    let mut board = board_with_rules(&[1,1,0,0,0,0,0,1,1,4,1,1,1,0], relay());
+    let outcome = board.choose(&Player::A, 0, CCW);
+    assert_eq!(outcome.laps, 2);
+    assert_eq!(outcome.captured, 5);
+    assert_eq!(board.pits, [0,0,1,0,0,0,5,1,1,0,1,1,1,0]);
+}
+
+#[test]
+fn no_relay_by_default() {
+    let mut board = board_from_counts(&[1,1,0,0,0,0,0,1,1,4,1,1,1,0]);
+    let outcome = board.choose(&Player::A, 0, CCW);
+    assert_eq!(outcome.laps, 1);
+    assert_eq!(outcome.turn, Turn::Player(Player::B));
+    assert_eq!(board.pits, [0,2,0,0,0,0,0,1,1,4,1,1,1,0]);
+}
+
+#[test]
+fn relay_lap_limit() {
+    let counts = [1,6,6,5,12,9,0,5,12,11,6,7,4,0];
+    let mut board = board_with_rules(&counts, relay());
+    assert_eq!(board.choose(&Player::A, 1, CCW).laps, 78);
+    let mut board = board_with_rules(&counts, Rules::kalah().with_max_laps(10));
+    let outcome = board.choose(&Player::A, 1, CCW);
+    assert_eq!(outcome.laps, 10);
+    assert_eq!(outcome.turn, Turn::Player(Player::B));
+    assert_eq!(board.pits.iter().map(|&count| u32::from(count)).sum::<u32>(), 84);
+}
+
+#[test]
+fn mirrored() {
+    let board = board_from_counts(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14]);
+    assert_eq!(board.mirrored().pits, [8, 9, 10, 11, 12, 13, 14, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(board.mirrored().mirrored(), board);
+}
+
+#[test]
+fn canonical() {
+    let board = board_from_counts(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14]);
+    assert_eq!(board.canonical(&Player::A), (board, Transform::Identity));
+    assert_eq!(board.canonical(&Player::B), (board.mirrored(), Transform::Mirror));
+    assert_eq!(board.mirrored().canonical(&Player::A).0, board.canonical(&Player::B).0);
+}
+
+// Moves are numbered from each player's own side, so the same pond plays the same way
+// on the mirrored board
+#[test]
+fn mirrored_moves_match() {
+    let mut rng = Rng::new(39);
+    let mut board = Board::with_rules(Rules::kalah());
+    let mut mirror = board.mirrored();
+    let mut player = Player::A;
+    loop {
+        let ponds: Vec<usize> = (0..6).filter(|&pond| board.valid_move(&player, pond, CCW).is_ok()).collect();
+        let pond = ponds[rng.below(ponds.len())];
+        let turn = board.choose(&player, pond, CCW).turn;
+        let mirror_turn = mirror.choose(&player.next(), pond, CCW).turn;
+        assert_eq!(mirror, board.mirrored());
+        assert_eq!(mirror_turn, turn.mirrored());
+        if turn.is_finished() {
+            break;
+        }
+        player = turn.player().clone();
+    }
+}
+
+// Without a capture or the end of the game, sowing seed by seed lands on the same board as
+// choose
+#[test]
+fn sowing_matches_choose() {
+    let all_rules = [
+        Rules::kalah(),
+        Rules::kalah().with_relay().with_sowing(SowingPolicy::PlayerChoice),
+        Rules::oware().with_sowing(SowingPolicy::PlayerChoice),
+    ];
+    for rules in &all_rules {
+        for seed in 1..100 {
+            let mut rng = Rng::new(seed);
+            let mut board = Board::with_rules(*rules);
+            let mut turn = Turn::Player(Player::A);
+            while !turn.is_finished() {
+                let player = turn.player().clone();
+                let moves: Vec<(usize, Direction)> = (0..::PONDS_PER_PLAYER)
+                    .flat_map(|pond| rules.directions().iter().map(move |&direction| (pond, direction)))
+                    .filter(|&(pond, direction)| board.valid_move(&player, pond, direction).is_ok())
+                    .collect();
+                let (pond, direction) = moves[rng.below(moves.len())];
+                let mut pits = board.pits;
+                for step in board.sowing(&player, pond, direction) {
+                    match step {
+                        Sow::PickUp(idx) => pits[idx] = 0,
+                        Sow::Drop(idx) => pits[idx] += 1,
+                    }
+                }
+                let outcome = board.choose(&player, pond, direction);
+                if outcome.captured == 0 && !outcome.turn.is_finished() {
+                    assert_eq!(pits, board.pits, "{:?} {} {:?}", rules, pond, direction);
+                }
+                turn = outcome.turn;
+            }
+        }
+    }
+}
+
+#[test]
+fn sowing_relay() {
+    let rules = Rules::kalah().with_relay().with_sowing(SowingPolicy::PlayerChoice);
+    let board = board_with_rules(&[0,0,0,0,2,1,0,0,0,0,0,0,0,0], rules);
+    assert_eq!(board.sowing(&Player::A, 4, CCW), vec![Sow::PickUp(4), Sow::Drop(5), Sow::Drop(6)]);
+    assert_eq!(
+        board.sowing(&Player::A, 5, CW),
+        vec![Sow::PickUp(5), Sow::Drop(4), Sow::PickUp(4), Sow::Drop(3), Sow::Drop(2), Sow::Drop(1)]
+    );
 }
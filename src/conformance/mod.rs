@@ -0,0 +1,221 @@
+// Rules conformance cases kept in tests/fixtures, so every board representation is held to
+// the same corpus. Each case is a block of `key: value` lines, with blank lines between
+// cases and `#` starting a comment:
+//
+//   name: capture the opposite pond
+//   rules: kalah capture=opposite
+//   start: 0,0,0,0,1,8/0/1,1,1,1,1,1/0/A
+//   moves: 5
+//   after: 0,0,0,0,1,0/4/2,2,2,2,2,0/0
+//   turn: B
+//   captured: 3
+//
+// `rules` is written as for Rules::from_str, e.g. `kalah sowing=cw relay`.
+// `start` is a Setup and defaults to the usual start for the rules. `moves` are played in
+// order and the rest of the case describes the last one:
+// - `error` is the Error it should be rejected with (e.g. `must_feed`), or otherwise
+// - `after` is the board afterwards, as in a Setup without the player to move
+// - `turn` is A, B, `B may swap` or `finished` plus the Ending (e.g. `finished majority`)
+// - `extra_turn`, `captured` and `laps` are optional checks on the MoveOutcome
+use std::fs;
+use std::path::Path;
+
+use error::Error;
+use moves::Move;
+use player::Player;
+use rules::Rules;
+use setup::Setup;
+use turn::{Turn, Ending};
+
+#[cfg(test)]
+mod tests;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+const KEYS: [&str; 10] = ["name", "rules", "start", "moves", "error", "after", "turn", "extra_turn", "captured", "laps"];
+
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    // file and line the case starts on
+    pub source: String,
+    pub name: String,
+    pub rules: Rules,
+    pub start: Option<Setup>,
+    pub moves: Vec<Move>,
+    pub expected: Result<Played, Error>,
+}
+
+// What the last move did. Representations that can't report part of it leave it as None
+// and it isn't checked.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Played {
+    // in the packed board's order
+    pub pits: [u32; ::TOTAL_POOLS],
+    pub turn: Turn,
+    pub extra_turn: Option<bool>,
+    pub captured: Option<u32>,
+    pub laps: Option<u32>,
+}
+
+impl Fixture {
+    // the pits for the start position, or the usual start
+    pub fn start_pits(&self) -> [u32; ::TOTAL_POOLS] {
+        let setup = self.start.clone().unwrap_or_else(|| Setup::start(&self.rules));
+        let mut pits = [0; ::TOTAL_POOLS];
+        for (pit, &count) in pits.iter_mut().zip(setup.pits().iter()) {
+            *pit = u32::from(count);
+        }
+        pits
+    }
+
+    pub fn to_move(&self) -> Player {
+        self.start.as_ref().map_or(Player::A, |setup| setup.to_move.clone())
+    }
+
+    // Compares what a representation did with what was expected, describing the first
+    // difference found
+    pub fn check(&self, actual: &Result<Played, Error>) -> Result<(), String> {
+        let (expected, actual) = match (&self.expected, actual) {
+            (Ok(expected), Ok(actual)) => (expected, actual),
+            (expected, actual) if expected == actual => return Ok(()),
+            (expected, actual) => return Err(format!("expected {:?}, got {:?}", expected, actual)),
+        };
+        if expected.pits != actual.pits {
+            return Err(format!("expected board {:?}, got {:?}", expected.pits, actual.pits));
+        }
+        if expected.turn != actual.turn {
+            return Err(format!("expected {:?}, got {:?}", expected.turn, actual.turn));
+        }
+        check_field("extra_turn", expected.extra_turn, actual.extra_turn)?;
+        check_field("captured", expected.captured, actual.captured)?;
+        check_field("laps", expected.laps, actual.laps)
+    }
+}
+
+fn check_field<T: PartialEq + ::std::fmt::Debug>(name: &str, expected: Option<T>, actual: Option<T>)
+    -> Result<(), String>
+{
+    match (expected, actual) {
+        (Some(expected), Some(actual)) if expected != actual =>
+            Err(format!("expected {} {:?}, got {:?}", name, expected, actual)),
+        _ => Ok(()),
+    }
+}
+
+// Every case in every fixture file, in file name order
+pub fn load_all() -> Vec<Fixture> {
+    let mut paths: Vec<_> = fs::read_dir(FIXTURES)
+        .expect("Can't read the fixtures directory")
+        .map(|entry| entry.expect("Can't read the fixtures directory").path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    paths.sort();
+    paths.iter().flat_map(|path| load(path)).collect()
+}
+
+// panics with the file and line of anything it can't read
+pub fn load(path: &Path) -> Vec<Fixture> {
+    let text = fs::read_to_string(path).expect("Can't read fixture file");
+    let file = path.file_name().unwrap().to_string_lossy();
+    let mut fixtures = Vec::new();
+    let mut block: Vec<(usize, &str, &str)> = Vec::new();
+    // an extra blank line so the last block gets parsed
+    for (number, line) in text.lines().chain(Some("")).enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            if !block.is_empty() {
+                let source = format!("{}:{}", file, block[0].0);
+                fixtures.push(parse(&block).unwrap_or_else(|reason| panic!("{}: {}", source, reason)));
+                fixtures.last_mut().unwrap().source = source;
+                block.clear();
+            }
+            continue;
+        }
+        match line.find(':') {
+            Some(colon) => block.push((number + 1, line[..colon].trim(), line[colon + 1..].trim())),
+            None => panic!("{}:{}: expected 'key: value'", file, number + 1),
+        }
+    }
+    fixtures
+}
+
+fn parse(block: &[(usize, &str, &str)]) -> Result<Fixture, String> {
+    let value = |key: &str| block.iter().find(|&&(_, k, _)| k == key).map(|&(_, _, value)| value);
+    for &(line, key, _) in block {
+        if !KEYS.contains(&key) {
+            return Err(format!("unknown key '{}' on line {}", key, line));
+        }
+    }
+    let required = |key: &str| value(key).ok_or_else(|| format!("missing '{}'", key));
+    let rules = required("rules")?.parse::<Rules>()?;
+    let start = match value("start") {
+        Some(setup) => Some(setup.parse::<Setup>().map_err(|error| error.to_string())?),
+        None => None,
+    };
+    let moves = required("moves")?.split_whitespace()
+        .map(str::parse)
+        .collect::<Result<Vec<Move>, String>>()?;
+    if moves.is_empty() {
+        return Err("no moves".to_string());
+    }
+    let expected = match value("error") {
+        Some(error) => Err(parse_error(error)?),
+        None => {
+            let pits = parse_pits(required("after")?)?;
+            Ok(Played {
+                pits,
+                turn: parse_turn(required("turn")?, &pits)?,
+                extra_turn: value("extra_turn").map(parse_bool).transpose()?,
+                captured: value("captured").map(parse_count).transpose()?,
+                laps: value("laps").map(parse_count).transpose()?,
+            })
+        },
+    };
+    Ok(Fixture { source: String::new(), name: required("name")?.to_string(), rules, start, moves, expected })
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    value.parse().map_err(|_| format!("'{}' is not true or false", value))
+}
+
+fn parse_count(value: &str) -> Result<u32, String> {
+    value.parse().map_err(|_| format!("'{}' is not a count", value))
+}
+
+fn parse_error(value: &str) -> Result<Error, String> {
+    match value {
+        "empty_pool" => Ok(Error::EmptyPool),
+        "invalid_index" => Ok(Error::InvalidIndex),
+        "game_finished" => Ok(Error::GameFinished),
+        "must_feed" => Ok(Error::MustFeed),
+        "cannot_swap" => Ok(Error::CannotSwap),
+        "wrong_direction" => Ok(Error::WrongDirection),
+        _ => Err(format!("unknown error '{}'", value)),
+    }
+}
+
+// A's ponds/A's bank/B's ponds/B's bank, borrowing the Setup format
+fn parse_pits(value: &str) -> Result<[u32; ::TOTAL_POOLS], String> {
+    let setup = format!("{}/A", value).parse::<Setup>().map_err(|error| error.to_string())?;
+    let mut pits = [0; ::TOTAL_POOLS];
+    pits[..6].copy_from_slice(&setup.ponds_a);
+    pits[6] = setup.bank_a;
+    pits[7..13].copy_from_slice(&setup.ponds_b);
+    pits[13] = setup.bank_b;
+    Ok(pits)
+}
+
+// the result of a finished game comes from the banks
+fn parse_turn(value: &str, pits: &[u32; ::TOTAL_POOLS]) -> Result<Turn, String> {
+    let ending = match value {
+        "A" => return Ok(Turn::Player(Player::A)),
+        "B" => return Ok(Turn::Player(Player::B)),
+        "B may swap" => return Ok(Turn::SwapOffered(Player::B)),
+        "finished side_empty" => Ending::SideEmpty,
+        "finished majority" => Ending::Majority,
+        "finished no_moves" => Ending::NoMoves,
+        "finished no_captures" => Ending::NoCaptures,
+        _ => return Err(format!("unknown turn '{}'", value)),
+    };
+    Ok(Turn::new_finished(pits[6], pits[13], ending))
+}
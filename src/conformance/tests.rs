@@ -0,0 +1,114 @@
+use super::*;
+use board::reference;
+use kalaha::Kalaha;
+
+// Plays every case through play, which returns None for cases it can't handle, and fails
+// listing every case that didn't match
+fn run(play: &dyn Fn(&Fixture) -> Option<Result<Played, Error>>) -> usize {
+    let mut checked = 0;
+    let mut failures = Vec::new();
+    for fixture in load_all() {
+        if let Some(actual) = play(&fixture) {
+            checked += 1;
+            if let Err(reason) = fixture.check(&actual) {
+                failures.push(format!("{} ({}): {}", fixture.source, fixture.name, reason));
+            }
+        }
+    }
+    assert!(failures.is_empty(), "{} of {} cases failed:\n{}", failures.len(), checked, failures.join("\n"));
+    checked
+}
+
+fn play_kalaha(fixture: &Fixture) -> Result<Played, Error> {
+    let mut game = match fixture.start {
+        Some(ref setup) => Kalaha::from_setup_any_total(fixture.rules, setup).expect("Invalid start"),
+        None => Kalaha::with_rules(fixture.rules),
+    };
+    let (last, before) = fixture.moves.split_last().unwrap();
+    for &mv in before {
+        game.valid_move(mv).expect("Invalid move before the last");
+        game.choose(mv);
+    }
+    game.valid_move(*last)?;
+    let outcome = game.choose(*last);
+    let mut pits = [0; ::TOTAL_POOLS];
+    for (idx, player) in [Player::A, Player::B].iter().enumerate() {
+        pits[idx * 7..idx * 7 + 6].copy_from_slice(&game.ponds(player));
+        pits[idx * 7 + 6] = game.bank(player);
+    }
+    Ok(Played {
+        pits,
+        turn: outcome.turn,
+        extra_turn: Some(outcome.extra_turn),
+        captured: Some(outcome.captured),
+        laps: Some(outcome.laps),
+    })
+}
+
+// The reference board only knows the default Kalah rules and doesn't say what a move did
+// beyond whose turn it is
+fn play_reference(fixture: &Fixture) -> Option<Result<Played, Error>> {
+    if fixture.rules != Rules::kalah() {
+        return None;
+    }
+    let mut board = reference::Board::from_counts(&fixture.start_pits());
+    let mut player = fixture.to_move();
+    let mut turn = Turn::Player(player.clone());
+    for (idx, mv) in fixture.moves.iter().enumerate() {
+        let pond = match *mv {
+            Move::Pond(pond) => pond,
+            _ => return None,
+        };
+        if let Err(error) = board.valid_move(&player, pond) {
+            assert_eq!(idx, fixture.moves.len() - 1, "Invalid move before the last");
+            return Some(Err(error));
+        }
+        turn = board.choose(&player, pond);
+        if !turn.is_finished() {
+            player = turn.player().clone();
+        }
+    }
+    let mut pits = [0; ::TOTAL_POOLS];
+    for (idx, player) in [Player::A, Player::B].iter().enumerate() {
+        pits[idx * 7..idx * 7 + 6].copy_from_slice(&board.pond_counts(player));
+        pits[idx * 7 + 6] = board.bank(player).count;
+    }
+    Some(Ok(Played { pits, turn, extra_turn: None, captured: None, laps: None }))
+}
+
+#[test]
+fn kalaha_conforms() {
+    let checked = run(&|fixture| Some(play_kalaha(fixture)));
+    assert_eq!(checked, load_all().len());
+}
+
+#[test]
+fn reference_conforms() {
+    assert!(run(&play_reference) > 0);
+}
+
+#[test]
+fn parse_case() {
+    let fixtures = load(Path::new(&format!("{}/kalah.txt", FIXTURES)));
+    let fixture = &fixtures[0];
+    assert_eq!(fixture.source, "kalah.txt:3");
+    assert_eq!(fixture.rules, Rules::kalah());
+    assert_eq!(fixture.start, None);
+    assert_eq!(fixture.moves, vec![Move::Pond(1)]);
+    assert_eq!(fixture.expected.as_ref().unwrap().turn, Turn::Player(Player::B));
+}
+
+#[test]
+fn check_reports_difference() {
+    let mut fixture = load(Path::new(&format!("{}/kalah.txt", FIXTURES)))[0].clone();
+    let mut played = fixture.expected.clone().unwrap();
+    played.captured = None;
+    assert_eq!(fixture.check(&Ok(played.clone())), Ok(()));
+    played.pits[0] += 1;
+    assert!(fixture.check(&Ok(played)).unwrap_err().starts_with("expected board"));
+    fixture.expected = Err(Error::EmptyPool);
+    assert_eq!(
+        fixture.check(&Err(Error::MustFeed)),
+        Err("expected Err(EmptyPool), got Err(MustFeed)".to_string())
+    );
+}
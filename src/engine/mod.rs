@@ -0,0 +1,424 @@
+// A line based protocol for running AIs as separate processes, so engines written in other
+// languages can play. The controller writes commands to the engine's stdin:
+//
+//   kei                  start a session, answered with `id name <name>` and then `keiok`
+//   isready              answered with `readyok` once everything before it is done
+//   rules <rules>        the rules to play by, as in Rules::from_str, and back to the start
+//   position startpos|<setup> [opening|swap] [quiet <n>] [moves <move>...]
+//                        the position to search, from the start or a Setup, then any moves.
+//                        Under the pie rule `opening` after a setup means player A is still
+//                        making their first move and `swap` that player B may swap now. The
+//                        start is always player A's opening. `quiet` is how many moves have
+//                        gone by without an Oware capture.
+//   go depth <n>|movetime <ms>
+//                        search the position to a depth or for about that long
+//   quit
+//
+// and the engine answers `go` with any number of `info depth <n> score <score> pv <move>...`
+// lines followed by `bestmove <move>`, or `bestmove none` if it has no move. Scores are the
+// bank difference for the player to move. Anything the engine can't follow gets an
+// `error <reason>` line and is otherwise ignored.
+use std::cell::RefCell;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::ai::{AI, AIError, Analysis, Until};
+use super::kalaha::Kalaha;
+use super::moves::Move;
+use super::rules::Rules;
+use super::setup::{Setup, SetupError};
+
+#[cfg(test)]
+mod tests;
+
+// deepest a timed search will go, well past anything that finishes in time
+const MAX_DEPTH: u32 = 40;
+
+// how long ExternalEngine::spawn waits for a program to answer kei
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// how long past its movetime an ExternalEngine waits for bestmove before giving up on it
+pub const MOVETIME_MARGIN: Duration = Duration::from_secs(1);
+
+// how long a dropped ExternalEngine waits for the program to quit before killing it
+const QUIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+// How long an engine should think about a move
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SearchLimit {
+    Depth(u32),
+    MoveTime(Duration),
+}
+
+impl fmt::Display for SearchLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            SearchLimit::Depth(depth) => f.write_str(&format!("depth {}", depth)),
+            SearchLimit::MoveTime(time) => f.write_str(&format!("movetime {}", time.as_millis())),
+        }
+    }
+}
+
+// The position command that recreates game, pie rule and all
+pub fn position_command(game: &Kalaha) -> Option<String> {
+    let setup = game.setup()?;
+    let pie = if game.opening() {
+        " opening"
+    } else if game.swap_offered() {
+        " swap"
+    } else {
+        ""
+    };
+    let quiet = match game.quiet_moves() {
+        0 => String::new(),
+        quiet_moves => format!(" quiet {}", quiet_moves),
+    };
+    Some(format!("position {}{}{}", setup, pie, quiet))
+}
+
+// Reads the arguments to a position command
+pub fn parse_position(rules: Rules, args: &str) -> Result<Kalaha, String> {
+    let mut words = args.split_whitespace().peekable();
+    let (mut game, from_setup) = match words.next() {
+        Some("startpos") => (Kalaha::with_rules(rules), false),
+        Some(setup) => {
+            let setup: Setup = setup.parse().map_err(|error: SetupError| error.to_string())?;
+            (Kalaha::from_setup_any_total(rules, &setup).map_err(|error| error.to_string())?, true)
+        },
+        None => return Err("position needs startpos or a setup".to_string()),
+    };
+    let opening = words.peek() == Some(&"opening");
+    let swap = words.peek() == Some(&"swap");
+    if opening || swap {
+        words.next();
+    }
+    // the start already knows where the pie rule is, a setup doesn't
+    if from_setup {
+        game.restore_pie_rule(opening, swap).map_err(|_| "the pie rule doesn't allow that here".to_string())?;
+    } else if opening || swap {
+        return Err("opening and swap only follow a setup".to_string());
+    }
+    if words.peek() == Some(&"quiet") {
+        words.next();
+        let quiet = words.next().unwrap_or("");
+        game.restore_quiet_moves(quiet.parse().map_err(|_| format!("'{}' is not a number of moves", quiet))?);
+    }
+    match words.next() {
+        Some("moves") => (),
+        Some(word) => return Err(format!("expected moves, found '{}'", word)),
+        None => return Ok(game),
+    }
+    for word in words {
+        let mv: Move = word.parse()?;
+        game.valid_move(mv).map_err(|error| format!("illegal move {}: {:?}", mv, error))?;
+        game.choose(mv);
+    }
+    Ok(game)
+}
+
+fn parse_limit(args: &str) -> Result<SearchLimit, String> {
+    let words: Vec<&str> = args.split_whitespace().collect();
+    match words[..] {
+        ["depth", depth] => match depth.parse() {
+            Ok(depth) if depth > 0 => Ok(SearchLimit::Depth(depth)),
+            _ => Err(format!("'{}' is not a depth", depth)),
+        },
+        ["movetime", time] => time.parse()
+            .map(|time| SearchLimit::MoveTime(Duration::from_millis(time)))
+            .map_err(|_| format!("'{}' is not a time in milliseconds", time)),
+        _ => Err("go needs depth <n> or movetime <ms>".to_string()),
+    }
+}
+
+// Answers the protocol on input and output with the AIs make_ai builds for each depth, until
+// told to quit or input runs out
+pub fn serve<R: BufRead, W: Write>(name: &str, make_ai: &dyn Fn(u32) -> Box<dyn AI>, input: R, mut output: W)
+    -> io::Result<()>
+{
+    let mut rules = Rules::default();
+    let mut game = Kalaha::with_rules(rules);
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        let (command, args) = match line.find(' ') {
+            Some(space) => (&line[..space], line[space + 1..].trim()),
+            None => (line, ""),
+        };
+        let result = match command {
+            "" => Ok(()),
+            "kei" => {
+                writeln!(output, "id name {}\nkeiok", name)?;
+                Ok(())
+            },
+            "isready" => {
+                writeln!(output, "readyok")?;
+                Ok(())
+            },
+            "rules" => args.parse().map(|new_rules| {
+                rules = new_rules;
+                game = Kalaha::with_rules(rules);
+            }),
+            "position" => parse_position(rules, args).map(|position| game = position),
+            "go" => match parse_limit(args) {
+                Ok(limit) => {
+                    go(make_ai, &game, limit, &mut output)?;
+                    Ok(())
+                },
+                Err(reason) => Err(reason),
+            },
+            "quit" => break,
+            _ => Err(format!("unknown command '{}'", command)),
+        };
+        if let Err(reason) = result {
+            writeln!(output, "error {}", reason)?;
+        }
+        output.flush()?;
+    }
+    Ok(())
+}
+
+// Reports each depth of the search, then the best move
+fn go<W: Write>(make_ai: &dyn Fn(u32) -> Box<dyn AI>, game: &Kalaha, limit: SearchLimit, output: &mut W)
+    -> io::Result<()>
+{
+    let best = deepen(make_ai, game, limit, None, &mut |depth, analysis, pv| {
+        let pv: Vec<String> = pv.iter().map(Move::to_string).collect();
+        writeln!(output, "info depth {} score {} pv {}", depth, analysis.score.unwrap_or(0), pv.join(" "))
+    })?;
+    match best {
+        Ok(analysis) => writeln!(output, "bestmove {}", analysis.mv),
+        Err(error) => writeln!(output, "error {}\nbestmove none", error),
+    }
+}
+
+// Told about each depth deepen finishes, with what the search found and the line it expects
+pub type Report<'a> = dyn FnMut(u32, &Analysis, &[Move]) -> io::Result<()> + 'a;
+
+// Searches deeper and deeper until the limit, passing each search that gives a score to
+// report along with the line of play it expects, and returns the deepest finished search's
+// move. Under a movetime every depth after the first gives up once the time is up. Setting
+// stop gives up straight away, at any depth. An AI that doesn't search is only asked once.
+pub fn deepen(make_ai: &dyn Fn(u32) -> Box<dyn AI>, game: &Kalaha, limit: SearchLimit,
+              stop: Option<Arc<AtomicBool>>, report: &mut Report) -> io::Result<Result<Analysis, AIError>>
+{
+    let (depths, deadline) = match limit {
+        SearchLimit::Depth(depth) => (depth..depth + 1, None),
+        SearchLimit::MoveTime(time) => (1..MAX_DEPTH + 1, Some(Instant::now() + time)),
+    };
+    let mut best = None;
+    for depth in depths {
+        // the first depth always finishes in time so there's a move to give
+        let until = Until { deadline: if best.is_some() { deadline } else { None }, stop: stop.clone() };
+        let (analysis, pv) = match make_ai(depth).analyse_until(game, &until) {
+            Ok(found) => found,
+            Err(error) => return Ok(best.ok_or(error)),
+        };
+        best = Some(analysis);
+        if analysis.score.is_none() {
+            break;
+        }
+        report(depth, &analysis, &pv)?;
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            break;
+        }
+    }
+    Ok(best.ok_or(AIError::NoValidMoves))
+}
+
+// Reads the engine's output a line at a time on a thread of its own, so waiting for a line
+// can time out. The thread ends when the engine does.
+fn read_lines(output: ChildStdout) -> Receiver<io::Result<String>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut output = BufReader::new(output);
+        loop {
+            let mut line = String::new();
+            let line = match output.read_line(&mut line) {
+                Ok(0) => Err(io::Error::new(io::ErrorKind::UnexpectedEof, "engine exited")),
+                Ok(_) => Ok(line.trim().to_string()),
+                Err(error) => Err(error),
+            };
+            let done = line.is_err();
+            if sender.send(line).is_err() || done {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+struct Process {
+    child: Child,
+    input: ChildStdin,
+    lines: Receiver<io::Result<String>>,
+}
+
+impl Process {
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.input, "{}", line)?;
+        self.input.flush()
+    }
+
+    // the next line from the engine, or a TimedOut error once past deadline
+    fn receive(&self, deadline: Option<Instant>, waiting_for: &str) -> io::Result<String> {
+        let exited = || io::Error::new(io::ErrorKind::UnexpectedEof, "engine exited");
+        let late = || {
+            io::Error::new(io::ErrorKind::TimedOut, format!("the engine didn't answer {} in time", waiting_for))
+        };
+        let line = match deadline {
+            Some(deadline) => self.lines.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                .map_err(|error| match error {
+                    RecvTimeoutError::Timeout => late(),
+                    RecvTimeoutError::Disconnected => exited(),
+                }),
+            None => self.lines.recv().map_err(|_| exited()),
+        };
+        line?
+    }
+
+    // Reads the engine's answer to kei up to keiok, giving back the name it sent if any
+    fn handshake(&self, deadline: Instant) -> io::Result<Option<String>> {
+        let mut name = None;
+        loop {
+            let line = self.receive(Some(deadline), "kei")?;
+            if line == "keiok" {
+                return Ok(name);
+            } else if let Some(engine_name) = line.strip_prefix("id name ") {
+                name = Some(engine_name.to_string());
+            }
+        }
+    }
+
+    fn kill(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// An AI that runs another program and asks it for moves over the engine protocol
+pub struct ExternalEngine {
+    name: String,
+    limit: SearchLimit,
+    process: RefCell<Process>,
+}
+
+impl ExternalEngine {
+    // Starts the program and waits up to HANDSHAKE_TIMEOUT for it to answer the handshake
+    pub fn spawn(program: &str, args: &[&str], limit: SearchLimit) -> io::Result<ExternalEngine> {
+        ExternalEngine::spawn_with_timeout(program, args, limit, HANDSHAKE_TIMEOUT)
+    }
+
+    // Starts the program and waits up to timeout for it to answer the handshake, killing it
+    // if it doesn't
+    pub fn spawn_with_timeout(program: &str, args: &[&str], limit: SearchLimit, timeout: Duration)
+        -> io::Result<ExternalEngine>
+    {
+        let mut child = Command::new(program).args(args).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+        let input = child.stdin.take().expect("stdin is piped");
+        let lines = read_lines(child.stdout.take().expect("stdout is piped"));
+        let mut process = Process { child, input, lines };
+        let deadline = Instant::now() + timeout;
+        let name = match process.send("kei").and_then(|_| process.handshake(deadline)) {
+            Ok(name) => name,
+            Err(error) => {
+                process.kill();
+                return Err(error);
+            },
+        };
+        Ok(ExternalEngine {
+            name: name.unwrap_or_else(|| program.to_string()),
+            limit,
+            process: RefCell::new(process),
+        })
+    }
+
+    // what the engine calls itself
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn search(&self, game: &Kalaha) -> io::Result<Result<Analysis, AIError>> {
+        let position = match position_command(game) {
+            Some(position) => position,
+            None => return Ok(Err(AIError::GameFinished)),
+        };
+        let mut process = self.process.borrow_mut();
+        process.send(&format!("rules {}", game.rules()))?;
+        process.send(&position)?;
+        process.send(&format!("go {}", self.limit))?;
+        // an engine searching to a depth takes as long as it takes
+        let deadline = match self.limit {
+            SearchLimit::MoveTime(time) => Some(Instant::now() + time + MOVETIME_MARGIN),
+            SearchLimit::Depth(_) => None,
+        };
+        let mut score = None;
+        let mut error = None;
+        loop {
+            let line = match process.receive(deadline, "go") {
+                Ok(line) => line,
+                // it may still be searching, so there's no knowing what it says next
+                Err(ref late) if late.kind() == io::ErrorKind::TimedOut => {
+                    process.kill();
+                    return Ok(Err(AIError::OutOfTime));
+                },
+                Err(error) => return Err(error),
+            };
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("info") => {
+                    while let Some(word) = words.next() {
+                        if word == "score" {
+                            score = words.next().and_then(|score| score.parse().ok());
+                        }
+                    }
+                },
+                Some("error") => error = Some(line["error".len()..].trim().to_string()),
+                Some("bestmove") => {
+                    let result = match words.next() {
+                        Some("none") | None => Err(AIError::Failed(error.unwrap_or_else(|| "no move".to_string()))),
+                        Some(mv) => mv.parse().map(|mv| Analysis { mv, score }).map_err(AIError::Failed),
+                    };
+                    return Ok(result);
+                },
+                _ => (),
+            }
+        }
+    }
+}
+
+impl AI for ExternalEngine {
+    fn choose(&self, game: &Kalaha) -> Result<Move, AIError> {
+        self.analyse(game).map(|analysis| analysis.mv)
+    }
+
+    fn analyse(&self, game: &Kalaha) -> Result<Analysis, AIError> {
+        self.search(game).unwrap_or_else(|error| Err(AIError::Failed(format!("{}: {}", self.name, error))))
+    }
+}
+
+impl fmt::Debug for ExternalEngine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("ExternalEngine").field("name", &self.name).field("limit", &self.limit).finish()
+    }
+}
+
+impl Drop for ExternalEngine {
+    fn drop(&mut self) {
+        // it may have gone already, in which case there's nothing to tidy up, and if it
+        // doesn't quit when asked it's killed
+        let process = self.process.get_mut();
+        if process.send("quit").is_ok() {
+            let deadline = Instant::now() + QUIT_TIMEOUT;
+            while matches!(process.child.try_wait(), Ok(None)) && Instant::now() < deadline {
+                thread::sleep(Duration::from_millis(10));
+            }
+        }
+        process.kill();
+    }
+}
@@ -0,0 +1,153 @@
+use std::io::Cursor;
+
+use super::*;
+use ai::{AlphaBeta, FirstValid};
+use player::Player;
+
+fn alphabeta(depth: u32) -> Box<dyn AI> {
+    Box::new(AlphaBeta::new(depth))
+}
+
+fn first(_: u32) -> Box<dyn AI> {
+    Box::new(FirstValid {})
+}
+
+// the engine's replies to the commands
+fn serve_lines(make_ai: &dyn Fn(u32) -> Box<dyn AI>, commands: &str) -> Vec<String> {
+    let mut output = Vec::new();
+    serve("test", make_ai, Cursor::new(commands), &mut output).unwrap();
+    String::from_utf8(output).unwrap().lines().map(String::from).collect()
+}
+
+#[test]
+fn handshake() {
+    assert_eq!(serve_lines(&first, "kei\nisready\n"), vec!["id name test", "keiok", "readyok"]);
+}
+
+#[test]
+fn go_depth() {
+    let lines = serve_lines(&alphabeta, "position startpos moves 0\ngo depth 3\n");
+    let mut game = Kalaha::new();
+    game.choose(0);
+    let analysis = AlphaBeta::new(3).analyse(&game).unwrap();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with(&format!("info depth 3 score {} pv {} ", analysis.score.unwrap(), analysis.mv)));
+    assert_eq!(lines[0].split_whitespace().count(), 9);
+    assert_eq!(lines[1], format!("bestmove {}", analysis.mv));
+}
+
+#[test]
+fn go_movetime() {
+    let lines = serve_lines(&alphabeta, "go movetime 50\n");
+    assert!(lines[0].starts_with("info depth 1 score "));
+    assert!(lines.last().unwrap().starts_with("bestmove "));
+    // AIs that don't search just give a move
+    assert_eq!(serve_lines(&first, "go movetime 50\n"), vec!["bestmove 0"]);
+}
+
+// the depth that runs out of time is cut short rather than finished
+#[test]
+fn movetime_stops_mid_depth() {
+    let start = Instant::now();
+    let mut depths = Vec::new();
+    let limit = SearchLimit::MoveTime(Duration::from_millis(50));
+    let best = deepen(&alphabeta, &Kalaha::new(), limit, None, &mut |depth, _, _| {
+        depths.push(depth);
+        Ok(())
+    }).unwrap();
+    assert!(start.elapsed() < Duration::from_millis(500), "{:?}", start.elapsed());
+    assert!(depths.len() < MAX_DEPTH as usize && depths.iter().copied().eq(1..depths.len() as u32 + 1), "{:?}", depths);
+    assert!(best.is_ok());
+}
+
+#[test]
+fn rules_apply_to_positions() {
+    let lines = serve_lines(&first, "rules oware\nposition 1,0,0,0,0,1/22/0,0,0,0,0,0/24/A\ngo depth 1\n");
+    // pond 0 can't feed player B
+    assert_eq!(lines, vec!["bestmove 5"]);
+}
+
+#[test]
+fn errors() {
+    let lines = serve_lines(
+        &first,
+        "hello\nrules chess\nposition startpos moves 0 0\nposition startpos swap\n\
+         position 6,6,6,6,6,6/0/6,6,6,6,6,6/0/A swap\ngo fast\nisready\n",
+    );
+    assert_eq!(lines, vec![
+        "error unknown command 'hello'",
+        "error unknown rules 'chess'",
+        "error illegal move 0: EmptyPool",
+        "error opening and swap only follow a setup",
+        "error the pie rule doesn't allow that here",
+        "error go needs depth <n> or movetime <ms>",
+        "readyok",
+    ]);
+}
+
+#[test]
+fn no_move_when_finished() {
+    let lines = serve_lines(&first, "position 0,0,0,0,0,1/35/1,0,0,0,0,0/35/A moves 5\ngo depth 1\n");
+    assert_eq!(lines, vec!["error Game has finished", "bestmove none"]);
+}
+
+#[test]
+fn quit_stops() {
+    assert_eq!(serve_lines(&first, "quit\nisready\n"), Vec::<String>::new());
+}
+
+#[test]
+fn position_round_trip() {
+    let pie = Rules::kalah().with_pie_rule(true);
+    let mut games = vec![Kalaha::with_rules(pie), Kalaha::new()];
+    let mut game = Kalaha::with_rules(pie);
+    game.choose(0);
+    games.push(game.clone());
+    game.choose(1);
+    assert!(game.swap_offered());
+    games.push(game.clone());
+    game.choose(Move::Swap);
+    games.push(game.clone());
+    for game in &games {
+        let command = position_command(game).unwrap();
+        let parsed = parse_position(*game.rules(), &command["position ".len()..]).unwrap();
+        assert_eq!(parsed.setup(), game.setup());
+        assert_eq!(parsed.opening(), game.opening());
+        assert_eq!(parsed.swap_offered(), game.swap_offered());
+        assert_eq!(parsed.legal_moves(), game.legal_moves());
+    }
+    assert_eq!(position_command(&games[3]).unwrap(), "position 0,0,8,8,8,8/2/7,7,6,6,6,6/0/B swap");
+}
+
+#[test]
+fn position_from_start_with_moves() {
+    let game = parse_position(Rules::kalah(), "startpos moves 0 1 3").unwrap();
+    assert_eq!(*game.current_player(), Player::A);
+    assert_eq!(game.bank(&Player::A), 2);
+    assert_eq!(parse_position(Rules::kalah(), "startpos 0").unwrap_err(), "expected moves, found '0'");
+}
+
+// the start under the pie rule is player A's opening, so their move offers the swap
+#[test]
+fn position_from_start_under_the_pie_rule() {
+    let pie = Rules::kalah().with_pie_rule(true);
+    let mut game = Kalaha::with_rules(pie);
+    game.choose(1);
+    let parsed = parse_position(pie, "startpos moves 1").unwrap();
+    assert!(parsed.swap_offered());
+    assert_eq!(parsed.legal_moves(), game.legal_moves());
+    let lines = serve_lines(&first, "rules kalah pie\nposition startpos moves 1\ngo depth 1\n");
+    assert_eq!(lines, vec![format!("bestmove {}", game.legal_moves()[0])]);
+}
+
+#[test]
+fn position_keeps_quiet_moves() {
+    let mut game = Kalaha::with_rules(Rules::oware());
+    for &pond in &[0, 0, 1] {
+        game.choose(pond);
+    }
+    assert_eq!(game.quiet_moves(), 3);
+    let command = position_command(&game).unwrap();
+    assert!(command.ends_with(" quiet 3"));
+    assert_eq!(parse_position(Rules::oware(), &command["position ".len()..]).unwrap().quiet_moves(), 3);
+}
@@ -1,7 +1,29 @@
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Error {
     EmptyPool,
     InvalidIndex,
     GameFinished,
+    // the opponent has no seeds and this move wouldn't give them any
+    MustFeed,
+    // swapping sides is only allowed straight after the first move under the pie rule
+    CannotSwap,
+    // the move sows in a direction the rules don't allow, or doesn't pick a direction
+    // when the rules leave it to the player
+    WrongDirection,
     NotImplemented,
 }
+
+impl Error {
+    // a short name for the error, as in protocols and fixtures
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Error::EmptyPool => "empty_pool",
+            Error::InvalidIndex => "invalid_index",
+            Error::GameFinished => "game_finished",
+            Error::MustFeed => "must_feed",
+            Error::CannotSwap => "cannot_swap",
+            Error::WrongDirection => "wrong_direction",
+            Error::NotImplemented => "not_implemented",
+        }
+    }
+}
@@ -0,0 +1,360 @@
+// A C API behind the `ffi` feature, for embedding the engine in other languages. Games are
+// opaque handles from kalaha_new or kalaha_with_rules that have to go back to kalaha_free.
+// Functions that can fail return a KalahaStatus, with illegal moves mapped from Error, and
+// never panic on bad input. include/kalaha.h is generated from this file by build.rs.
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::ptr;
+
+use super::ai;
+use super::error::Error;
+use super::kalaha::Kalaha;
+use super::moves::Move;
+use super::player::Player;
+use super::rules::{Rules, Direction};
+use super::turn::GameResult;
+
+#[cfg(test)]
+mod tests;
+
+pub const KALAHA_PLAYER_A: u32 = 0;
+pub const KALAHA_PLAYER_B: u32 = 1;
+// the winner of a drawn game
+pub const KALAHA_PLAYER_NONE: u32 = 2;
+
+// sow from the pond in the direction the rules sow in
+pub const KALAHA_MOVE_POND: u32 = 0;
+pub const KALAHA_MOVE_CLOCKWISE: u32 = 1;
+pub const KALAHA_MOVE_COUNTER_CLOCKWISE: u32 = 2;
+// swap sides under the pie rule, the pond is ignored
+pub const KALAHA_MOVE_SWAP: u32 = 3;
+
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum KalahaStatus {
+    Ok = 0,
+    EmptyPool,
+    InvalidIndex,
+    GameFinished,
+    MustFeed,
+    CannotSwap,
+    WrongDirection,
+    NotImplemented,
+    // a pointer argument was NULL
+    NullPointer,
+    // an argument was out of range or not UTF-8
+    InvalidArgument,
+    // the game is still going
+    NotFinished,
+    AiFailed,
+}
+
+impl KalahaStatus {
+    const ALL: [KalahaStatus; 12] = [
+        KalahaStatus::Ok, KalahaStatus::EmptyPool, KalahaStatus::InvalidIndex, KalahaStatus::GameFinished,
+        KalahaStatus::MustFeed, KalahaStatus::CannotSwap, KalahaStatus::WrongDirection,
+        KalahaStatus::NotImplemented, KalahaStatus::NullPointer, KalahaStatus::InvalidArgument,
+        KalahaStatus::NotFinished, KalahaStatus::AiFailed,
+    ];
+
+    // the status with this value, which C can set to anything
+    fn from_u32(value: u32) -> Option<KalahaStatus> {
+        KalahaStatus::ALL.iter().cloned().find(|&status| status as u32 == value)
+    }
+}
+
+impl From<Error> for KalahaStatus {
+    fn from(error: Error) -> KalahaStatus {
+        match error {
+            Error::EmptyPool => KalahaStatus::EmptyPool,
+            Error::InvalidIndex => KalahaStatus::InvalidIndex,
+            Error::GameFinished => KalahaStatus::GameFinished,
+            Error::MustFeed => KalahaStatus::MustFeed,
+            Error::CannotSwap => KalahaStatus::CannotSwap,
+            Error::WrongDirection => KalahaStatus::WrongDirection,
+            Error::NotImplemented => KalahaStatus::NotImplemented,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct KalahaMove {
+    // one of the KALAHA_MOVE constants
+    pub kind: u32,
+    // 0 to 5, from the player's first pond
+    pub pond: u32,
+}
+
+impl KalahaMove {
+    fn to_move(self) -> Result<Move, KalahaStatus> {
+        let pond = self.pond as usize;
+        match self.kind {
+            KALAHA_MOVE_POND => Ok(Move::Pond(pond)),
+            KALAHA_MOVE_CLOCKWISE => Ok(Move::Directed(pond, Direction::Clockwise)),
+            KALAHA_MOVE_COUNTER_CLOCKWISE => Ok(Move::Directed(pond, Direction::CounterClockwise)),
+            KALAHA_MOVE_SWAP => Ok(Move::Swap),
+            _ => Err(KalahaStatus::InvalidArgument),
+        }
+    }
+
+    fn from_move(mv: Move) -> KalahaMove {
+        match mv {
+            Move::Pond(pond) => KalahaMove { kind: KALAHA_MOVE_POND, pond: pond as u32 },
+            Move::Directed(pond, Direction::Clockwise) => KalahaMove { kind: KALAHA_MOVE_CLOCKWISE, pond: pond as u32 },
+            Move::Directed(pond, Direction::CounterClockwise) =>
+                KalahaMove { kind: KALAHA_MOVE_COUNTER_CLOCKWISE, pond: pond as u32 },
+            Move::Swap => KalahaMove { kind: KALAHA_MOVE_SWAP, pond: 0 },
+        }
+    }
+}
+
+// What a move did
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct KalahaOutcome {
+    pub extra_turn: bool,
+    pub captured: u32,
+    pub laps: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct KalahaResult {
+    // KALAHA_PLAYER_A, KALAHA_PLAYER_B or KALAHA_PLAYER_NONE
+    pub winner: u32,
+    pub score_a: u32,
+    pub score_b: u32,
+    // the loser forfeited rather than being outscored
+    pub forfeit: bool,
+}
+
+// The game behind a handle
+pub struct KalahaGame {
+    game: Kalaha,
+}
+
+fn player(player: u32) -> Result<Player, KalahaStatus> {
+    match player {
+        KALAHA_PLAYER_A => Ok(Player::A),
+        KALAHA_PLAYER_B => Ok(Player::B),
+        _ => Err(KalahaStatus::InvalidArgument),
+    }
+}
+
+fn player_code(player: &Player) -> u32 {
+    match *player {
+        Player::A => KALAHA_PLAYER_A,
+        Player::B => KALAHA_PLAYER_B,
+    }
+}
+
+unsafe fn c_str<'a>(string: *const c_char) -> Result<&'a str, KalahaStatus> {
+    if string.is_null() {
+        return Err(KalahaStatus::NullPointer);
+    }
+    CStr::from_ptr(string).to_str().map_err(|_| KalahaStatus::InvalidArgument)
+}
+
+fn status(result: Result<(), KalahaStatus>) -> KalahaStatus {
+    result.err().unwrap_or(KalahaStatus::Ok)
+}
+
+/// A new game of Kalah from the usual start.
+#[no_mangle]
+pub extern "C" fn kalaha_new() -> *mut KalahaGame {
+    Box::into_raw(Box::new(KalahaGame { game: Kalaha::new() }))
+}
+
+/// A new game under rules written as for the command line, e.g. "oware pie", or NULL if
+/// they can't be read.
+///
+/// # Safety
+/// rules must be NULL or a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn kalaha_with_rules(rules: *const c_char) -> *mut KalahaGame {
+    match c_str(rules).ok().and_then(|rules| rules.parse::<Rules>().ok()) {
+        Some(rules) => Box::into_raw(Box::new(KalahaGame { game: Kalaha::with_rules(rules) })),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Frees a game. NULL is ignored.
+///
+/// # Safety
+/// game must be NULL or a handle that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn kalaha_free(game: *mut KalahaGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// KALAHA_STATUS_OK if the player to move may make mv, or why not.
+///
+/// # Safety
+/// game must be NULL or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn kalaha_valid_move(game: *const KalahaGame, mv: KalahaMove) -> KalahaStatus {
+    let game = match game.as_ref() {
+        Some(game) => game,
+        None => return KalahaStatus::NullPointer,
+    };
+    status(mv.to_move().and_then(|mv| game.game.valid_move(mv).map_err(KalahaStatus::from)))
+}
+
+/// Makes mv if it's valid, filling in outcome unless it's NULL.
+///
+/// # Safety
+/// game must be NULL or a live handle, and outcome NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn kalaha_choose(game: *mut KalahaGame, mv: KalahaMove, outcome: *mut KalahaOutcome)
+    -> KalahaStatus
+{
+    let game = match game.as_mut() {
+        Some(game) => game,
+        None => return KalahaStatus::NullPointer,
+    };
+    let mv = match mv.to_move() {
+        Ok(mv) => mv,
+        Err(status) => return status,
+    };
+    if let Err(error) = game.game.valid_move(mv) {
+        return error.into();
+    }
+    let played = game.game.choose(mv);
+    if let Some(outcome) = outcome.as_mut() {
+        *outcome = KalahaOutcome { extra_turn: played.extra_turn, captured: played.captured, laps: played.laps };
+    }
+    KalahaStatus::Ok
+}
+
+/// Copies the player's six ponds, from their first, into ponds.
+///
+/// # Safety
+/// game must be NULL or a live handle, and ponds NULL or room for six counts.
+#[no_mangle]
+pub unsafe extern "C" fn kalaha_ponds(game: *const KalahaGame, side: u32, ponds: *mut u32) -> KalahaStatus {
+    let game = match game.as_ref() {
+        Some(game) if !ponds.is_null() => game,
+        _ => return KalahaStatus::NullPointer,
+    };
+    match player(side) {
+        Ok(player) => {
+            ptr::copy_nonoverlapping(game.game.ponds(&player).as_ptr(), ponds, 6);
+            KalahaStatus::Ok
+        },
+        Err(status) => status,
+    }
+}
+
+/// The seeds in the player's bank, or 0 for a NULL game or unknown player.
+///
+/// # Safety
+/// game must be NULL or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn kalaha_bank(game: *const KalahaGame, side: u32) -> u32 {
+    match (game.as_ref(), player(side)) {
+        (Some(game), Ok(player)) => game.game.bank(&player),
+        _ => 0,
+    }
+}
+
+/// KALAHA_PLAYER_A or KALAHA_PLAYER_B, or KALAHA_PLAYER_NONE once the game has finished.
+///
+/// # Safety
+/// game must be NULL or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn kalaha_current_player(game: *const KalahaGame) -> u32 {
+    match game.as_ref() {
+        Some(game) if !game.game.is_finished() => player_code(game.game.current_player()),
+        _ => KALAHA_PLAYER_NONE,
+    }
+}
+
+/// Whether the game has finished. A NULL game counts as finished.
+///
+/// # Safety
+/// game must be NULL or a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn kalaha_is_finished(game: *const KalahaGame) -> bool {
+    game.as_ref().is_none_or(|game| game.game.is_finished())
+}
+
+/// Fills in result once the game has finished.
+///
+/// # Safety
+/// game must be NULL or a live handle, and result NULL or writable.
+#[no_mangle]
+pub unsafe extern "C" fn kalaha_result(game: *const KalahaGame, result: *mut KalahaResult) -> KalahaStatus {
+    let (game, result) = match (game.as_ref(), result.as_mut()) {
+        (Some(game), Some(result)) => (game, result),
+        _ => return KalahaStatus::NullPointer,
+    };
+    if !game.game.is_finished() {
+        return KalahaStatus::NotFinished;
+    }
+    *result = match *game.game.game_result() {
+        GameResult::Winner { ref player, score_a, score_b, .. } =>
+            KalahaResult { winner: player_code(player), score_a, score_b, forfeit: false },
+        GameResult::Draw { score, .. } =>
+            KalahaResult { winner: KALAHA_PLAYER_NONE, score_a: score, score_b: score, forfeit: false },
+        GameResult::Forfeit { ref player, score_a, score_b } =>
+            KalahaResult { winner: player_code(&player.next()), score_a, score_b, forfeit: true },
+    };
+    KalahaStatus::Ok
+}
+
+/// Asks an AI ("first", "last", "minmax", "alphabeta" or "caching") searching to depth for
+/// a move, without making it.
+///
+/// # Safety
+/// game must be NULL or a live handle, ai NULL or a NUL terminated string and mv NULL or
+/// writable.
+#[no_mangle]
+pub unsafe extern "C" fn kalaha_ai_move(game: *const KalahaGame, ai: *const c_char, depth: u32, mv: *mut KalahaMove)
+    -> KalahaStatus
+{
+    let (game, mv) = match (game.as_ref(), mv.as_mut()) {
+        (Some(game), Some(mv)) => (game, mv),
+        _ => return KalahaStatus::NullPointer,
+    };
+    let ai = match c_str(ai).map(|name| ai::by_name(name, depth.max(1))) {
+        Ok(Some(ai)) => ai,
+        Ok(None) => return KalahaStatus::InvalidArgument,
+        Err(status) => return status,
+    };
+    match ai.choose(&game.game) {
+        Ok(chosen) => {
+            *mv = KalahaMove::from_move(chosen);
+            KalahaStatus::Ok
+        },
+        Err(ai::AIError::GameFinished) => KalahaStatus::GameFinished,
+        Err(_) => KalahaStatus::AiFailed,
+    }
+}
+
+/// A description of a status, which lives as long as the program. Values that aren't a
+/// KalahaStatus get "unknown status".
+#[no_mangle]
+pub extern "C" fn kalaha_status_message(status: u32) -> *const c_char {
+    let status = match KalahaStatus::from_u32(status) {
+        Some(status) => status,
+        None => return b"unknown status\0".as_ptr() as *const c_char,
+    };
+    let message: &'static [u8] = match status {
+        KalahaStatus::Ok => b"ok\0",
+        KalahaStatus::EmptyPool => b"the pond is empty\0",
+        KalahaStatus::InvalidIndex => b"there is no such pond\0",
+        KalahaStatus::GameFinished => b"the game has finished\0",
+        KalahaStatus::MustFeed => b"the move must give the opponent seeds\0",
+        KalahaStatus::CannotSwap => b"swapping is not allowed now\0",
+        KalahaStatus::WrongDirection => b"the rules don't allow sowing that way\0",
+        KalahaStatus::NotImplemented => b"not implemented\0",
+        KalahaStatus::NullPointer => b"a pointer argument was NULL\0",
+        KalahaStatus::InvalidArgument => b"an argument was out of range\0",
+        KalahaStatus::NotFinished => b"the game has not finished\0",
+        KalahaStatus::AiFailed => b"the AI found no move\0",
+    };
+    message.as_ptr() as *const c_char
+}
@@ -0,0 +1,47 @@
+use super::*;
+use std::ffi::CString;
+
+#[test]
+fn moves_round_trip() {
+    let moves = [
+        Move::Pond(3),
+        Move::Directed(0, Direction::Clockwise),
+        Move::Directed(5, Direction::CounterClockwise),
+        Move::Swap,
+    ];
+    for &mv in &moves {
+        assert_eq!(KalahaMove::from_move(mv).to_move(), Ok(mv));
+    }
+    assert_eq!(KalahaMove { kind: 4, pond: 0 }.to_move(), Err(KalahaStatus::InvalidArgument));
+}
+
+#[test]
+fn statuses_from_errors() {
+    assert_eq!(KalahaStatus::from(Error::MustFeed), KalahaStatus::MustFeed);
+    assert_eq!(KalahaStatus::from(Error::EmptyPool) as u32, 1);
+}
+
+#[test]
+fn game_handle() {
+    let rules = CString::new("oware").unwrap();
+    unsafe {
+        let game = kalaha_with_rules(rules.as_ptr());
+        let mut ponds = [0; 6];
+        assert_eq!(kalaha_ponds(game, KALAHA_PLAYER_B, ponds.as_mut_ptr()), KalahaStatus::Ok);
+        assert_eq!(ponds, [4; 6]);
+        let mut outcome = KalahaOutcome { extra_turn: true, captured: 9, laps: 9 };
+        assert_eq!(kalaha_choose(game, KalahaMove { kind: KALAHA_MOVE_POND, pond: 2 }, &mut outcome), KalahaStatus::Ok);
+        assert_eq!(outcome, KalahaOutcome { extra_turn: false, captured: 0, laps: 1 });
+        assert_eq!(kalaha_current_player(game), KALAHA_PLAYER_B);
+        kalaha_free(game);
+    }
+}
+
+#[test]
+fn status_messages() {
+    let message = |status| unsafe { CStr::from_ptr(kalaha_status_message(status)) }.to_str().unwrap();
+    assert_eq!(message(KalahaStatus::MustFeed as u32), "the move must give the opponent seeds");
+    assert_eq!(message(KalahaStatus::AiFailed as u32), "the AI found no move");
+    assert_eq!(message(12), "unknown status");
+    assert_eq!(message(u32::MAX), "unknown status");
+}
@@ -0,0 +1,433 @@
+// A REST style API over HTTP for web frontends, with JSON bodies both ways:
+//
+//   POST /games                 {"rules": "kalah pie", "setup": "...", "any_total": false}
+//                               starts a game, every field optional, and returns it
+//   GET  /games/<id>            the game: its rules, board, turn, legal moves and setup
+//   POST /games/<id>/moves      {"move": "3cw"} makes a move, returning what it did and the game
+//   POST /games/<id>/ai         {"ai": "alphabeta", "depth": 6} or {"ai": ..., "movetime": 500}
+//                               asks an AI for a move, and makes it too with "play": true. Depth
+//                               goes up to 16, or 9 for minmax and caching which don't prune, and
+//                               movetime to 60000.
+//   GET  /games/<id>/history    where the game started and every move made since
+//
+// Boards, turns and results are shown as in json::ToJson. Anything that goes wrong is
+// answered with {"error": "<reason>"}, where an illegal move's reason is the Error's name.
+// The API keeps the last 10000 games started, forgetting the oldest to make room, and
+// turns away connections past 256 at a time with a 503.
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::ai::{self, AIError};
+use super::engine::{deepen, SearchLimit};
+use super::json::{Json, ToJson};
+use super::kalaha::Kalaha;
+use super::moves::Move;
+use super::player::Player;
+use super::rules::Rules;
+use super::setup::{Setup, SetupError};
+use super::turn::MoveOutcome;
+
+#[cfg(test)]
+mod tests;
+
+// depth an AI searches to when not given a depth or time
+const DEFAULT_DEPTH: u32 = 6;
+
+// biggest request body read, far more than any request needs
+const MAX_BODY: usize = 64 * 1024;
+
+// biggest request line and headers read, together
+const MAX_HEAD: u64 = 8 * 1024;
+
+// how long a client may keep a connection waiting for the rest of its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+// deepest search and longest think a request may ask an AI for, where AIs that don't prune
+// stop sooner as each level costs them as much as all the ones before put together
+const MAX_DEPTH: i64 = 16;
+const MAX_UNPRUNED_DEPTH: i64 = 9;
+const MAX_MOVETIME: i64 = 60_000;
+
+// games kept before the oldest are forgotten
+const MAX_GAMES: usize = 10_000;
+
+// connections served at once, past which they're turned away
+const MAX_CONNECTIONS: usize = 256;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub body: Json,
+}
+
+impl Response {
+    fn ok(body: Json) -> Response {
+        Response { status: 200, body }
+    }
+
+    fn error(status: u16, reason: &str) -> Response {
+        Response { status, body: Json::object(vec![("error", Json::from(reason))]) }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            201 => "Created",
+            204 => "No Content",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            409 => "Conflict",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            422 => "Unprocessable Entity",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+// A move made through the API
+struct Played {
+    player: Player,
+    mv: Move,
+    outcome: MoveOutcome,
+}
+
+struct Record {
+    start: Kalaha,
+    game: Kalaha,
+    history: Vec<Played>,
+}
+
+impl Record {
+    fn play(&mut self, mv: Move) -> MoveOutcome {
+        let player = self.game.current_player().clone();
+        let outcome = self.game.choose(mv);
+        self.history.push(Played { player, mv, outcome: outcome.clone() });
+        outcome
+    }
+}
+
+// the game with its id first
+fn game_json(id: u64, game: &Kalaha) -> Json {
+    let mut fields = vec![("id".to_string(), Json::Number(id as i64))];
+    if let Json::Object(game) = game.to_json() {
+        fields.extend(game);
+    }
+    Json::Object(fields)
+}
+
+// The games and what can be done with them, independent of HTTP
+pub struct Api {
+    // by id, which counts up so the first is the oldest
+    games: Mutex<BTreeMap<u64, Record>>,
+    next_id: Mutex<u64>,
+    max_games: usize,
+}
+
+impl Default for Api {
+    fn default() -> Api {
+        Api::new()
+    }
+}
+
+impl Api {
+    pub fn new() -> Api {
+        Api { games: Mutex::new(BTreeMap::new()), next_id: Mutex::new(0), max_games: MAX_GAMES }
+    }
+
+    // how many games to keep before forgetting the oldest, MAX_GAMES by default
+    pub fn with_max_games(self, max_games: usize) -> Api {
+        Api { max_games: max_games.max(1), ..self }
+    }
+
+    // Answers a request, where body is empty or JSON
+    pub fn handle(&self, method: &str, path: &str, body: &str) -> Response {
+        let body = match body.trim() {
+            "" => Json::Object(Vec::new()),
+            body => match Json::parse(body) {
+                Ok(body) => body,
+                Err(reason) => return Response::error(400, &format!("invalid JSON: {}", reason)),
+            },
+        };
+        let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+        let id = match segments[..] {
+            ["games"] => return match method {
+                "POST" => self.create(&body),
+                _ => Response::error(405, "use POST to start a game"),
+            },
+            ["games", id, ..] => match id.parse::<u64>() {
+                Ok(id) => id,
+                Err(_) => return Response::error(404, &format!("no game {}", id)),
+            },
+            _ => return Response::error(404, &format!("nothing at {}", path)),
+        };
+        match (method, &segments[2..]) {
+            ("GET", []) => self.with_game(id, |record| Response::ok(game_json(id, &record.game))),
+            ("POST", ["moves"]) => self.make_move(id, &body),
+            ("POST", ["ai"]) => self.ask_ai(id, &body),
+            ("GET", ["history"]) => self.with_game(id, |record| Response::ok(history_json(id, record))),
+            (_, []) | (_, ["history"]) => Response::error(405, "use GET"),
+            (_, ["moves"]) | (_, ["ai"]) => Response::error(405, "use POST"),
+            _ => Response::error(404, &format!("nothing at {}", path)),
+        }
+    }
+
+    fn with_game<F: FnOnce(&mut Record) -> Response>(&self, id: u64, f: F) -> Response {
+        match self.games.lock().unwrap().get_mut(&id) {
+            Some(record) => f(record),
+            None => Response::error(404, &format!("no game {}", id)),
+        }
+    }
+
+    fn create(&self, body: &Json) -> Response {
+        let game = match new_game(body) {
+            Ok(game) => game,
+            Err(reason) => return Response::error(400, &reason),
+        };
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id
+        };
+        let json = game_json(id, &game);
+        let mut games = self.games.lock().unwrap();
+        while games.len() >= self.max_games {
+            let oldest = *games.keys().next().expect("there are games");
+            games.remove(&oldest);
+        }
+        games.insert(id, Record { start: game.clone(), game, history: Vec::new() });
+        Response { status: 201, body: json }
+    }
+
+    fn make_move(&self, id: u64, body: &Json) -> Response {
+        let mv = match body.get("move").and_then(Json::as_str).map(str::parse::<Move>) {
+            Some(Ok(mv)) => mv,
+            Some(Err(reason)) => return Response::error(400, &reason),
+            None => return Response::error(400, "expected {\"move\": \"<move>\"}"),
+        };
+        self.with_game(id, |record| {
+            if let Err(error) = record.game.valid_move(mv) {
+                return Response::error(422, error.name());
+            }
+            let outcome = record.play(mv);
+            Response::ok(Json::object(vec![("outcome", outcome.to_json()), ("game", game_json(id, &record.game))]))
+        })
+    }
+
+    fn ask_ai(&self, id: u64, body: &Json) -> Response {
+        let name = body.get("ai").and_then(Json::as_str).unwrap_or("alphabeta");
+        if ai::by_name(name, 1).is_none() {
+            return Response::error(400, &format!("unknown AI '{}'", name));
+        }
+        let max_depth = match name {
+            "minmax" | "caching" => MAX_UNPRUNED_DEPTH,
+            _ => MAX_DEPTH,
+        };
+        let limit = match (body.get("depth").map(Json::as_i64), body.get("movetime").map(Json::as_i64)) {
+            (None, None) => SearchLimit::Depth(DEFAULT_DEPTH),
+            (Some(Some(depth)), None) if depth > 0 && depth <= max_depth => SearchLimit::Depth(depth as u32),
+            (None, Some(Some(time))) if time > 0 && time <= MAX_MOVETIME =>
+                SearchLimit::MoveTime(Duration::from_millis(time as u64)),
+            _ => return Response::error(400, &format!(
+                "expected a depth from 1 to {} or a movetime from 1 to {} ms, but not both", max_depth, MAX_MOVETIME
+            )),
+        };
+        let play = body.get("play").and_then(Json::as_bool).unwrap_or(false);
+        // search without holding the lock, so other games carry on meanwhile
+        let (game, moves) = {
+            let games = self.games.lock().unwrap();
+            match games.get(&id) {
+                Some(record) => (record.game.clone(), record.history.len()),
+                None => return Response::error(404, &format!("no game {}", id)),
+            }
+        };
+        let mut searched = 0;
+        let make_ai = |depth| ai::by_name(name, depth).unwrap();
+        let analysis = match deepen(&make_ai, &game, limit, None, &mut |depth, _, _| {
+            searched = depth;
+            Ok(())
+        }) {
+            Ok(Ok(analysis)) => analysis,
+            Ok(Err(AIError::GameFinished)) => return Response::error(422, "game_finished"),
+            Ok(Err(error)) => return Response::error(422, &error.to_string()),
+            Err(error) => return Response::error(500, &error.to_string()),
+        };
+        let mut fields = vec![
+            ("move", Json::from(analysis.mv.to_string())),
+            ("score", Json::from(analysis.score)),
+            ("depth", Json::from(if analysis.score.is_some() { Some(searched) } else { None })),
+        ];
+        if !play {
+            return Response::ok(Json::object(fields));
+        }
+        self.with_game(id, |record| {
+            if record.history.len() != moves {
+                return Response::error(409, "the game moved on during the search");
+            }
+            let outcome = record.play(analysis.mv);
+            fields.push(("outcome", outcome.to_json()));
+            fields.push(("game", game_json(id, &record.game)));
+            Response::ok(Json::object(fields))
+        })
+    }
+}
+
+fn new_game(body: &Json) -> Result<Kalaha, String> {
+    let rules = match body.get("rules") {
+        None => Rules::default(),
+        Some(rules) => rules.as_str().ok_or("rules should be a string")?.parse()?,
+    };
+    let setup = match body.get("setup") {
+        None | Some(Json::Null) => return Ok(Kalaha::with_rules(rules)),
+        Some(setup) => setup.as_str().ok_or("setup should be a string")?
+            .parse::<Setup>()
+            .map_err(|error| error.to_string())?,
+    };
+    let game = if body.get("any_total").and_then(Json::as_bool).unwrap_or(false) {
+        Kalaha::from_setup_any_total(rules, &setup)
+    } else {
+        Kalaha::from_setup(rules, &setup)
+    };
+    game.map_err(|error: SetupError| error.to_string())
+}
+
+fn history_json(id: u64, record: &Record) -> Json {
+    let moves = record.history.iter()
+        .map(|played| Json::object(vec![
+            ("player", played.player.to_json()),
+            ("move", Json::from(played.mv.to_string())),
+            ("outcome", played.outcome.to_json()),
+        ]))
+        .collect();
+    Json::object(vec![
+        ("id", Json::Number(id as i64)),
+        ("rules", Json::from(record.start.rules().to_string())),
+        ("start", Json::from(record.start.setup().map(|setup| setup.to_string()))),
+        ("moves", Json::Array(moves)),
+    ])
+}
+
+// Serves the Api over HTTP/1.1, one connection per request
+pub struct HttpServer {
+    listener: TcpListener,
+    api: Arc<Api>,
+    connections: Arc<AtomicUsize>,
+}
+
+// Counts a connection as open until dropped
+struct Open(Arc<AtomicUsize>);
+
+impl Drop for Open {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl HttpServer {
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<HttpServer> {
+        Ok(HttpServer {
+            listener: TcpListener::bind(addr)?,
+            api: Arc::new(Api::new()),
+            connections: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // Answers requests forever, each connection in its own thread up to MAX_CONNECTIONS
+    pub fn run(&self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(error) => {
+                    eprintln!("Couldn't accept a connection: {}", error);
+                    continue;
+                },
+            };
+            let others = self.connections.fetch_add(1, Ordering::SeqCst);
+            let open = Open(Arc::clone(&self.connections));
+            if others >= MAX_CONNECTIONS {
+                // a short answer fits in the socket's buffer, so this doesn't wait on the client
+                let _ = write_response(&mut stream, &Response::error(503, "too many connections"));
+                continue;
+            }
+            let api = Arc::clone(&self.api);
+            thread::spawn(move || {
+                let _open = open;
+                // the client going away mid request only matters to them
+                let _ = serve_connection(&api, stream);
+            });
+        }
+        Ok(())
+    }
+}
+
+fn serve_connection(api: &Api, stream: TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+    let mut head = (&mut reader).take(MAX_HEAD);
+    let mut request_line = String::new();
+    head.read_line(&mut request_line)?;
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if head.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some(colon) = header.find(':') {
+            if header[..colon].trim().eq_ignore_ascii_case("content-length") {
+                content_length = header[colon + 1..].trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    // ran out before the blank line ending the headers
+    let head_too_large = head.limit() == 0;
+    let words: Vec<&str> = request_line.split_whitespace().collect();
+    let response = match words[..] {
+        _ if head_too_large => Response::error(431, "request line and headers too large"),
+        [_, _, _] if content_length > MAX_BODY => Response::error(413, "request body too large"),
+        // browsers check they may call the API from another origin first
+        ["OPTIONS", _, _] => Response { status: 204, body: Json::Null },
+        [method, target, _] => {
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body)?;
+            let path = target.split('?').next().unwrap();
+            match String::from_utf8(body) {
+                Ok(body) => api.handle(method, path, &body),
+                Err(_) => Response::error(400, "body is not UTF-8"),
+            }
+        },
+        _ => Response::error(400, "expected an HTTP request line"),
+    };
+    write_response(&mut writer, &response)?;
+    // read whatever of a refused request is left, as closing with it unread would reset the
+    // connection and could lose the response
+    writer.shutdown(Shutdown::Write)?;
+    io::copy(&mut reader.take(MAX_HEAD + MAX_BODY as u64), &mut io::sink())?;
+    Ok(())
+}
+
+fn write_response<W: Write>(writer: &mut W, response: &Response) -> io::Result<()> {
+    let body = match response.body {
+        Json::Null => String::new(),
+        ref body => body.to_string(),
+    };
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\nAccess-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+         Access-Control-Allow-Headers: Content-Type\r\nConnection: close\r\n\r\n{}",
+        response.status, response.reason(), body.len(), body
+    )?;
+    writer.flush()
+}
@@ -0,0 +1,156 @@
+use super::*;
+use ai::AI;
+
+fn created(api: &Api, body: &str) -> u64 {
+    let response = api.handle("POST", "/games", body);
+    assert_eq!(response.status, 201, "{}", response.body);
+    response.body.get("id").and_then(Json::as_i64).unwrap() as u64
+}
+
+fn error(response: &Response) -> (u16, &str) {
+    (response.status, response.body.get("error").and_then(Json::as_str).unwrap())
+}
+
+#[test]
+fn create_and_fetch() {
+    let api = Api::new();
+    let id = created(&api, r#"{"rules": "oware"}"#);
+    let response = api.handle("GET", &format!("/games/{}", id), "");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body.get("rules"), Some(&Json::from("oware")));
+    assert_eq!(response.body.get("turn"), Some(&Kalaha::with_rules(Rules::oware()).turn().to_json()));
+    assert_ne!(created(&api, ""), id);
+}
+
+#[test]
+fn create_from_setup() {
+    let api = Api::new();
+    let id = created(&api, r#"{"setup": "0,0,0,0,0,1/0/1,0,0,0,0,0/0/B", "any_total": true}"#);
+    let game = api.handle("GET", &format!("/games/{}/", id), "").body;
+    assert_eq!(game.get("setup"), Some(&Json::from("0,0,0,0,0,1/0/1,0,0,0,0,0/0/B")));
+    assert_eq!(game.get("legal_moves"), Some(&Json::Array(vec![Json::from("0")])));
+    assert_eq!(
+        error(&api.handle("POST", "/games", r#"{"setup": "0,0,0,0,0,1/0/1,0,0,0,0,0/0/B"}"#)).0,
+        400
+    );
+    assert_eq!(
+        error(&api.handle("POST", "/games", r#"{"rules": "chess"}"#)),
+        (400, "unknown rules 'chess'")
+    );
+    let huge = r#"{"setup": "4294967295,1,0,0,0,0/0/0,0,0,0,0,0/0/A", "any_total": true}"#;
+    assert_eq!(
+        error(&api.handle("POST", "/games", huge)),
+        (400, "Setup has 4294967295 seeds, at most 255 fit on the board")
+    );
+}
+
+#[test]
+fn moves_and_history() {
+    let api = Api::new();
+    let id = created(&api, r#"{"rules": "kalah pie"}"#);
+    let moves = format!("/games/{}/moves", id);
+    let response = api.handle("POST", &moves, r#"{"move": "0"}"#);
+    assert_eq!(response.body.get("outcome").and_then(|outcome| outcome.get("extra_turn")), Some(&Json::Bool(true)));
+    api.handle("POST", &moves, r#"{"move": "1"}"#);
+    let response = api.handle("POST", &moves, r#"{"move": "swap"}"#);
+    assert_eq!(response.body.get("game").and_then(|game| game.get("swapped")), Some(&Json::Bool(true)));
+    let history = api.handle("GET", &format!("/games/{}/history", id), "").body;
+    assert_eq!(history.get("start"), Some(&Json::from("6,6,6,6,6,6/0/6,6,6,6,6,6/0/A")));
+    let played: Vec<_> = match history.get("moves") {
+        Some(Json::Array(moves)) => moves.iter()
+            .map(|mv| (mv.get("player").unwrap().as_str().unwrap(), mv.get("move").unwrap().as_str().unwrap()))
+            .collect(),
+        _ => panic!("no moves in {}", history),
+    };
+    assert_eq!(played, vec![("A", "0"), ("A", "1"), ("B", "swap")]);
+}
+
+#[test]
+fn illegal_moves() {
+    let api = Api::new();
+    let id = created(&api, "");
+    let moves = format!("/games/{}/moves", id);
+    assert_eq!(error(&api.handle("POST", &moves, r#"{"move": "9"}"#)), (422, "invalid_index"));
+    assert_eq!(error(&api.handle("POST", &moves, r#"{"move": "swap"}"#)), (422, "cannot_swap"));
+    assert_eq!(error(&api.handle("POST", &moves, r#"{"move": "north"}"#)).0, 400);
+    assert_eq!(error(&api.handle("POST", &moves, r#"{"pond": 3}"#)).0, 400);
+    assert_eq!(error(&api.handle("POST", &moves, "{\"move\": ")).0, 400);
+    let history = api.handle("GET", &format!("/games/{}/history", id), "").body;
+    assert_eq!(history.get("moves"), Some(&Json::Array(vec![])));
+}
+
+#[test]
+fn ai_moves() {
+    let api = Api::new();
+    let id = created(&api, "");
+    let path = format!("/games/{}/ai", id);
+    let expected = ai::AlphaBeta::new(4).analyse(&Kalaha::new()).unwrap();
+    let response = api.handle("POST", &path, r#"{"ai": "alphabeta", "depth": 4}"#);
+    assert_eq!(response, Response::ok(Json::object(vec![
+        ("move", Json::from(expected.mv.to_string())),
+        ("score", Json::from(expected.score)),
+        ("depth", Json::from(4)),
+    ])));
+    let response = api.handle("POST", &path, r#"{"ai": "first", "play": true}"#);
+    assert_eq!(response.body.get("move"), Some(&Json::from("0")));
+    assert_eq!(response.body.get("depth"), Some(&Json::Null));
+    let history = api.handle("GET", &format!("/games/{}/history", id), "").body;
+    assert!(matches!(history.get("moves"), Some(Json::Array(moves)) if moves.len() == 1));
+    let response = api.handle("POST", &path, r#"{"movetime": 20}"#);
+    assert!(response.body.get("depth").and_then(Json::as_i64).unwrap() >= 1);
+}
+
+#[test]
+fn ai_errors() {
+    let api = Api::new();
+    let id = created(&api, r#"{"setup": "0,0,0,0,0,1/0/0,0,0,0,0,1/0/A", "any_total": true}"#);
+    let path = format!("/games/{}/ai", id);
+    assert_eq!(error(&api.handle("POST", &path, r#"{"ai": "oracle"}"#)), (400, "unknown AI 'oracle'"));
+    assert_eq!(error(&api.handle("POST", &path, r#"{"depth": 0}"#)).0, 400);
+    assert_eq!(
+        error(&api.handle("POST", &path, r#"{"depth": 4294967297}"#)),
+        (400, "expected a depth from 1 to 16 or a movetime from 1 to 60000 ms, but not both")
+    );
+    // AIs that don't prune can't be asked to go as deep
+    assert_eq!(
+        error(&api.handle("POST", &path, r#"{"ai": "minmax", "depth": 16}"#)),
+        (400, "expected a depth from 1 to 9 or a movetime from 1 to 60000 ms, but not both")
+    );
+    assert_eq!(error(&api.handle("POST", &path, r#"{"movetime": 3600000}"#)).0, 400);
+    assert_eq!(error(&api.handle("POST", &path, r#"{"depth": 2, "movetime": 5}"#)).0, 400);
+    api.handle("POST", &path, r#"{"play": true}"#);
+    assert_eq!(error(&api.handle("POST", &path, "")), (422, "game_finished"));
+}
+
+#[test]
+fn oldest_games_are_forgotten() {
+    let api = Api::new().with_max_games(2);
+    let ids: Vec<u64> = (0..3).map(|_| created(&api, "")).collect();
+    assert_eq!(error(&api.handle("GET", &format!("/games/{}", ids[0]), "")).0, 404);
+    for id in &ids[1..] {
+        assert_eq!(api.handle("GET", &format!("/games/{}", id), "").status, 200);
+    }
+}
+
+#[test]
+fn unknown_routes() {
+    let api = Api::new();
+    let id = created(&api, "");
+    assert_eq!(error(&api.handle("GET", "/games/7", "")), (404, "no game 7"));
+    assert_eq!(error(&api.handle("GET", "/games/x", "")), (404, "no game x"));
+    assert_eq!(error(&api.handle("GET", "/", "")).0, 404);
+    assert_eq!(error(&api.handle("GET", "/games", "")).0, 405);
+    assert_eq!(error(&api.handle("DELETE", &format!("/games/{}", id), "")).0, 405);
+    assert_eq!(error(&api.handle("GET", &format!("/games/{}/moves", id), "")).0, 405);
+    assert_eq!(error(&api.handle("GET", &format!("/games/{}/board", id), "")).0, 404);
+}
+
+#[test]
+fn responses() {
+    let mut written = Vec::new();
+    write_response(&mut written, &Response::error(404, "no game 7")).unwrap();
+    let written = String::from_utf8(written).unwrap();
+    assert!(written.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    assert!(written.contains("Content-Length: 21\r\n"));
+    assert!(written.ends_with("\r\n\r\n{\"error\":\"no game 7\"}"));
+}
@@ -0,0 +1,409 @@
+// Just enough JSON for the HTTP API, so the crate doesn't need any more dependencies.
+// Numbers are whole, which is all the game ever needs.
+use std::fmt;
+
+use super::board::Board;
+use super::kalaha::Kalaha;
+use super::player::Player;
+use super::turn::{Turn, GameResult, MoveOutcome};
+
+#[cfg(test)]
+mod tests;
+
+// How deeply arrays and objects may nest, so a body of brackets can't exhaust the stack
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(i64),
+    String(String),
+    Array(Vec<Json>),
+    // in the order the keys were written
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    // An object from its keys and values
+    pub fn object(fields: Vec<(&str, Json)>) -> Json {
+        Json::Object(fields.into_iter().map(|(key, value)| (key.to_string(), value)).collect())
+    }
+
+    // The value of key, if this is an object with it
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref fields) => fields.iter().find(|&(k, _)| k == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::String(ref string) => Some(string),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Json::Number(number) => Some(number),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self {
+            Json::Bool(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Json, String> {
+        let mut parser = Parser { text: text.as_bytes(), at: 0, depth: 0 };
+        let value = parser.value()?;
+        parser.whitespace();
+        if parser.at != text.len() {
+            return Err(format!("unexpected text at {}", parser.at));
+        }
+        Ok(value)
+    }
+}
+
+impl<'a> From<&'a str> for Json {
+    fn from(string: &'a str) -> Json {
+        Json::String(string.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(string: String) -> Json {
+        Json::String(string)
+    }
+}
+
+impl From<u32> for Json {
+    fn from(number: u32) -> Json {
+        Json::Number(i64::from(number))
+    }
+}
+
+impl From<i32> for Json {
+    fn from(number: i32) -> Json {
+        Json::Number(i64::from(number))
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Json {
+        Json::Bool(value)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(value: Option<T>) -> Json {
+        value.map_or(Json::Null, Into::into)
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter, string: &str) -> Result<(), fmt::Error> {
+    f.write_str("\"")?;
+    for c in string.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+// Compact JSON, with no whitespace
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(number) => write!(f, "{}", number),
+            Json::String(ref string) => write_string(f, string),
+            Json::Array(ref values) => {
+                f.write_str("[")?;
+                for (idx, value) in values.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                f.write_str("]")
+            },
+            Json::Object(ref fields) => {
+                f.write_str("{")?;
+                for (idx, (key, value)) in fields.iter().enumerate() {
+                    if idx > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                f.write_str("}")
+            },
+        }
+    }
+}
+
+struct Parser<'a> {
+    text: &'a [u8],
+    at: usize,
+    // arrays and objects open around the current value
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn whitespace(&mut self) {
+        while self.at < self.text.len() && (self.text[self.at] as char).is_ascii_whitespace() {
+            self.at += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.whitespace();
+        self.text.get(self.at).cloned()
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.at += 1;
+                Ok(())
+            },
+            _ => Err(format!("expected '{}' at {}", expected as char, self.at)),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, String> {
+        if self.text[self.at..].starts_with(word.as_bytes()) {
+            self.at += word.len();
+            Ok(value)
+        } else {
+            Err(format!("unexpected text at {}", self.at))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        match self.peek() {
+            Some(b'n') => self.literal("null", Json::Null),
+            Some(b't') => self.literal("true", Json::Bool(true)),
+            Some(b'f') => self.literal("false", Json::Bool(false)),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'[') | Some(b'{') if self.depth == MAX_DEPTH => Err(format!("nested too deeply at {}", self.at)),
+            Some(b'[') => self.nested(Parser::array),
+            Some(b'{') => self.nested(Parser::object),
+            Some(b'-') | Some(b'0'..=b'9') => self.number(),
+            Some(_) => Err(format!("unexpected text at {}", self.at)),
+            None => Err("unexpected end of JSON".to_string()),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Parser<'a>) -> Result<Json, String>) -> Result<Json, String> {
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn number(&mut self) -> Result<Json, String> {
+        let start = self.at;
+        if self.text[self.at] == b'-' {
+            self.at += 1;
+        }
+        while self.at < self.text.len() && self.text[self.at].is_ascii_digit() {
+            self.at += 1;
+        }
+        let number = String::from_utf8_lossy(&self.text[start..self.at]);
+        number.parse().map(Json::Number).map_err(|_| format!("'{}' is not a whole number", number))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            let c = *self.text.get(self.at).ok_or("unterminated string")?;
+            self.at += 1;
+            match c {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = *self.text.get(self.at).ok_or("unterminated string")?;
+                    self.at += 1;
+                    let c = match escaped {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'u' => {
+                            let hex = self.text.get(self.at..self.at + 4).ok_or("unterminated string")?;
+                            self.at += 4;
+                            u32::from_str_radix(&String::from_utf8_lossy(hex), 16).ok()
+                                .and_then(::std::char::from_u32)
+                                .ok_or_else(|| format!("bad escape at {}", self.at - 6))?
+                        },
+                        _ => return Err(format!("bad escape at {}", self.at - 2)),
+                    };
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+                },
+                c => bytes.push(c),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| "string is not UTF-8".to_string())
+    }
+
+    fn array(&mut self) -> Result<Json, String> {
+        self.expect(b'[')?;
+        let mut values = Vec::new();
+        if self.peek() == Some(b']') {
+            self.at += 1;
+            return Ok(Json::Array(values));
+        }
+        loop {
+            values.push(self.value()?);
+            match self.peek() {
+                Some(b',') => self.at += 1,
+                Some(b']') => {
+                    self.at += 1;
+                    return Ok(Json::Array(values));
+                },
+                _ => return Err(format!("expected ',' or ']' at {}", self.at)),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Json, String> {
+        self.expect(b'{')?;
+        let mut fields = Vec::new();
+        if self.peek() == Some(b'}') {
+            self.at += 1;
+            return Ok(Json::Object(fields));
+        }
+        loop {
+            self.whitespace();
+            let key = self.string()?;
+            self.expect(b':')?;
+            fields.push((key, self.value()?));
+            match self.peek() {
+                Some(b',') => self.at += 1,
+                Some(b'}') => {
+                    self.at += 1;
+                    return Ok(Json::Object(fields));
+                },
+                _ => return Err(format!("expected ',' or '}}' at {}", self.at)),
+            }
+        }
+    }
+}
+
+// Game types as the HTTP API shows them
+pub trait ToJson {
+    fn to_json(&self) -> Json;
+}
+
+fn counts(counts: &[u32]) -> Json {
+    Json::Array(counts.iter().map(|&count| Json::from(count)).collect())
+}
+
+impl ToJson for Player {
+    // "A" or "B"
+    fn to_json(&self) -> Json {
+        match *self {
+            Player::A => Json::from("A"),
+            Player::B => Json::from("B"),
+        }
+    }
+}
+
+impl ToJson for Board {
+    // {"ponds_a": [6, 6, 6, 6, 6, 6], "bank_a": 0, "ponds_b": [...], "bank_b": 0}
+    fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("ponds_a", counts(&self.pond_counts(&Player::A))),
+            ("bank_a", Json::from(self.bank(&Player::A))),
+            ("ponds_b", counts(&self.pond_counts(&Player::B))),
+            ("bank_b", Json::from(self.bank(&Player::B))),
+        ])
+    }
+}
+
+impl ToJson for GameResult {
+    // {"winner": "A", "score_a": 40, "score_b": 32, "ending": "side_empty"}, with a null
+    // winner for a draw. A forfeit has the ending "forfeit" and says who "forfeited".
+    fn to_json(&self) -> Json {
+        match *self {
+            GameResult::Winner { ref player, score_a, score_b, ending } => Json::object(vec![
+                ("winner", player.to_json()),
+                ("score_a", Json::from(score_a)),
+                ("score_b", Json::from(score_b)),
+                ("ending", Json::from(ending.name())),
+            ]),
+            GameResult::Draw { score, ending } => Json::object(vec![
+                ("winner", Json::Null),
+                ("score_a", Json::from(score)),
+                ("score_b", Json::from(score)),
+                ("ending", Json::from(ending.name())),
+            ]),
+            GameResult::Forfeit { ref player, score_a, score_b } => Json::object(vec![
+                ("winner", player.next().to_json()),
+                ("score_a", Json::from(score_a)),
+                ("score_b", Json::from(score_b)),
+                ("ending", Json::from("forfeit")),
+                ("forfeited", player.to_json()),
+            ]),
+        }
+    }
+}
+
+impl ToJson for Turn {
+    // {"state": "move", "player": "A"}, {"state": "swap_offered", "player": "B"} or
+    // {"state": "finished", "result": {...}}
+    fn to_json(&self) -> Json {
+        match *self {
+            Turn::Player(ref player) => Json::object(vec![("state", Json::from("move")), ("player", player.to_json())]),
+            Turn::SwapOffered(ref player) =>
+                Json::object(vec![("state", Json::from("swap_offered")), ("player", player.to_json())]),
+            Turn::Finished(ref result) =>
+                Json::object(vec![("state", Json::from("finished")), ("result", result.to_json())]),
+        }
+    }
+}
+
+impl ToJson for MoveOutcome {
+    fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("turn", self.turn.to_json()),
+            ("extra_turn", Json::from(self.extra_turn)),
+            ("captured", Json::from(self.captured)),
+            ("laps", Json::from(self.laps)),
+        ])
+    }
+}
+
+impl ToJson for Kalaha {
+    // The rules, board and turn, with the legal moves and the position as a Setup while the
+    // game is going
+    fn to_json(&self) -> Json {
+        Json::object(vec![
+            ("rules", Json::from(self.rules().to_string())),
+            ("board", self.board().to_json()),
+            ("turn", self.turn().to_json()),
+            ("swapped", Json::from(self.swapped())),
+            ("legal_moves", Json::Array(self.legal_moves().iter().map(|mv| Json::from(mv.to_string())).collect())),
+            ("setup", Json::from(self.setup().map(|setup| setup.to_string()))),
+        ])
+    }
+}
@@ -0,0 +1,102 @@
+use super::*;
+use moves::Move;
+use rules::Rules;
+use turn::Ending;
+
+#[test]
+fn parse() {
+    assert_eq!(
+        Json::parse(r#" {"rules": "oware pie", "depth": -6, "play": true, "setup": null, "moves": ["1", "swap"]} "#),
+        Ok(Json::object(vec![
+            ("rules", Json::from("oware pie")),
+            ("depth", Json::Number(-6)),
+            ("play", Json::Bool(true)),
+            ("setup", Json::Null),
+            ("moves", Json::Array(vec![Json::from("1"), Json::from("swap")])),
+        ]))
+    );
+    assert_eq!(Json::parse(r#""a\"\\\né""#), Ok(Json::from("a\"\\\né")));
+    assert_eq!(Json::parse("[]"), Ok(Json::Array(vec![])));
+    assert_eq!(Json::parse("{}"), Ok(Json::Object(vec![])));
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!(Json::parse(""), Err("unexpected end of JSON".to_string()));
+    assert_eq!(Json::parse("[1 2]"), Err("expected ',' or ']' at 3".to_string()));
+    assert_eq!(Json::parse("{\"a\": 1} x"), Err("unexpected text at 9".to_string()));
+    assert_eq!(Json::parse("1.5"), Err("unexpected text at 1".to_string()));
+    assert_eq!(Json::parse("\"abc"), Err("unterminated string".to_string()));
+    assert_eq!(Json::parse("nul"), Err("unexpected text at 0".to_string()));
+}
+
+#[test]
+fn parse_nesting() {
+    let nested = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    assert!(Json::parse(&nested(MAX_DEPTH)).is_ok());
+    assert_eq!(Json::parse(&nested(MAX_DEPTH + 1)), Err("nested too deeply at 64".to_string()));
+    // far more than the stack would take
+    assert_eq!(Json::parse(&"[".repeat(60000)), Err("nested too deeply at 64".to_string()));
+    assert_eq!(Json::parse(&"{\"a\":".repeat(100)).unwrap_err(), "nested too deeply at 320");
+}
+
+#[test]
+fn display_round_trip() {
+    let json = Json::object(vec![
+        ("name", Json::from("tab\there \"quoted\" \u{1}")),
+        ("counts", Json::Array(vec![Json::Number(0), Json::Number(-3)])),
+        ("nested", Json::object(vec![("empty", Json::Array(vec![])), ("none", Json::Null)])),
+    ]);
+    let text = json.to_string();
+    assert_eq!(
+        text,
+        r#"{"name":"tab\there \"quoted\" \u0001","counts":[0,-3],"nested":{"empty":[],"none":null}}"#
+    );
+    assert_eq!(Json::parse(&text), Ok(json));
+}
+
+#[test]
+fn get() {
+    let json = Json::parse(r#"{"move": "3cw", "depth": 4, "play": false}"#).unwrap();
+    assert_eq!(json.get("move").and_then(Json::as_str), Some("3cw"));
+    assert_eq!(json.get("depth").and_then(Json::as_i64), Some(4));
+    assert_eq!(json.get("play").and_then(Json::as_bool), Some(false));
+    assert_eq!(json.get("missing"), None);
+    assert_eq!(Json::Null.get("move"), None);
+}
+
+#[test]
+fn game_types() {
+    let mut game = Kalaha::new();
+    assert_eq!(
+        game.to_json().to_string(),
+        concat!(
+            r#"{"rules":"kalah","board":{"ponds_a":[6,6,6,6,6,6],"bank_a":0,"ponds_b":[6,6,6,6,6,6],"bank_b":0},"#,
+            r#""turn":{"state":"move","player":"A"},"swapped":false,"legal_moves":["0","1","2","3","4","5"],"#,
+            r#""setup":"6,6,6,6,6,6/0/6,6,6,6,6,6/0/A"}"#
+        )
+    );
+    assert_eq!(
+        game.choose(Move::Pond(0)).to_json().to_string(),
+        r#"{"turn":{"state":"move","player":"A"},"extra_turn":true,"captured":0,"laps":1}"#
+    );
+    let mut game = Kalaha::with_rules(Rules::kalah().with_pie_rule(true));
+    game.choose(Move::Pond(1));
+    assert_eq!(game.turn().to_json().to_string(), r#"{"state":"swap_offered","player":"B"}"#);
+}
+
+#[test]
+fn results() {
+    assert_eq!(
+        Turn::new_finished(40, 32, Ending::SideEmpty).to_json().to_string(),
+        r#"{"state":"finished","result":{"winner":"A","score_a":40,"score_b":32,"ending":"side_empty"}}"#
+    );
+    assert_eq!(
+        Turn::new_finished(24, 24, Ending::NoMoves).game_result().to_json().to_string(),
+        r#"{"winner":null,"score_a":24,"score_b":24,"ending":"no_moves"}"#
+    );
+    assert_eq!(
+        GameResult::Forfeit { player: Player::A, score_a: 3, score_b: 1 }.to_json().to_string(),
+        r#"{"winner":"B","score_a":3,"score_b":1,"ending":"forfeit","forfeited":"A"}"#
+    );
+}
@@ -2,39 +2,269 @@ use std::fmt;
 
 use super::error::Error;
 use super::player::Player;
-use super::turn::{Turn, GameResult};
-use super::board::Board;
+use super::turn::{Turn, GameResult, MoveOutcome};
+use super::board::{self, Board, Sow, Transform};
 use super::ai;
+use super::moves::{self, Move, MoveList};
+use super::rng::Rng;
+use super::rules::{Rules, SowingPolicy, Direction};
+use super::setup::{Setup, SetupError};
 
 #[cfg(test)]
 mod tests;
 
-#[derive(Debug, Clone)]
+// What `Kalaha::play` does when an AI fails to produce a legal move
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum IllegalMovePolicy {
+    // the offending player loses immediately
+    Forfeit,
+    // ask the AI again, up to the given number of times, then forfeit
+    Retry(u32),
+    // play a random legal move on the AI's behalf
+    Random,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Fault {
+    IllegalMove(Move, Error),
+    Failed(ai::AIError),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Resolution {
+    Retried,
+    RandomMove(Move),
+    Forfeit,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Incident {
+    pub player: Player,
+    pub fault: Fault,
+    pub resolution: Resolution,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PlayReport {
+    pub game_result: GameResult,
+    pub incidents: Vec<Incident>,
+    // the players swapped sides under the pie rule, so the first AI finished as player B
+    pub swapped: bool,
+    // every move made, random ones included, to replay the game from where it started
+    pub moves: Vec<Move>,
+}
+
+// Token returned by Kalaha::make_move to take the move back again
+#[derive(Debug, PartialEq, Clone)]
+pub struct Undo {
+    // None for a swap, which leaves the board alone
+    board: Option<board::Undo>,
+    turn: Turn,
+    opening: bool,
+    swapped: bool,
+}
+
+// The seat controlling side once the players have or haven't swapped: 0 for whoever started
+// as player A, 1 for whoever started as player B
+pub fn seat_for(side: &Player, swapped: bool) -> usize {
+    match (side, swapped) {
+        (&Player::A, false) | (&Player::B, true) => 0,
+        (&Player::A, true) | (&Player::B, false) => 1,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "::serialize::GameRepr", try_from = "::serialize::GameRepr"))]
 pub struct Kalaha {
     board: Board,
     turn: Turn,
+    // player A hasn't finished their first move yet
+    opening: bool,
+    // player B took over player A's side under the pie rule
+    swapped: bool,
 }
 
 impl Kalaha {
     pub fn new() -> Kalaha {
-        Kalaha { board: Board::new(), turn: Turn::Player(Player::A) }
+        Kalaha::with_rules(Rules::default())
     }
 
-    pub fn valid_move(&self, pond: usize) -> Result<(), Error> {
-        match self.turn {
-            Turn::Finished(_) => Err(Error::GameFinished),
-            Turn::Player(ref player) => self.board.valid_move(player, pond)
+    pub fn with_rules(rules: Rules) -> Kalaha {
+        Kalaha {
+            board: Board::with_rules(rules),
+            turn: Turn::Player(Player::A),
+            opening: true,
+            swapped: false,
+        }
+    }
+
+    // Starts from a custom position, which must have the same number of seeds the rules
+    // start with. Under the pie rule a position with player A to move is the opening, so
+    // player B is offered a swap once A has moved.
+    pub fn from_setup(rules: Rules, setup: &Setup) -> Result<Kalaha, SetupError> {
+        setup.check_total(&rules, false)?;
+        Kalaha::from_checked_setup(rules, setup)
+    }
+
+    // Like from_setup but with any number of seeds, say to take some away from the
+    // stronger player
+    pub fn from_setup_any_total(rules: Rules, setup: &Setup) -> Result<Kalaha, SetupError> {
+        setup.check_total(&rules, true)?;
+        Kalaha::from_checked_setup(rules, setup)
+    }
+
+    fn from_checked_setup(rules: Rules, setup: &Setup) -> Result<Kalaha, SetupError> {
+        let game = Kalaha {
+            board: Board::from_pits(rules, setup.pits()),
+            turn: Turn::Player(setup.to_move.clone()),
+            opening: setup.to_move == Player::A,
+            swapped: false,
+        };
+        if game.legal_moves().is_empty() {
+            return Err(SetupError::NoMoves);
+        }
+        // say one side is empty, or a bank has a majority that ends the game
+        if let Some(ending) = game.board.ending(&setup.to_move) {
+            return Err(SetupError::Over(ending));
+        }
+        Ok(game)
+    }
+
+    // A game put back together from what parts gave, as long as play could have got there:
+    // the player to move has a move in a game that hasn't ended, the opening, swap and swapped
+    // flags fit the pie rule, and a finished game's scores are what's in the banks
+    #[cfg(feature = "serde")]
+    pub(crate) fn from_parts(board: Board, turn: Turn, opening: bool, swapped: bool) -> Result<Kalaha, String> {
+        let pie_rule = board.rules().pie_rule;
+        let game = Kalaha { board, turn, opening, swapped };
+        match game.turn {
+            Turn::Player(ref player) | Turn::SwapOffered(ref player) if game.legal_moves().is_empty() =>
+                return Err(format!("{} is to move but has no legal moves", player)),
+            Turn::Player(ref player) | Turn::SwapOffered(ref player) if game.board.ending(player).is_some() =>
+                return Err("the game should have ended".to_string()),
+            Turn::Player(_) | Turn::SwapOffered(_) => (),
+            Turn::Finished(ref result) => {
+                let scores = match *result {
+                    GameResult::Winner { score_a, score_b, .. } | GameResult::Forfeit { score_a, score_b, .. } =>
+                        (score_a, score_b),
+                    GameResult::Draw { score, .. } => (score, score),
+                };
+                if scores != (game.bank(&Player::A), game.bank(&Player::B)) {
+                    return Err(format!("the result {} doesn't match the banks", result));
+                }
+            },
+        }
+        match (&game.turn, opening, swapped) {
+            (_, _, true) if !pie_rule => Err("only the pie rule swaps sides".to_string()),
+            (&Turn::SwapOffered(Player::B), false, false) if pie_rule => Ok(game),
+            (&Turn::SwapOffered(_), _, _) => Err("a swap is only offered to player B after the opening".to_string()),
+            (&Turn::Player(Player::B), true, _) => Err("player B can't move during player A's opening".to_string()),
+            (_, true, true) => Err("the players can't have swapped during the opening".to_string()),
+            _ => Ok(game),
+        }
+    }
+
+    // What from_parts takes
+    #[cfg(feature = "serde")]
+    pub(crate) fn parts(&self) -> (&Board, &Turn, bool, bool) {
+        (&self.board, &self.turn, self.opening, self.swapped)
+    }
+
+    // The current position, or None once the game has finished
+    pub fn setup(&self) -> Option<Setup> {
+        if self.is_finished() {
+            return None;
+        }
+        Some(Setup {
+            ponds_a: self.ponds(&Player::A),
+            bank_a: self.bank(&Player::A),
+            ponds_b: self.ponds(&Player::B),
+            bank_b: self.bank(&Player::B),
+            to_move: self.current_player().clone(),
+        })
+    }
+
+    pub fn rules(&self) -> &Rules {
+        self.board.rules()
+    }
+
+    // The game with the sides swapped, so player B's position is now player A's and the
+    // other player is to move
+    pub fn mirrored(&self) -> Kalaha {
+        Kalaha { board: self.board.mirrored(), turn: self.turn.mirrored(), ..self.clone() }
+    }
+
+    // One game for a position and its mirror, with player A to move unless it's finished,
+    // along with how to get there. Search caches can key on this so mirrored positions
+    // share an entry. Scores for A in the canonical game are scores for B in this one
+    // when the transform is a mirror.
+    pub fn canonical(&self) -> (Kalaha, Transform) {
+        if self.is_finished() {
+            return (self.clone(), Transform::Identity);
+        }
+        let (board, transform) = self.board.canonical(self.current_player());
+        let turn = match transform {
+            Transform::Identity => self.turn.clone(),
+            Transform::Mirror => self.turn.mirrored(),
+        };
+        (Kalaha { board, turn, ..self.clone() }, transform)
+    }
+
+    pub fn valid_move<M: Into<Move>>(&self, mv: M) -> Result<(), Error> {
+        match (&self.turn, mv.into()) {
+            (&Turn::Finished(_), _) => Err(Error::GameFinished),
+            (&Turn::SwapOffered(_), Move::Swap) => Ok(()),
+            (_, Move::Swap) => Err(Error::CannotSwap),
+            (turn, Move::Pond(pond)) => match self.rules().sowing {
+                SowingPolicy::Fixed(direction) => self.board.valid_move(turn.player(), pond, direction),
+                SowingPolicy::PlayerChoice => Err(Error::WrongDirection),
+            },
+            (turn, Move::Directed(pond, direction)) => self.board.valid_move(turn.player(), pond, direction),
+        }
+    }
+
+    // Ponds in order, each counter-clockwise then clockwise if the player picks the
+    // direction, then the swap if it's on offer
+    pub fn legal_moves(&self) -> MoveList {
+        let mut moves = MoveList::new();
+        let choose_direction = self.rules().sowing == SowingPolicy::PlayerChoice;
+        for pond in 0..super::PONDS_PER_PLAYER {
+            if choose_direction {
+                for &direction in self.rules().directions() {
+                    if self.valid_move(Move::Directed(pond, direction)).is_ok() {
+                        moves.push(Move::Directed(pond, direction));
+                    }
+                }
+            } else if self.valid_move(pond).is_ok() {
+                moves.push(Move::Pond(pond));
+            }
+        }
+        if self.valid_move(Move::Swap).is_ok() {
+            moves.push(Move::Swap);
         }
+        moves
+    }
+
+    // Every legal move paired with the game after making it, in legal_moves order
+    pub fn children(&self) -> Children<'_> {
+        Children { game: self, moves: self.legal_moves().into_iter() }
     }
 
     pub fn bank(&self, player: &Player) -> u32 {
-        self.board.bank(player).count
+        self.board.bank(player)
     }
 
     pub fn ponds(&self, player: &Player) -> [u32; 6] {
         self.board.pond_counts(player)
     }
 
+    // Every count in one array: A's ponds, A's bank, B's ponds then B's bank. Sowing's pit
+    // numbers index into it.
+    pub fn pits(&self) -> [u32; super::TOTAL_POOLS] {
+        self.board.counts()
+    }
+
     pub fn current_player(&self) -> &Player {
         self.turn.player()
     }
@@ -43,35 +273,254 @@ impl Kalaha {
         self.turn.is_finished()
     }
 
+    pub(crate) fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn turn(&self) -> &Turn {
+        &self.turn
+    }
+
     pub fn game_result(&self) -> &GameResult {
         self.turn.game_result()
     }
 
-    // panics if pond is not a valid_move
-    pub fn choose(&mut self, pond: usize) {
-        self.valid_move(pond).expect("Invalid move");
-        self.turn = self.board.choose(self.turn.player(), pond);
+    // player B may swap sides instead of moving
+    pub fn swap_offered(&self) -> bool {
+        match self.turn {
+            Turn::SwapOffered(_) => true,
+            Turn::Player(_) | Turn::Finished(_) => false,
+        }
     }
 
-    pub fn play(&mut self, ai_player_a: &Box<ai::AI>, ai_player_b: &Box<ai::AI>, verbose: bool) -> &GameResult
+    // Player A hasn't finished their first move, so under the pie rule player B will be
+    // offered a swap once they have
+    pub fn opening(&self) -> bool {
+        self.opening && self.rules().pie_rule
+    }
+
+    // Puts the pie rule back where it was in a game rebuilt from its Setup, which doesn't
+    // record it. Fails with CannotSwap if the rules or the player to move don't allow it.
+    pub(crate) fn restore_pie_rule(&mut self, opening: bool, swap_offered: bool) -> Result<(), Error> {
+        if !opening && !swap_offered {
+            self.opening = false;
+            return Ok(());
+        }
+        match self.turn {
+            Turn::Player(Player::A) if opening && !swap_offered && self.rules().pie_rule => self.opening = true,
+            Turn::Player(Player::B) if swap_offered && !opening && self.rules().pie_rule =>
+                self.turn = Turn::SwapOffered(Player::B),
+            _ => return Err(Error::CannotSwap),
+        }
+        Ok(())
+    }
+
+    // Moves since the last capture, which end an Oware game once there are too many
+    pub fn quiet_moves(&self) -> u32 {
+        u32::from(self.board.quiet_moves())
+    }
+
+    // Like restore_pie_rule, for the count of moves without a capture
+    pub(crate) fn restore_quiet_moves(&mut self, quiet_moves: u32) {
+        self.board.set_quiet_moves(quiet_moves.min(u32::from(u8::MAX)) as u8);
+    }
+
+    // Whether the players swapped sides under the pie rule. Scores and results are
+    // always by side, so after a swap player A's score belongs to whoever moved second.
+    pub fn swapped(&self) -> bool {
+        self.swapped
+    }
+
+    // The seat controlling side in this game, as in seat_for
+    pub fn seat(&self, side: &Player) -> usize {
+        seat_for(side, self.swapped)
+    }
+
+    // panics if mv is not a valid_move
+    pub fn choose<M: Into<Move>>(&mut self, mv: M) -> MoveOutcome {
+        self.make_move(mv).0
+    }
+
+    // Makes the move in place rather than on a clone, for use in search.
+    // panics if mv is not a valid_move
+    pub fn make_move<M: Into<Move>>(&mut self, mv: M) -> (MoveOutcome, Undo) {
+        let mv = mv.into();
+        self.valid_move(mv).expect("Invalid move");
+        let mut undo = Undo {
+            board: None,
+            turn: self.turn.clone(),
+            opening: self.opening,
+            swapped: self.swapped,
+        };
+        let mut outcome = match mv {
+            // valid_move only allows a plain pond when the direction is fixed
+            Move::Pond(pond) => self.sow(pond, self.rules().directions()[0], &mut undo),
+            Move::Directed(pond, direction) => self.sow(pond, direction, &mut undo),
+            Move::Swap => {
+                // the board stays put and the same side moves next, just with the
+                // players the other way round
+                self.swapped = true;
+                let turn = Turn::Player(self.turn.player().clone());
+                MoveOutcome { turn, extra_turn: false, captured: 0, laps: 0 }
+            }
+        };
+        if self.opening && outcome.turn != Turn::Player(Player::A) {
+            self.opening = false;
+            if self.rules().pie_rule && outcome.turn == Turn::Player(Player::B) {
+                outcome.turn = Turn::SwapOffered(Player::B);
+            }
+        }
+        self.turn = outcome.turn.clone();
+        (outcome, undo)
+    }
+
+    fn sow(&mut self, pond: usize, direction: Direction, undo: &mut Undo) -> MoveOutcome {
+        let (outcome, board) = self.board.make_move(self.turn.player(), pond, direction);
+        undo.board = Some(board);
+        outcome
+    }
+
+    // The pits mv picks seeds up from and drops them into, see Board::sowing. Nothing for a
+    // swap.
+    // panics if mv is not a valid_move
+    pub fn sowing<M: Into<Move>>(&self, mv: M) -> Vec<Sow> {
+        let mv = mv.into();
+        self.valid_move(mv).expect("Invalid move");
+        match mv {
+            Move::Pond(pond) => self.board.sowing(self.turn.player(), pond, self.rules().directions()[0]),
+            Move::Directed(pond, direction) => self.board.sowing(self.turn.player(), pond, direction),
+            Move::Swap => Vec::new(),
+        }
+    }
+
+    // Takes back the move that returned undo. Moves must be unmade in reverse order.
+    pub fn unmake_move(&mut self, undo: Undo) {
+        if let Some(board) = undo.board {
+            self.board.unmake_move(board);
+        }
+        self.turn = undo.turn;
+        self.opening = undo.opening;
+        self.swapped = undo.swapped;
+    }
+
+    pub fn play(&mut self, ai_player_a: &dyn ai::AI, ai_player_b: &dyn ai::AI,
+                policy: IllegalMovePolicy, verbose: bool) -> PlayReport
     {
+        let mut incidents = Vec::new();
+        let mut moves = Vec::new();
+        let mut rng = Rng::from_time();
         if verbose {
             println!("{}", self);
         }
         while !self.turn.is_finished() {
-            let choice = match *self.turn.player() {
-                Player::A => ai_player_a.choose(&self),
-                Player::B => ai_player_b.choose(&self),
+            let player = self.turn.player().clone();
+            let ai_player = [ai_player_a, ai_player_b][self.seat(&player)];
+            let choice = match self.request_move(ai_player, policy, &mut rng, &mut incidents) {
+                Some(choice) => choice,
+                None => {
+                    if verbose {
+                        println!("{} forfeits\n", player);
+                    }
+                    self.forfeit(player);
+                    break;
+                }
             };
             if verbose {
-                println!("Player chose pond {}\n", choice);
+                match choice {
+                    Move::Pond(pond) => println!("Player chose pond {}\n", pond),
+                    Move::Directed(pond, direction) =>
+                        println!("Player chose pond {} {}\n", pond, direction),
+                    Move::Swap => println!("Player swapped sides\n"),
+                }
             }
             self.choose(choice);
+            moves.push(choice);
             if verbose {
                 println!("{}", self);
             }
         }
-        self.turn.game_result()
+        PlayReport { game_result: self.turn.game_result().clone(), incidents, swapped: self.swapped, moves }
+    }
+
+    // returns None if the current player forfeits
+    fn request_move(&self, ai_player: &dyn ai::AI, policy: IllegalMovePolicy, rng: &mut Rng,
+                    incidents: &mut Vec<Incident>) -> Option<Move>
+    {
+        let mut retries = 0;
+        loop {
+            let fault = match ai_player.choose(self) {
+                Ok(mv) => match self.valid_move(mv) {
+                    Ok(()) => return Some(mv),
+                    Err(error) => Fault::IllegalMove(mv, error),
+                },
+                Err(error) => Fault::Failed(error),
+            };
+            let resolution = match policy {
+                IllegalMovePolicy::Retry(max_retries) if retries < max_retries => Resolution::Retried,
+                IllegalMovePolicy::Random => Resolution::RandomMove(self.random_move(rng)),
+                _ => Resolution::Forfeit,
+            };
+            incidents.push(Incident {
+                player: self.current_player().clone(),
+                fault,
+                resolution: resolution.clone(),
+            });
+            match resolution {
+                Resolution::Retried => retries += 1,
+                Resolution::RandomMove(mv) => return Some(mv),
+                Resolution::Forfeit => return None,
+            }
+        }
+    }
+
+    fn random_move(&self, rng: &mut Rng) -> Move {
+        let moves = self.legal_moves();
+        moves[rng.below(moves.len())]
+    }
+
+    pub(crate) fn forfeit(&mut self, player: Player) {
+        let score_a = self.bank(&Player::A);
+        let score_b = self.bank(&Player::B);
+        self.turn = Turn::Finished(GameResult::Forfeit { player, score_a, score_b });
+    }
+}
+
+pub struct Children<'a> {
+    game: &'a Kalaha,
+    moves: moves::IntoIter,
+}
+
+impl<'a> Children<'a> {
+    fn child(&self, mv: Move) -> (Move, Kalaha) {
+        let mut game = self.game.clone();
+        game.choose(mv);
+        (mv, game)
+    }
+}
+
+impl<'a> Iterator for Children<'a> {
+    type Item = (Move, Kalaha);
+
+    fn next(&mut self) -> Option<(Move, Kalaha)> {
+        self.moves.next().map(|mv| self.child(mv))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.moves.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for Children<'a> {
+    fn next_back(&mut self) -> Option<(Move, Kalaha)> {
+        self.moves.next_back().map(|mv| self.child(mv))
+    }
+}
+
+impl<'a> ExactSizeIterator for Children<'a> {}
+
+impl Default for Kalaha {
+    fn default() -> Kalaha {
+        Kalaha::new()
     }
 }
 
@@ -1,4 +1,51 @@
 use super::*;
+use ai::{AI, AIError, FirstValid, LastValid};
+use turn::Ending;
+use std::cell::Cell;
+
+// Plays the given pond a fixed number of times, then the first valid one
+#[derive(Debug)]
+struct Stubborn {
+    pond: usize,
+    remaining: Cell<u32>,
+}
+
+impl Stubborn {
+    fn new(pond: usize, times: u32) -> Stubborn {
+        Stubborn { pond, remaining: Cell::new(times) }
+    }
+}
+
+impl AI for Stubborn {
+    fn choose(&self, game: &Kalaha) -> Result<Move, AIError> {
+        if self.remaining.get() > 0 {
+            self.remaining.set(self.remaining.get() - 1);
+            Ok(Move::Pond(self.pond))
+        } else {
+            FirstValid {}.choose(game)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Broken {}
+
+impl AI for Broken {
+    fn choose(&self, _game: &Kalaha) -> Result<Move, AIError> {
+        Err(AIError::Failed("broken".to_string()))
+    }
+}
+
+fn finished_game() -> Kalaha {
+    Kalaha {
+        turn: Turn::Finished(GameResult::Draw { score: 36, ending: Ending::SideEmpty }),
+        ..Kalaha::new()
+    }
+}
+
+fn pie_game() -> Kalaha {
+    Kalaha::with_rules(Rules::kalah().with_pie_rule(true))
+}
 
 #[test]
 fn valid_move_valid() {
@@ -19,9 +66,380 @@ fn valid_move_pond_empty() {
 
 #[test]
 fn valid_move_finished() {
-    let kalaha = Kalaha {
-        board: Board::new(),
-        turn: Turn::Finished(GameResult::Draw { score: 36 })
-    };
+    let kalaha = finished_game();
     assert_eq!(kalaha.valid_move(0), Err(Error::GameFinished));
 }
+
+#[test]
+fn play_forfeit_illegal() {
+    let mut kalaha = Kalaha::new();
+    let report = kalaha.play(&Stubborn::new(6, 1), &FirstValid {}, IllegalMovePolicy::Forfeit, false);
+    assert_eq!(
+        report.game_result,
+        GameResult::Forfeit { player: Player::A, score_a: 0, score_b: 0 }
+    );
+    assert_eq!(
+        report.incidents,
+        vec![Incident {
+            player: Player::A,
+            fault: Fault::IllegalMove(Move::Pond(6), Error::InvalidIndex),
+            resolution: Resolution::Forfeit,
+        }]
+    );
+    assert!(kalaha.is_finished());
+}
+
+#[test]
+fn play_forfeit_failed() {
+    let mut kalaha = Kalaha::new();
+    let report = kalaha.play(&FirstValid {}, &Broken {}, IllegalMovePolicy::Forfeit, false);
+    match report.game_result {
+        GameResult::Forfeit { player: Player::B, .. } => (),
+        ref result => panic!("Unexpected result {:?}", result),
+    }
+    assert_eq!(report.incidents[0].fault, Fault::Failed(AIError::Failed("broken".to_string())));
+}
+
+#[test]
+fn play_retry_recovers() {
+    let mut kalaha = Kalaha::new();
+    let report = kalaha.play(&Stubborn::new(6, 2), &FirstValid {}, IllegalMovePolicy::Retry(2), false);
+    assert_eq!(report.incidents.len(), 2);
+    assert!(report.incidents.iter().all(|i| i.resolution == Resolution::Retried));
+    assert!(!matches!(report.game_result, GameResult::Forfeit { .. }));
+}
+
+#[test]
+fn play_retry_exhausted() {
+    let mut kalaha = Kalaha::new();
+    let report = kalaha.play(&Stubborn::new(6, 3), &FirstValid {}, IllegalMovePolicy::Retry(2), false);
+    assert_eq!(report.incidents.len(), 3);
+    assert_eq!(report.incidents[2].resolution, Resolution::Forfeit);
+    assert_eq!(
+        report.game_result,
+        GameResult::Forfeit { player: Player::A, score_a: 0, score_b: 0 }
+    );
+}
+
+#[test]
+fn play_random_fallback() {
+    let mut kalaha = Kalaha::new();
+    let report = kalaha.play(&FirstValid {}, &Broken {}, IllegalMovePolicy::Random, false);
+    assert!(!report.incidents.is_empty());
+    for incident in &report.incidents {
+        assert_eq!(incident.player, Player::B);
+        match incident.resolution {
+            Resolution::RandomMove(Move::Pond(pond)) => assert!(pond < ::PONDS_PER_PLAYER),
+            ref resolution => panic!("Unexpected resolution {:?}", resolution),
+        }
+    }
+    assert!(!matches!(report.game_result, GameResult::Forfeit { .. }));
+}
+
+#[test]
+fn legal_moves_new() {
+    let expected: Vec<Move> = (0..6).map(Move::Pond).collect();
+    assert_eq!(Kalaha::new().legal_moves().as_slice(), &expected[..]);
+}
+
+#[test]
+fn legal_moves_skips_empty() {
+    let mut kalaha = Kalaha::new();
+    kalaha.choose(0);
+    let expected: Vec<Move> = (1..6).map(Move::Pond).collect();
+    assert_eq!(kalaha.legal_moves().as_slice(), &expected[..]);
+}
+
+#[test]
+fn legal_moves_finished() {
+    let kalaha = finished_game();
+    assert!(kalaha.legal_moves().is_empty());
+    assert_eq!(kalaha.children().count(), 0);
+}
+
+#[test]
+fn children_match_choose() {
+    let kalaha = Kalaha::new();
+    let children: Vec<(Move, Kalaha)> = kalaha.children().collect();
+    assert_eq!(children.len(), 6);
+    for (pond, child) in children {
+        let mut expected = kalaha.clone();
+        expected.choose(pond);
+        assert_eq!(child.board, expected.board);
+        assert_eq!(child.turn, expected.turn);
+    }
+}
+
+#[test]
+fn children_rev() {
+    let moves: Vec<Move> = Kalaha::new().children().rev().map(|(mv, _)| mv).collect();
+    let expected: Vec<Move> = (0..6).rev().map(Move::Pond).collect();
+    assert_eq!(moves, expected);
+}
+
+#[test]
+fn make_move_matches_choose() {
+    let mut made = Kalaha::new();
+    let mut chosen = Kalaha::new();
+    for &pond in &[2, 5, 0, 4] {
+        made.make_move(pond);
+        chosen.choose(pond);
+        assert_eq!(made.board, chosen.board);
+        assert_eq!(made.turn, chosen.turn);
+    }
+}
+
+#[test]
+fn unmake_move_whole_game() {
+    let mut rng = ::rng::Rng::new(1234);
+    let mut kalaha = Kalaha::new();
+    let mut history = Vec::new();
+    while !kalaha.is_finished() {
+        let moves = kalaha.legal_moves();
+        let before = kalaha.clone();
+        let (_, undo) = kalaha.make_move(moves[rng.below(moves.len())]);
+        history.push((before, undo));
+    }
+    while let Some((before, undo)) = history.pop() {
+        kalaha.unmake_move(undo);
+        assert_eq!(kalaha.board, before.board);
+        assert_eq!(kalaha.turn, before.turn);
+    }
+}
+
+#[test]
+fn pie_rule_off_by_default() {
+    let mut kalaha = Kalaha::new();
+    kalaha.choose(1);
+    assert_eq!(kalaha.turn, Turn::Player(Player::B));
+    assert_eq!(kalaha.valid_move(Move::Swap), Err(Error::CannotSwap));
+}
+
+#[test]
+fn pie_rule_offers_swap() {
+    let mut kalaha = pie_game();
+    assert_eq!(kalaha.valid_move(Move::Swap), Err(Error::CannotSwap));
+    let outcome = kalaha.choose(1);
+    assert_eq!(outcome.turn, Turn::SwapOffered(Player::B));
+    assert_eq!(kalaha.to_string().lines().last(), Some("Next turn: Player B (may swap)"));
+    let moves = kalaha.legal_moves();
+    assert_eq!(moves.len(), 7);
+    assert_eq!(moves.last(), Some(&Move::Swap));
+}
+
+#[test]
+fn pie_rule_waits_for_extra_turns() {
+    let mut kalaha = pie_game();
+    // the last of pond 0's seeds lands in A's bank
+    let outcome = kalaha.choose(0);
+    assert!(outcome.extra_turn);
+    assert_eq!(outcome.turn, Turn::Player(Player::A));
+    assert_eq!(kalaha.choose(1).turn, Turn::SwapOffered(Player::B));
+}
+
+#[test]
+fn pie_rule_only_once() {
+    let mut kalaha = pie_game();
+    kalaha.choose(1);
+    kalaha.choose(0);
+    kalaha.choose(2);
+    assert_eq!(kalaha.turn, Turn::Player(Player::B));
+    assert_eq!(kalaha.valid_move(Move::Swap), Err(Error::CannotSwap));
+}
+
+#[test]
+fn swap_keeps_board_and_side_to_move() {
+    let mut kalaha = pie_game();
+    kalaha.choose(1);
+    let board = kalaha.board;
+    let outcome = kalaha.choose(Move::Swap);
+    let turn = Turn::Player(Player::B);
+    assert_eq!(outcome, MoveOutcome { turn, extra_turn: false, captured: 0, laps: 0 });
+    assert_eq!(kalaha.board, board);
+    assert!(kalaha.swapped());
+    assert_eq!(kalaha.valid_move(Move::Swap), Err(Error::CannotSwap));
+}
+
+#[test]
+fn declining_swap() {
+    let mut kalaha = pie_game();
+    kalaha.choose(1);
+    kalaha.choose(0);
+    assert!(!kalaha.swapped());
+    assert_eq!(kalaha.turn, Turn::Player(Player::A));
+}
+
+#[test]
+fn unmake_swap() {
+    let mut kalaha = pie_game();
+    let (_, first) = kalaha.make_move(1);
+    let (_, swap) = kalaha.make_move(Move::Swap);
+    kalaha.unmake_move(swap);
+    assert_eq!(kalaha.turn, Turn::SwapOffered(Player::B));
+    assert!(!kalaha.swapped());
+    kalaha.unmake_move(first);
+    assert_eq!(kalaha.turn, Turn::Player(Player::A));
+    kalaha.choose(1);
+    assert_eq!(kalaha.turn, Turn::SwapOffered(Player::B));
+}
+
+#[test]
+fn play_swaps_ais() {
+    let mut kalaha = pie_game();
+    let mut reference = pie_game();
+    let report = kalaha.play(&FirstValid {}, &LastValid {}, IllegalMovePolicy::Forfeit, false);
+    assert!(report.swapped);
+    // LastValid swaps as soon as it can, then moves for player A
+    reference.choose(0);
+    reference.choose(1);
+    reference.choose(Move::Swap);
+    while !reference.is_finished() {
+        let ai: &dyn AI = match *reference.current_player() {
+            Player::A => &LastValid {},
+            Player::B => &FirstValid {},
+        };
+        let mv = ai.choose(&reference).unwrap();
+        reference.choose(mv);
+    }
+    assert_eq!(report.game_result, *reference.game_result());
+}
+
+fn choice_game() -> Kalaha {
+    Kalaha::with_rules(Rules::kalah().with_sowing(SowingPolicy::PlayerChoice))
+}
+
+#[test]
+fn legal_moves_player_choice() {
+    let moves = choice_game().legal_moves();
+    assert_eq!(moves.len(), 12);
+    assert_eq!(moves[0], Move::Directed(0, Direction::CounterClockwise));
+    assert_eq!(moves[1], Move::Directed(0, Direction::Clockwise));
+}
+
+#[test]
+fn player_choice_needs_direction() {
+    let kalaha = choice_game();
+    assert_eq!(kalaha.valid_move(0), Err(Error::WrongDirection));
+    assert_eq!(Kalaha::new().valid_move(Move::Directed(0, Direction::Clockwise)), Err(Error::WrongDirection));
+    assert_eq!(Kalaha::new().valid_move(Move::Directed(0, Direction::CounterClockwise)), Ok(()));
+}
+
+#[test]
+fn directed_moves_sow_their_way() {
+    let mut ccw = choice_game();
+    assert!(ccw.choose(Move::Directed(0, Direction::CounterClockwise)).extra_turn);
+    let mut cw = choice_game();
+    assert!(!cw.choose(Move::Directed(0, Direction::Clockwise)).extra_turn);
+    assert_eq!(cw.ponds(&Player::B), [7, 7, 7, 7, 7, 7]);
+}
+
+#[test]
+fn from_setup() {
+    let setup: Setup = "0,0,0,0,0,3/30/6,2,0,1,0,0/30/B".parse().unwrap();
+    let kalaha = Kalaha::from_setup(Rules::kalah(), &setup).unwrap();
+    assert_eq!(kalaha.current_player(), &Player::B);
+    assert_eq!(kalaha.ponds(&Player::A), [0, 0, 0, 0, 0, 3]);
+    assert_eq!(kalaha.bank(&Player::B), 30);
+    assert_eq!(kalaha.setup(), Some(setup));
+}
+
+#[test]
+fn from_setup_handicap() {
+    // player A starts a seed down in every pond
+    let setup: Setup = "5,5,5,5,5,5/0/6,6,6,6,6,6/0/A".parse().unwrap();
+    assert_eq!(
+        Kalaha::from_setup(Rules::kalah(), &setup).unwrap_err(),
+        SetupError::WrongTotal { expected: 72, found: 66 }
+    );
+    let kalaha = Kalaha::from_setup_any_total(Rules::kalah(), &setup).unwrap();
+    assert_eq!(kalaha.ponds(&Player::A), [5; 6]);
+}
+
+#[test]
+fn from_setup_no_moves() {
+    let setup: Setup = "0,0,0,0,0,0/36/6,6,6,6,6,6/0/A".parse().unwrap();
+    assert_eq!(Kalaha::from_setup(Rules::kalah(), &setup).unwrap_err(), SetupError::NoMoves);
+    // under Oware the move has to feed player B
+    let setup: Setup = "0,0,0,0,1,0/23/0,0,0,0,0,0/24/A".parse().unwrap();
+    assert_eq!(Kalaha::from_setup(Rules::oware(), &setup).unwrap_err(), SetupError::NoMoves);
+}
+
+// positions where the game has already ended aren't somewhere to start from
+#[test]
+fn from_setup_already_over() {
+    let setup: Setup = "6,6,6,6,6,6/36/0,0,0,0,0,0/0/A".parse().unwrap();
+    assert_eq!(Kalaha::from_setup(Rules::kalah(), &setup).unwrap_err(), SetupError::Over(Ending::SideEmpty));
+    let setup: Setup = "1,1,1,1,1,1/37/6,6,6,6,5,0/0/B".parse().unwrap();
+    let majority = Rules::kalah().with_end_on_majority(true);
+    assert_eq!(Kalaha::from_setup(majority, &setup).unwrap_err(), SetupError::Over(Ending::Majority));
+    // the same position plays on without the majority rule
+    assert!(Kalaha::from_setup(Rules::kalah(), &setup).is_ok());
+    let setup: Setup = "4,4,4,4,4,2/25/0,0,0,0,0,1/0/B".parse().unwrap();
+    assert_eq!(Kalaha::from_setup(Rules::oware(), &setup).unwrap_err(), SetupError::Over(Ending::Majority));
+}
+
+#[test]
+fn from_setup_pie_rule() {
+    let setup: Setup = "5,7,6,6,6,6/0/6,6,6,6,6,6/0/A".parse().unwrap();
+    let mut kalaha = Kalaha::from_setup(Rules::kalah().with_pie_rule(true), &setup).unwrap();
+    assert!(kalaha.opening());
+    kalaha.choose(1);
+    assert_eq!(kalaha.turn, Turn::SwapOffered(Player::B));
+    // with player B to move the opening is over
+    let setup: Setup = "6,6,6,6,6,6/0/6,6,6,6,6,6/0/B".parse().unwrap();
+    let mut kalaha = Kalaha::from_setup(Rules::kalah().with_pie_rule(true), &setup).unwrap();
+    assert!(!kalaha.opening());
+    kalaha.choose(1);
+    assert_eq!(kalaha.turn, Turn::Player(Player::A));
+}
+
+#[test]
+fn setup_finished() {
+    assert_eq!(finished_game().setup(), None);
+}
+
+#[test]
+fn canonical_after_a_move() {
+    let mut kalaha = Kalaha::new();
+    assert_eq!(kalaha.canonical(), (kalaha.clone(), Transform::Identity));
+    kalaha.choose(1);
+    let (canonical, transform) = kalaha.canonical();
+    assert_eq!(transform, Transform::Mirror);
+    assert_eq!(canonical.current_player(), &Player::A);
+    assert_eq!(canonical.ponds(&Player::A), kalaha.ponds(&Player::B));
+    assert_eq!(canonical.bank(&Player::B), kalaha.bank(&Player::A));
+    assert_eq!(canonical.mirrored(), kalaha);
+}
+
+#[test]
+fn canonical_swap_offered() {
+    let mut kalaha = pie_game();
+    kalaha.choose(1);
+    let (canonical, transform) = kalaha.canonical();
+    assert_eq!(transform, Transform::Mirror);
+    assert_eq!(canonical.turn, Turn::SwapOffered(Player::A));
+    assert_eq!(canonical.valid_move(Move::Swap), Ok(()));
+}
+
+#[test]
+fn canonical_finished() {
+    let kalaha = finished_game();
+    assert_eq!(kalaha.canonical(), (kalaha.clone(), Transform::Identity));
+}
+
+#[test]
+fn restore_pie_rule() {
+    let setup = Setup::start(&Rules::kalah());
+    let mut kalaha = Kalaha::from_setup(Rules::kalah().with_pie_rule(true), &setup).unwrap();
+    kalaha.restore_pie_rule(false, false).unwrap();
+    assert!(!kalaha.opening());
+    kalaha.restore_pie_rule(true, false).unwrap();
+    assert!(kalaha.opening());
+    kalaha.choose(1);
+    assert!(kalaha.swap_offered());
+    // no swap for player A, or without the pie rule
+    let mut kalaha = Kalaha::from_setup(Rules::kalah().with_pie_rule(true), &setup).unwrap();
+    assert_eq!(kalaha.restore_pie_rule(false, true), Err(Error::CannotSwap));
+    let mut kalaha = Kalaha::from_setup(Rules::kalah(), &setup).unwrap();
+    assert_eq!(kalaha.restore_pie_rule(true, false), Err(Error::CannotSwap));
+}
@@ -1,14 +1,65 @@
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+#[cfg(feature = "python")]
+extern crate pyo3;
+#[cfg(feature = "tui")]
+extern crate ratatui;
+// pyo3's macros name ::core, which the 2015 edition looks up from the crate root
+#[cfg(feature = "python")]
+extern crate core;
+
 mod error;
 mod player;
+// only used by the reference board that the packed one is tested against
+#[cfg(test)]
 mod pool;
 mod turn;
 mod board;
+// rules tests read from fixture files
+#[cfg(test)]
+mod conformance;
 mod kalaha;
+mod moves;
+mod multi;
+mod perft;
+mod rng;
+mod rules;
+mod setup;
+#[cfg(feature = "serde")]
+mod serialize;
 
 pub mod ai;
-pub use kalaha::Kalaha;
+pub mod engine;
+pub mod http;
+pub mod json;
+pub mod render;
+pub mod server;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub use kalaha::{Kalaha, seat_for, Children, Undo, IllegalMovePolicy, Incident, Fault, Resolution, PlayReport};
+pub use moves::{Move, MoveList};
+pub use multi::{MultiKalaha, MultiTurn, MIN_SEATS, MAX_SEATS};
+pub use perft::{perft, PerftCounts};
+pub use board::{Transform, Sow};
+pub use error::Error;
+pub use rules::{Rules, Variant, CapturePolicy, SweepPolicy, SowingPolicy, Direction};
+pub use player::Player;
+pub use setup::{Setup, SetupError};
+pub use turn::{Turn, GameResult, Ending, MoveOutcome, Ranking, Standing};
 
 const INIT_COUNT: u32 = 6;
+const OWARE_INIT_COUNT: u32 = 4;
 const PONDS_PER_PLAYER: usize = 6;
 const TOTAL_POOLS: usize = 14;
 
@@ -1,69 +1,551 @@
 extern crate clap;
 extern crate kalaha;
 
-use clap::{Arg, App};
-use kalaha::Kalaha;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process;
+use std::time::{Duration, Instant};
+
+use clap::{Arg, App, ArgMatches, SubCommand};
+use kalaha::{Kalaha, Move, MultiKalaha, Setup, IllegalMovePolicy, Rules};
 use kalaha::ai;
+use kalaha::engine::{ExternalEngine, SearchLimit};
+use kalaha::render::{self, Diagram, Replay};
+use kalaha::server::Server;
+
+fn ai_from_arg(name: &str, depth: Option<&str>) -> Box<dyn ai::AI> {
+    ai_with_depth(name, depth.map_or(6, |s| s.parse().unwrap()))
+}
+
+fn ai_with_depth(name: &str, depth: u32) -> Box<dyn ai::AI> {
+    ai::by_name(name, depth).expect("Unknown AI name")
+}
 
-fn ai_from_arg(name: &str, depth: Option<&str>) -> Box<ai::AI> {
-    let depth = depth.map_or(6, |s| s.parse().unwrap());
+fn multi_ai_from_arg(name: &str, depth: u32) -> Box<dyn ai::MultiAI> {
     match name {
-        "first" => return Box::new(ai::FirstValid {}),
-        "last" => return Box::new(ai::LastValid {}),
-        "minmax" => return Box::new(ai::MinMax::new(depth)),
-        "alphabeta" => return Box::new(ai::AlphaBeta::new(depth)),
+        "first" => Box::new(ai::FirstValid {}),
+        "last" => Box::new(ai::LastValid {}),
+        "maxn" => Box::new(ai::MaxN::new(depth)),
         _ => panic!("Unknown AI name"),
     }
 }
 
+fn policy_from_arg(name: &str, retries: Option<&str>) -> IllegalMovePolicy {
+    match name {
+        "forfeit" => IllegalMovePolicy::Forfeit,
+        "retry" => IllegalMovePolicy::Retry(retries.map_or(3, |s| s.parse().unwrap())),
+        "random" => IllegalMovePolicy::Random,
+        _ => panic!("Unknown illegal move policy"),
+    }
+}
+
+// The flags written out as a Rules string, so oware refuses kalah-only flags the same way
+// the engine protocol and HTTP API do
+fn rules_from_args(matches: &ArgMatches) -> Rules {
+    let mut words = vec![matches.value_of("rules").unwrap_or("kalah").to_string()];
+    for &name in &["capture", "sweep", "sowing"] {
+        if let Some(value) = matches.value_of(name) {
+            words.push(format!("{}={}", name, value));
+        }
+    }
+    for &name in &["relay", "majority", "pie"] {
+        if matches.is_present(name) {
+            words.push(name.to_string());
+        }
+    }
+    words.join(" ").parse().unwrap_or_else(|error: String| {
+        eprintln!("{}", error);
+        process::exit(1);
+    })
+}
+
+// A game under the rules from the arguments, starting from --setup if given
+fn game_from_args(matches: &ArgMatches) -> Kalaha {
+    let rules = rules_from_args(matches);
+    let setup = match matches.value_of("setup") {
+        Some(setup) => setup.parse::<Setup>(),
+        None => return Kalaha::with_rules(rules),
+    };
+    let game = setup.and_then(|setup| if matches.is_present("any_total") {
+        Kalaha::from_setup_any_total(rules, &setup)
+    } else {
+        Kalaha::from_setup(rules, &setup)
+    });
+    game.unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    })
+}
+
+fn play(game: &mut Kalaha, ai_one: &dyn ai::AI, ai_two: &dyn ai::AI, policy: IllegalMovePolicy, verbose: bool) {
+    let report = game.play(ai_one, ai_two, policy, verbose);
+    for incident in &report.incidents {
+        println!("{:?}", incident);
+    }
+    if report.swapped {
+        println!("Sides swapped after the first move, {:?} finished as Player B", ai_one);
+    }
+    println!("{}", report.game_result);
+}
+
+fn perft(matches: &ArgMatches) {
+    let max_depth: u32 = matches.value_of("depth").map_or(8, |s| s.parse().unwrap());
+    let game = game_from_args(matches);
+    println!("{:>5} {:>14} {:>12} {:>12} {:>10} {:>14}",
+             "depth", "leaves", "extra turns", "captures", "finished", "leaves/s");
+    for depth in 1..max_depth + 1 {
+        let start = Instant::now();
+        let counts = kalaha::perft(&game, depth);
+        let elapsed = start.elapsed();
+        let secs = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1e9;
+        println!("{:>5} {:>14} {:>12} {:>12} {:>10} {:>14.0}",
+                 depth, counts.leaves, counts.extra_turns, counts.captures, counts.finished,
+                 counts.leaves as f64 / secs);
+    }
+}
+
+// Runs command, split on spaces, as an engine protocol AI searching to depth
+fn external_from_arg(command: &str, depth: Option<&str>) -> Box<dyn ai::AI> {
+    let words: Vec<&str> = command.split_whitespace().collect();
+    let limit = SearchLimit::Depth(depth.map_or(6, |s| s.parse().unwrap()));
+    match ExternalEngine::spawn(words[0], &words[1..], limit) {
+        Ok(engine) => Box::new(engine),
+        Err(error) => {
+            eprintln!("Couldn't start engine '{}': {}", command, error);
+            process::exit(1);
+        },
+    }
+}
+
+// Answers the engine protocol on stdin and stdout
+fn engine(matches: &ArgMatches) {
+    let name = matches.value_of("ai").unwrap_or("alphabeta");
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let make_ai = |depth| ai_with_depth(name, depth);
+    if let Err(error) = kalaha::engine::serve(&format!("kalaha {}", name), &make_ai, stdin.lock(), stdout.lock()) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+// Hosts games over TCP until stopped, or until --games have been played
+fn serve(matches: &ArgMatches) {
+    let addr = matches.value_of("addr").unwrap_or("0.0.0.0:7878");
+    let games = matches.value_of("games").map(|s| s.parse().unwrap());
+    let timeout = matches.value_of("timeout").map(|s| Duration::from_secs(s.parse().unwrap()));
+    let server = match Server::bind(addr, game_from_args(matches)) {
+        Ok(server) => server.with_timeout(timeout),
+        Err(error) => {
+            eprintln!("Couldn't listen on {}: {}", addr, error);
+            process::exit(1);
+        },
+    };
+    println!("Listening on {}", server.local_addr().unwrap());
+    if let Err(error) = server.run(games, true) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+// Joins a game on a server and plays it with an AI
+fn client(matches: &ArgMatches) {
+    let addr = matches.value_of("addr").unwrap_or("localhost:7878");
+    let ai = ai_from_arg(matches.value_of("ai").unwrap_or("alphabeta"), matches.value_of("depth"));
+    let name = matches.value_of("name").map_or_else(|| format!("{:?}", ai), str::to_string);
+    match kalaha::server::connect(addr, &name, &*ai, matches.is_present("verbose")) {
+        Ok(result) => println!("Played as {:?}: {}", result.side, result.game_result),
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        },
+    }
+}
+
+fn multi(matches: &ArgMatches) {
+    let seats: usize = matches.value_of("seats").map_or(3, |s| s.parse().unwrap());
+    let depth: u32 = matches.value_of("depth").map_or(4, |s| s.parse().unwrap());
+    let names: Vec<&str> = matches.value_of("ais").map_or(vec!["maxn"; seats], |s| s.split(',').collect());
+    if names.len() != seats {
+        panic!("Need an AI for each of the {} seats", seats);
+    }
+    let ais: Vec<Box<dyn ai::MultiAI>> = names.iter().map(|name| multi_ai_from_arg(name, depth)).collect();
+    let ais: Vec<&dyn ai::MultiAI> = ais.iter().map(|ai| &**ai).collect();
+    if matches.is_present("setup") {
+        eprintln!("--setup is only for two players");
+        process::exit(1);
+    }
+    let mut game = MultiKalaha::with_rules(seats, rules_from_args(matches)).unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        process::exit(1);
+    });
+    println!("{:?}", ais);
+    match game.play(&ais, matches.is_present("verbose")) {
+        Ok(ranking) => println!("{}", ranking),
+        Err((seat, fault)) => println!("Seat {} failed: {:?}", seat, fault),
+    }
+}
+
+// The moves from --moves or the --game file, None if neither was given
+fn moves_from_args(matches: &ArgMatches) -> Result<Option<Vec<Move>>, String> {
+    let moves = match (matches.value_of("moves"), matches.value_of("game")) {
+        (Some(moves), _) => moves.to_string(),
+        (None, Some(path)) => fs::read_to_string(path).map_err(|error| format!("Couldn't read {}: {}", path, error))?,
+        (None, None) => return Ok(None),
+    };
+    moves.split_whitespace().map(str::parse).collect::<Result<Vec<Move>, String>>().map(Some)
+}
+
+fn exit_on_error(result: Result<String, String>) {
+    match result {
+        Ok(done) => println!("{}", done),
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        },
+    }
+}
+
+// Draws the position from the arguments as SVG, or every position of a game played from it
+// into a directory
+fn render(matches: &ArgMatches) {
+    let game = game_from_args(matches);
+    let result = moves_from_args(matches).and_then(|moves| match moves {
+        Some(moves) => {
+            let dir = Path::new(matches.value_of("out").unwrap_or("boards"));
+            let diagrams = render::replay(&game, &moves)?;
+            fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+            for (i, diagram) in diagrams.iter().enumerate() {
+                fs::write(dir.join(format!("{:03}.svg", i)), diagram.to_svg()).map_err(|error| error.to_string())?;
+            }
+            Ok(format!("Wrote {} diagrams to {}", diagrams.len(), dir.display()))
+        },
+        None => {
+            let path = matches.value_of("out").unwrap_or("board.svg");
+            fs::write(path, Diagram::new(&game).to_svg())
+                .map(|_| format!("Wrote {}", path))
+                .map_err(|error| error.to_string())
+        },
+    });
+    exit_on_error(result);
+}
+
+// Writes a game as an HTML page to step through, either the moves given or a game played
+// between two AIs from the position
+fn replay(matches: &ArgMatches) {
+    let game = game_from_args(matches);
+    let result = moves_from_args(matches).and_then(|moves| {
+        let mut replay = match moves {
+            Some(moves) => Replay::new(&game, &moves)?,
+            None => {
+                let ai_one = ai_from_arg(matches.value_of("ai_one").unwrap_or("alphabeta"), matches.value_of("depth"));
+                let ai_two = ai_from_arg(matches.value_of("ai_two").unwrap_or("alphabeta"), matches.value_of("depth"));
+                let report = game.clone().play(&*ai_one, &*ai_two, IllegalMovePolicy::Forfeit, false);
+                let title = format!("{:?} vs {:?}", ai_one, ai_two);
+                Replay::new(&game, &report.moves)?.with_result(report.game_result).with_title(&title)
+            },
+        };
+        if let Some(title) = matches.value_of("title") {
+            replay = replay.with_title(title);
+        }
+        if !matches.is_present("no_eval") {
+            let depth = matches.value_of("eval_depth").map_or(6, |s| s.parse().unwrap());
+            replay.evaluate(&*ai_with_depth(matches.value_of("eval").unwrap_or("alphabeta"), depth));
+        }
+        let path = matches.value_of("out").unwrap_or("replay.html");
+        fs::write(path, replay.to_html()).map_err(|error| error.to_string())?;
+        Ok(format!("Wrote {}", path))
+    });
+    exit_on_error(result);
+}
+
 fn main() {
     let matches = App::new("Kalaha simulator")
         .arg(Arg::with_name("ai_one")
             .short("a")
             .long("ai_one")
             .value_name("AI")
-            .possible_values(&["first", "last", "minmax", "alphabeta"])
+            .possible_values(&["first", "last", "minmax", "alphabeta", "caching"])
             .help("AI for player one"))
         .arg(Arg::with_name("ai_two")
             .short("b")
             .long("ai_two")
             .value_name("AI")
-            .possible_values(&["first", "last", "minmax", "alphabeta"])
+            .possible_values(&["first", "last", "minmax", "alphabeta", "caching"])
             .help("AI for player two"))
         .arg(Arg::with_name("ai_one_depth")
             .short("d")
             .long("ai_one_depth")
             .value_name("depth")
             .help("depth for AI one (if required)")
-            .required_ifs(&[("ai_one", "minmax"), ("ai_one", "alphabeta")]))
+            .required_ifs(&[("ai_one", "minmax"), ("ai_one", "alphabeta"), ("ai_one", "caching")]))
         .arg(Arg::with_name("ai_two_depth")
             .short("e")
             .long("ai_two_depth")
             .value_name("depth")
             .help("depth for AI two (if required)")
-            .required_ifs(&[("ai_two", "minmax"), ("ai_two", "alphabeta")]))
+            .required_ifs(&[("ai_two", "minmax"), ("ai_two", "alphabeta"), ("ai_two", "caching")]))
+        .arg(Arg::with_name("engine_one")
+            .long("engine_one")
+            .value_name("COMMAND")
+            .conflicts_with("ai_one")
+            .help("program to run for player one, speaking the engine protocol"))
+        .arg(Arg::with_name("engine_two")
+            .long("engine_two")
+            .value_name("COMMAND")
+            .conflicts_with("ai_two")
+            .help("program to run for player two, speaking the engine protocol"))
+        .arg(Arg::with_name("illegal_move")
+            .short("i")
+            .long("illegal_move")
+            .value_name("POLICY")
+            .possible_values(&["forfeit", "retry", "random"])
+            .help("what to do when an AI fails to make a legal move (default forfeit)"))
+        .arg(Arg::with_name("retries")
+            .short("r")
+            .long("retries")
+            .value_name("count")
+            .help("number of retries for the retry policy (default 3)"))
+        .arg(Arg::with_name("rules")
+            .long("rules")
+            .value_name("RULES")
+            .possible_values(&["kalah", "oware"])
+            .global(true)
+            .help("rules to play by (default kalah)"))
+        .arg(Arg::with_name("capture")
+            .long("capture")
+            .value_name("POLICY")
+            .possible_values(&["always", "opposite", "never"])
+            .global(true)
+            .help("when kalah captures happen: always, only if the opposite pond has seeds, or never \
+                   (default always)"))
+        .arg(Arg::with_name("sweep")
+            .long("sweep")
+            .value_name("POLICY")
+            .possible_values(&["own", "ran_out"])
+            .global(true)
+            .help("who gets the seeds left when a kalah player runs out: the player they belong to, \
+                   or the player who ran out (default own)"))
+        .arg(Arg::with_name("majority")
+            .long("majority")
+            .global(true)
+            .help("end the game as soon as a player has banked more than half the seeds"))
+        .arg(Arg::with_name("sowing")
+            .long("sowing")
+            .value_name("DIRECTION")
+            .possible_values(&["ccw", "cw", "choice"])
+            .global(true)
+            .help("which way seeds are sown: counter-clockwise, clockwise, or the player picks each move \
+                   (default ccw)"))
+        .arg(Arg::with_name("relay")
+            .long("relay")
+            .global(true)
+            .help("keep sowing from the last kalah pond when it wasn't empty"))
+        .arg(Arg::with_name("pie")
+            .long("pie")
+            .global(true)
+            .help("let player B swap sides after player A's first move"))
+        .arg(Arg::with_name("setup")
+            .long("setup")
+            .value_name("POSITION")
+            .global(true)
+            .help("start from this position instead, written as A's ponds/A's bank/B's ponds/B's bank/player \
+                   to move, e.g. 6,6,6,6,6,6/0/6,6,6,6,6,6/0/A"))
+        .arg(Arg::with_name("any_total")
+            .long("any_total")
+            .global(true)
+            .help("allow a --setup with a different number of seeds than the rules start with"))
         .arg(Arg::with_name("verbose")
             .short("v")
             .help("Use verbose mode"))
+        .subcommand(SubCommand::with_name("perft")
+            .about("Count the positions reachable from the start to check and time the rules engine")
+            .arg(Arg::with_name("depth")
+                .short("d")
+                .long("depth")
+                .value_name("depth")
+                .help("maximum number of moves to search (default 8)")))
+        .subcommand(SubCommand::with_name("engine")
+            .about("Play as an engine over the engine protocol on stdin and stdout")
+            .arg(Arg::with_name("ai")
+                .long("ai")
+                .value_name("AI")
+                .possible_values(&["first", "last", "minmax", "alphabeta", "caching"])
+                .help("AI to answer with, searching to the depth it is asked for (default alphabeta)")))
+        .subcommand(SubCommand::with_name("serve")
+            .about("Host games over TCP between pairs of clients as they connect")
+            .arg(Arg::with_name("addr")
+                .long("addr")
+                .value_name("address")
+                .help("address to listen on (default 0.0.0.0:7878)"))
+            .arg(Arg::with_name("games")
+                .long("games")
+                .value_name("count")
+                .help("stop after this many games (default never)"))
+            .arg(Arg::with_name("timeout")
+                .long("timeout")
+                .value_name("seconds")
+                .help("forfeit a client that takes longer than this to answer (default never)")))
+        .subcommand(SubCommand::with_name("client")
+            .about("Join a game on a server and play it with an AI")
+            .arg(Arg::with_name("addr")
+                .long("addr")
+                .value_name("address")
+                .help("server to connect to (default localhost:7878)"))
+            .arg(Arg::with_name("name")
+                .long("name")
+                .value_name("name")
+                .help("name to join with (default the AI)"))
+            .arg(Arg::with_name("ai")
+                .long("ai")
+                .value_name("AI")
+                .possible_values(&["first", "last", "minmax", "alphabeta", "caching"])
+                .help("AI to play with (default alphabeta)"))
+            .arg(Arg::with_name("depth")
+                .short("d")
+                .long("depth")
+                .value_name("depth")
+                .help("search depth for the AI (default 6)"))
+            .arg(Arg::with_name("verbose")
+                .short("v")
+                .help("Use verbose mode")))
+        .subcommand(SubCommand::with_name("multi")
+            .about("Play kalah with more than two players")
+            .arg(Arg::with_name("seats")
+                .short("n")
+                .long("seats")
+                .value_name("count")
+                .possible_values(&["2", "3", "4", "5", "6"])
+                .help("number of players (default 3)"))
+            .arg(Arg::with_name("ais")
+                .long("ais")
+                .value_name("AIS")
+                .help("comma separated AI for each seat, from first, last and maxn (default all maxn)"))
+            .arg(Arg::with_name("depth")
+                .short("d")
+                .long("depth")
+                .value_name("depth")
+                .help("search depth for maxn (default 4)"))
+            .arg(Arg::with_name("verbose")
+                .short("v")
+                .help("Use verbose mode")))
+        .subcommand(SubCommand::with_name("render")
+            .about("Draw the position as SVG, or every position of a game into a directory")
+            .arg(Arg::with_name("moves")
+                .long("moves")
+                .value_name("MOVES")
+                .help("moves to play from the position, separated by spaces, e.g. \"2 5 3cw swap\""))
+            .arg(Arg::with_name("game")
+                .long("game")
+                .value_name("FILE")
+                .conflicts_with("moves")
+                .help("file of moves to play from the position, separated by spaces or lines"))
+            .arg(Arg::with_name("out")
+                .short("o")
+                .long("out")
+                .value_name("PATH")
+                .help("file to write the position to (default board.svg), or with moves the directory to \
+                       write 000.svg for the start, 001.svg after the first move and so on (default boards)")))
+        .subcommand(SubCommand::with_name("replay")
+            .about("Write a game as an HTML page to step through, with an AI's evaluation of every position")
+            .arg(Arg::with_name("moves")
+                .long("moves")
+                .value_name("MOVES")
+                .help("moves to play from the position, separated by spaces (default a game between the AIs)"))
+            .arg(Arg::with_name("game")
+                .long("game")
+                .value_name("FILE")
+                .conflicts_with("moves")
+                .help("file of moves to play from the position, separated by spaces or lines"))
+            .arg(Arg::with_name("ai_one")
+                .short("a")
+                .long("ai_one")
+                .value_name("AI")
+                .possible_values(&["first", "last", "minmax", "alphabeta", "caching"])
+                .help("AI to play player one without moves (default alphabeta)"))
+            .arg(Arg::with_name("ai_two")
+                .short("b")
+                .long("ai_two")
+                .value_name("AI")
+                .possible_values(&["first", "last", "minmax", "alphabeta", "caching"])
+                .help("AI to play player two without moves (default alphabeta)"))
+            .arg(Arg::with_name("depth")
+                .short("d")
+                .long("depth")
+                .value_name("depth")
+                .help("search depth for the playing AIs (default 6)"))
+            .arg(Arg::with_name("eval")
+                .long("eval")
+                .value_name("AI")
+                .possible_values(&["minmax", "alphabeta", "caching"])
+                .help("AI to evaluate every position with (default alphabeta)"))
+            .arg(Arg::with_name("eval_depth")
+                .long("eval_depth")
+                .value_name("depth")
+                .help("search depth for the evaluating AI (default 6)"))
+            .arg(Arg::with_name("no_eval")
+                .long("no_eval")
+                .conflicts_with_all(&["eval", "eval_depth"])
+                .help("leave out the evaluation bar"))
+            .arg(Arg::with_name("title")
+                .long("title")
+                .value_name("TITLE")
+                .help("heading for the page"))
+            .arg(Arg::with_name("out")
+                .short("o")
+                .long("out")
+                .value_name("PATH")
+                .help("file to write the page to (default replay.html)")))
         .get_matches();
 
-    let ai_one = ai_from_arg(
-        matches.value_of("ai_one").unwrap_or("minmax"),
-        matches.value_of("ai_one_depth"),
-    );
-    let ai_two = ai_from_arg(
-        matches.value_of("ai_two").unwrap_or("alphabeta"),
-        matches.value_of("ai_two_depth"),
+    if let Some(matches) = matches.subcommand_matches("perft") {
+        perft(matches);
+        return;
+    }
+    if let Some(matches) = matches.subcommand_matches("multi") {
+        multi(matches);
+        return;
+    }
+    if let Some(matches) = matches.subcommand_matches("engine") {
+        engine(matches);
+        return;
+    }
+    if let Some(matches) = matches.subcommand_matches("serve") {
+        serve(matches);
+        return;
+    }
+    if let Some(matches) = matches.subcommand_matches("client") {
+        client(matches);
+        return;
+    }
+    if let Some(matches) = matches.subcommand_matches("render") {
+        render(matches);
+        return;
+    }
+    if let Some(matches) = matches.subcommand_matches("replay") {
+        replay(matches);
+        return;
+    }
+
+    let ai_one = match matches.value_of("engine_one") {
+        Some(command) => external_from_arg(command, matches.value_of("ai_one_depth")),
+        None => ai_from_arg(matches.value_of("ai_one").unwrap_or("minmax"), matches.value_of("ai_one_depth")),
+    };
+    let ai_two = match matches.value_of("engine_two") {
+        Some(command) => external_from_arg(command, matches.value_of("ai_two_depth")),
+        None => ai_from_arg(matches.value_of("ai_two").unwrap_or("alphabeta"), matches.value_of("ai_two_depth")),
+    };
+    let policy = policy_from_arg(
+        matches.value_of("illegal_move").unwrap_or("forfeit"),
+        matches.value_of("retries"),
     );
+    let game = game_from_args(&matches);
     let verbose = matches.is_present("verbose");
 
     println!("Player A {:?} vs Player B {:?}", ai_one, ai_two);
-    let mut game = Kalaha::new();
-    game.play(&ai_one, &ai_two, verbose);
-    println!("{}", game.game_result());
+    play(&mut game.clone(), &*ai_one, &*ai_two, policy, verbose);
 
     println!("Player A {:?} vs Player B {:?}", ai_two, ai_one);
-    let mut game = Kalaha::new();
-    game.play(&ai_two, &ai_one, verbose);
-    println!("{}", game.game_result());
+    play(&mut game.clone(), &*ai_two, &*ai_one, policy, verbose);
 }
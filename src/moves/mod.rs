@@ -0,0 +1,164 @@
+use std::fmt;
+use std::ops;
+use std::slice;
+use std::str::FromStr;
+
+use rules::Direction;
+
+#[cfg(test)]
+mod tests;
+
+// Every pond both ways plus a swap
+const MAX_MOVES: usize = 2 * super::PONDS_PER_PLAYER + 1;
+
+// Sorts ponds in order, then directed ponds, then swap
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum Move {
+    // sow the seeds from one of the current player's ponds, in the direction the rules fix
+    Pond(usize),
+    // sow the seeds from a pond in the given direction, when the rules let the player choose
+    Directed(usize, Direction),
+    // take over the opponent's position, when the pie rule allows it
+    Swap,
+}
+
+impl Move {
+    // the pond sown from, None for a swap
+    pub fn pond(&self) -> Option<usize> {
+        match *self {
+            Move::Pond(pond) | Move::Directed(pond, _) => Some(pond),
+            Move::Swap => None,
+        }
+    }
+}
+
+impl From<usize> for Move {
+    fn from(pond: usize) -> Move {
+        Move::Pond(pond)
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Move::Pond(pond) => f.write_str(&pond.to_string()),
+            Move::Directed(pond, Direction::CounterClockwise) => f.write_str(&format!("{}ccw", pond)),
+            Move::Directed(pond, Direction::Clockwise) => f.write_str(&format!("{}cw", pond)),
+            Move::Swap => f.write_str("swap"),
+        }
+    }
+}
+
+// Reads the Display form back, e.g. `3`, `3cw`, `3ccw` or `swap`
+impl FromStr for Move {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Move, String> {
+        let s = s.trim();
+        if s == "swap" {
+            return Ok(Move::Swap);
+        }
+        let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let pond = s[..digits].parse().map_err(|_| format!("'{}' is not a move", s))?;
+        match &s[digits..] {
+            "" => Ok(Move::Pond(pond)),
+            "ccw" => Ok(Move::Directed(pond, Direction::CounterClockwise)),
+            "cw" => Ok(Move::Directed(pond, Direction::Clockwise)),
+            _ => Err(format!("'{}' is not a move", s)),
+        }
+    }
+}
+
+// A fixed capacity list of moves, so enumerating moves doesn't allocate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub fn new() -> MoveList {
+        MoveList { moves: [Move::Swap; MAX_MOVES], len: 0 }
+    }
+
+    // panics if the list is full
+    pub fn push(&mut self, mv: Move) {
+        assert!(self.len < self.moves.len(), "Move list is full");
+        self.moves[self.len] = mv;
+        self.len += 1;
+    }
+
+    pub fn as_slice(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> MoveList {
+        MoveList::new()
+    }
+}
+
+impl ops::Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        self.as_slice()
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> slice::Iter<'a, Move> {
+        self.as_slice().iter()
+    }
+}
+
+impl IntoIterator for MoveList {
+    type Item = Move;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter { list: self, front: 0, back: self.len }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IntoIter {
+    list: MoveList,
+    front: usize,
+    back: usize,
+}
+
+impl Iterator for IntoIter {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        if self.front < self.back {
+            self.front += 1;
+            Some(self.list.moves[self.front - 1])
+        } else {
+            None
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.back - self.front;
+        (remaining, Some(remaining))
+    }
+}
+
+impl DoubleEndedIterator for IntoIter {
+    fn next_back(&mut self) -> Option<Move> {
+        if self.front < self.back {
+            self.back -= 1;
+            Some(self.list.moves[self.back])
+        } else {
+            None
+        }
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
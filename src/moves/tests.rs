@@ -0,0 +1,81 @@
+use super::*;
+
+fn list_of(ponds: &[usize]) -> MoveList {
+    let mut list = MoveList::new();
+    for &pond in ponds {
+        list.push(Move::Pond(pond));
+    }
+    list
+}
+
+#[test]
+fn new_is_empty() {
+    assert!(MoveList::new().is_empty());
+}
+
+#[test]
+fn push() {
+    let list = list_of(&[1, 3, 4]);
+    assert_eq!(list.len(), 3);
+    assert_eq!(list.as_slice(), &[Move::Pond(1), Move::Pond(3), Move::Pond(4)]);
+}
+
+#[test]
+#[should_panic(expected = "Move list is full")]
+fn push_full() {
+    let mut list = MoveList::new();
+    for pond in 0..6 {
+        list.push(Move::Directed(pond, Direction::CounterClockwise));
+        list.push(Move::Directed(pond, Direction::Clockwise));
+    }
+    list.push(Move::Swap);
+    list.push(Move::Swap);
+}
+
+#[test]
+fn into_iter() {
+    let list = list_of(&[0, 2, 5]);
+    assert_eq!(list.into_iter().collect::<Vec<_>>(), vec![Move::Pond(0), Move::Pond(2), Move::Pond(5)]);
+    assert_eq!(list.into_iter().rev().collect::<Vec<_>>(), vec![Move::Pond(5), Move::Pond(2), Move::Pond(0)]);
+}
+
+#[test]
+fn into_iter_both_ends() {
+    let mut iter = list_of(&[0, 2, 5]).into_iter();
+    assert_eq!(iter.next(), Some(Move::Pond(0)));
+    assert_eq!(iter.next_back(), Some(Move::Pond(5)));
+    assert_eq!(iter.len(), 1);
+    assert_eq!(iter.next(), Some(Move::Pond(2)));
+    assert_eq!(iter.next_back(), None);
+}
+
+#[test]
+fn move_order() {
+    assert!(Move::Pond(0) < Move::Pond(5));
+    assert!(Move::Pond(5) < Move::Swap);
+}
+
+#[test]
+fn move_display() {
+    assert_eq!(Move::Pond(3).to_string(), "3");
+    assert_eq!(Move::Directed(3, Direction::Clockwise).to_string(), "3cw");
+    assert_eq!(Move::Directed(3, Direction::CounterClockwise).to_string(), "3ccw");
+    assert_eq!(Move::Swap.to_string(), "swap");
+}
+
+#[test]
+fn move_from_str() {
+    let moves = [Move::Pond(3), Move::Directed(3, Direction::Clockwise), Move::Directed(0, Direction::CounterClockwise)];
+    for mv in moves.iter().chain(&[Move::Swap]) {
+        assert_eq!(mv.to_string().parse(), Ok(*mv));
+    }
+    assert_eq!("3x".parse::<Move>(), Err("'3x' is not a move".to_string()));
+    assert_eq!("cw".parse::<Move>(), Err("'cw' is not a move".to_string()));
+}
+
+#[test]
+fn move_pond() {
+    assert_eq!(Move::Pond(3).pond(), Some(3));
+    assert_eq!(Move::Directed(4, Direction::Clockwise).pond(), Some(4));
+    assert_eq!(Move::Swap.pond(), None);
+}
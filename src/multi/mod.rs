@@ -0,0 +1,259 @@
+// Kalah for more than two players. Each seat has a row of ponds and a bank, and the rows
+// are joined up in a ring so seeds go from one seat's bank onto the next seat's row.
+// Sowing and captures are shared with Board, so with two seats this plays exactly like
+// the two player game under the same rules.
+use std::fmt;
+use std::ops;
+
+use super::ai::MultiAI;
+use super::board::sow_kalah;
+use super::error::Error;
+use super::kalaha::Fault;
+use super::moves::{Move, MoveList};
+use super::rules::{Rules, Variant, SweepPolicy, SowingPolicy, Direction};
+use super::turn::{Ending, Ranking, seat_name};
+
+#[cfg(test)]
+mod tests;
+
+pub const MIN_SEATS: usize = 2;
+pub const MAX_SEATS: usize = 6;
+
+// ponds plus a bank
+const POOLS_PER_SEAT: usize = super::PONDS_PER_PLAYER + 1;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum MultiTurn {
+    Seat(usize),
+    Finished(Ranking, Ending),
+}
+
+impl MultiTurn {
+    pub fn is_finished(&self) -> bool {
+        match *self {
+            MultiTurn::Seat(_) => false,
+            MultiTurn::Finished(..) => true,
+        }
+    }
+
+    pub fn seat(&self) -> usize {
+        match *self {
+            MultiTurn::Seat(seat) => seat,
+            MultiTurn::Finished(..) => panic!("Game has finished"),
+        }
+    }
+
+    pub fn ranking(&self) -> &Ranking {
+        match *self {
+            MultiTurn::Seat(_) => panic!("Game has not finished"),
+            MultiTurn::Finished(ref ranking, _) => ranking,
+        }
+    }
+}
+
+// Seat s has ponds s * 7 to s * 7 + 5 and its bank at s * 7 + 6
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct MultiKalaha {
+    pits: Vec<u32>,
+    seats: usize,
+    rules: Rules,
+    turn_seat: Option<usize>,
+    // how the game ended, once there's no turn_seat
+    ending: Ending,
+}
+
+impl MultiKalaha {
+    // panics unless seats is between MIN_SEATS and MAX_SEATS
+    pub fn new(seats: usize) -> MultiKalaha {
+        MultiKalaha::with_rules(seats, Rules::kalah()).expect("Kalah rules work for any seats")
+    }
+
+    // Kalah's capture, sweep, relay and majority rules carry over to more seats, but Oware,
+    // sowing clockwise or by choice and the pie rule don't, so those are an error.
+    // panics unless seats is between MIN_SEATS and MAX_SEATS
+    pub fn with_rules(seats: usize, rules: Rules) -> Result<MultiKalaha, String> {
+        assert!((MIN_SEATS..=MAX_SEATS).contains(&seats), "Need {} to {} seats", MIN_SEATS, MAX_SEATS);
+        if rules.variant != Variant::Kalah {
+            return Err("more than two players can only play kalah".to_string());
+        } else if rules.sowing != SowingPolicy::Fixed(Direction::CounterClockwise) {
+            return Err("more than two players can only sow counter-clockwise".to_string());
+        } else if rules.pie_rule {
+            return Err("more than two players can't play the pie rule".to_string());
+        }
+        let mut pits = vec![rules.seeds_per_pond(); seats * POOLS_PER_SEAT];
+        for seat in 0..seats {
+            pits[bank_idx(seat)] = 0;
+        }
+        Ok(MultiKalaha { pits, seats, rules, turn_seat: Some(0), ending: Ending::SideEmpty })
+    }
+
+    pub fn seats(&self) -> usize {
+        self.seats
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn bank(&self, seat: usize) -> u32 {
+        self.pits[bank_idx(seat)]
+    }
+
+    pub fn banks(&self) -> Vec<u32> {
+        (0..self.seats).map(|seat| self.bank(seat)).collect()
+    }
+
+    pub fn ponds(&self, seat: usize) -> [u32; 6] {
+        let mut res = [0; 6];
+        res.copy_from_slice(&self.pits[pond_idxs(seat)]);
+        res
+    }
+
+    pub fn turn(&self) -> MultiTurn {
+        match self.turn_seat {
+            Some(seat) => MultiTurn::Seat(seat),
+            None => MultiTurn::Finished(Ranking::from_scores(&self.banks()), self.ending),
+        }
+    }
+
+    // panics if the game has finished
+    pub fn current_seat(&self) -> usize {
+        self.turn_seat.expect("Game has finished")
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.turn_seat.is_none()
+    }
+
+    pub fn valid_move(&self, pond: usize) -> Result<(), Error> {
+        match self.turn_seat {
+            None => Err(Error::GameFinished),
+            Some(_) if pond >= super::PONDS_PER_PLAYER => Err(Error::InvalidIndex),
+            Some(seat) if self.pits[pond_idx(seat, pond)] == 0 => Err(Error::EmptyPool),
+            Some(_) => Ok(()),
+        }
+    }
+
+    pub fn legal_moves(&self) -> MoveList {
+        let mut moves = MoveList::new();
+        for pond in 0..super::PONDS_PER_PLAYER {
+            if self.valid_move(pond).is_ok() {
+                moves.push(Move::Pond(pond));
+            }
+        }
+        moves
+    }
+
+    // panics if pond is not a valid_move
+    pub fn choose(&mut self, pond: usize) -> MultiTurn {
+        self.valid_move(pond).expect("Invalid move");
+        let seat = self.current_seat();
+        // every bank but your own is skipped
+        let (idx, _) = sow_kalah(&mut self.pits, pond_idx(seat, pond), Direction::CounterClockwise, self.rules.max_laps,
+                                 is_bank, |idx| is_bank(idx) && idx != bank_idx(seat), |_| ());
+        // ended in an empty pond of your own, so take it and the pond facing it, which is
+        // on the next seat's row like it is with two players
+        if !is_bank(idx) && idx / POOLS_PER_SEAT == seat && self.pits[idx] == 1 {
+            let opposite = pond_idx((seat + 1) % self.seats, super::PONDS_PER_PLAYER - 1 - idx % POOLS_PER_SEAT);
+            if self.rules.capture.captures(self.pits[opposite]) {
+                self.pits[bank_idx(seat)] += 1 + self.pits[opposite];
+                self.pits[idx] = 0;
+                self.pits[opposite] = 0;
+            }
+        }
+        self.turn_seat = match self.handle_finish() {
+            Some(ending) => {
+                self.ending = ending;
+                None
+            },
+            None if idx == bank_idx(seat) => Some(seat),
+            None => Some((seat + 1) % self.seats),
+        };
+        self.turn()
+    }
+
+    // Once any seat runs out of seeds, what's left on each row is banked by its seat, or
+    // all of it by the seat that ran out under SweepPolicy::PlayerWhoRanOut
+    fn handle_finish(&mut self) -> Option<Ending> {
+        let ran_out = (0..self.seats).find(|&seat| self.pits[pond_idxs(seat)].iter().all(|&count| count == 0));
+        if let Some(ran_out) = ran_out {
+            for seat in 0..self.seats {
+                let left: u32 = self.pits[pond_idxs(seat)].iter().sum();
+                for idx in pond_idxs(seat) {
+                    self.pits[idx] = 0;
+                }
+                let to = match self.rules.sweep {
+                    SweepPolicy::OwnSide => seat,
+                    SweepPolicy::PlayerWhoRanOut => ran_out,
+                };
+                self.pits[bank_idx(to)] += left;
+            }
+            Some(Ending::SideEmpty)
+        } else if self.rules.end_on_majority && self.has_majority() {
+            Some(Ending::Majority)
+        } else {
+            None
+        }
+    }
+
+    fn has_majority(&self) -> bool {
+        let total: u32 = self.pits.iter().sum();
+        self.banks().iter().any(|&bank| bank * 2 > total)
+    }
+
+    // Plays the game out with ais[seat] choosing for each seat. Stops at the first AI that
+    // fails or makes an illegal move, returning its seat and what went wrong.
+    pub fn play(&mut self, ais: &[&dyn MultiAI], verbose: bool) -> Result<Ranking, (usize, Fault)> {
+        assert_eq!(ais.len(), self.seats, "Need an AI for every seat");
+        if verbose {
+            println!("{}", self);
+        }
+        while let Some(seat) = self.turn_seat {
+            let pond = match ais[seat].choose(self) {
+                Ok(pond) => pond,
+                Err(error) => return Err((seat, Fault::Failed(error))),
+            };
+            if let Err(error) = self.valid_move(pond) {
+                return Err((seat, Fault::IllegalMove(Move::Pond(pond), error)));
+            }
+            if verbose {
+                println!("{} chose pond {}\n", seat_name(seat), pond);
+            }
+            self.choose(pond);
+            if verbose {
+                println!("{}", self);
+            }
+        }
+        Ok(self.turn().ranking().clone())
+    }
+}
+
+fn pond_idx(seat: usize, pond: usize) -> usize {
+    seat * POOLS_PER_SEAT + pond
+}
+
+fn pond_idxs(seat: usize) -> ops::Range<usize> {
+    pond_idx(seat, 0)..pond_idx(seat, super::PONDS_PER_PLAYER)
+}
+
+fn bank_idx(seat: usize) -> usize {
+    pond_idx(seat, super::PONDS_PER_PLAYER)
+}
+
+fn is_bank(idx: usize) -> bool {
+    idx % POOLS_PER_SEAT == super::PONDS_PER_PLAYER
+}
+
+// One line per seat in sowing order, ponds then bank
+impl fmt::Display for MultiKalaha {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        for seat in 0..self.seats {
+            let ponds: Vec<String> = self.ponds(seat).iter().map(|count| format!("{:2}", count)).collect();
+            f.write_str(&format!("{}: {} | {:2}\n", seat_name(seat), ponds.join(" "), self.bank(seat)))?;
+        }
+        match self.turn() {
+            MultiTurn::Seat(seat) => f.write_str(&format!("Next turn: {}\n", seat_name(seat))),
+            MultiTurn::Finished(ranking, _) => f.write_str(&format!("Game finished: {}\n", ranking)),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use super::*;
+use ai::FirstValid;
+use kalaha::Kalaha;
+use player::Player;
+use rng::Rng;
+use rules::CapturePolicy;
+use turn::GameResult;
+
+fn game_from_counts(counts: &[u32], seat: usize) -> MultiKalaha {
+    game_with_rules(counts, seat, Rules::kalah())
+}
+
+fn game_with_rules(counts: &[u32], seat: usize, rules: Rules) -> MultiKalaha {
+    let mut game = MultiKalaha::with_rules(counts.len() / POOLS_PER_SEAT, rules).unwrap();
+    game.pits = counts.to_vec();
+    game.turn_seat = Some(seat);
+    game
+}
+
+#[test]
+fn new_game() {
+    let game = MultiKalaha::new(3);
+    assert_eq!(game.seats(), 3);
+    assert_eq!(game.banks(), vec![0, 0, 0]);
+    assert_eq!(game.ponds(2), [::INIT_COUNT; 6]);
+    assert_eq!(game.turn(), MultiTurn::Seat(0));
+}
+
+#[test]
+#[should_panic(expected = "Need 2 to 6 seats")]
+fn too_many_seats() {
+    MultiKalaha::new(7);
+}
+
+#[test]
+fn two_player_only_rules() {
+    let error = |rules| MultiKalaha::with_rules(3, rules).unwrap_err();
+    assert_eq!(error(Rules::oware()), "more than two players can only play kalah");
+    assert_eq!(error(Rules::kalah().with_sowing(SowingPolicy::PlayerChoice)),
+               "more than two players can only sow counter-clockwise");
+    assert_eq!(error(Rules::kalah().with_pie_rule(true)), "more than two players can't play the pie rule");
+    assert!(MultiKalaha::with_rules(3, Rules::kalah().with_relay().with_end_on_majority(true)).is_ok());
+}
+
+#[test]
+fn valid_move() {
+    let mut game = MultiKalaha::new(4);
+    assert_eq!(game.valid_move(6), Err(Error::InvalidIndex));
+    game.choose(0);
+    assert_eq!(game.current_seat(), 0);
+    assert_eq!(game.valid_move(0), Err(Error::EmptyPool));
+}
+
+#[test]
+fn sowing_skips_other_banks() {
+    let mut game = game_from_counts(&[1,0,0,0,0,14,0, 1,1,1,1,1,1,0, 1,1,1,1,1,1,0], 0);
+    assert_eq!(game.choose(5), MultiTurn::Seat(1));
+    assert_eq!(game.pits, vec![2,0,0,0,0,0,1, 2,2,2,2,2,2,0, 2,2,2,2,2,2,0]);
+}
+
+#[test]
+fn turn_goes_round_the_ring() {
+    let mut game = MultiKalaha::new(3);
+    // landing in your own bank goes again
+    assert_eq!(game.choose(0), MultiTurn::Seat(0));
+    assert_eq!(game.choose(1), MultiTurn::Seat(1));
+    assert_eq!(game.choose(1), MultiTurn::Seat(2));
+    assert_eq!(game.choose(1), MultiTurn::Seat(0));
+}
+
+#[test]
+fn capture_from_next_seat() {
+    let mut game = game_from_counts(&[1,0,1,1,1,1,0, 1,1,1,1,7,1,0, 1,1,1,1,1,1,0], 0);
+    assert_eq!(game.choose(0), MultiTurn::Seat(1));
+    assert_eq!(game.pits, vec![0,0,1,1,1,1,8, 1,1,1,1,0,1,0, 1,1,1,1,1,1,0]);
+    // the last seat faces the first
+    let mut game = game_from_counts(&[1,1,7,1,1,1,0, 1,1,1,1,1,1,0, 0,0,1,0,0,1,0], 2);
+    assert_eq!(game.choose(2), MultiTurn::Seat(0));
+    assert_eq!(game.pits, vec![1,1,0,1,1,1,0, 1,1,1,1,1,1,0, 0,0,0,0,0,1,8]);
+}
+
+#[test]
+fn capture_policy() {
+    let counts = [1,0,1,1,1,1,0, 1,1,1,1,0,1,0, 1,1,1,1,1,1,0];
+    let mut game = game_with_rules(&counts, 0, Rules::kalah().with_capture(CapturePolicy::OppositeNotEmpty));
+    game.choose(0);
+    assert_eq!(game.pits, vec![0,1,1,1,1,1,0, 1,1,1,1,0,1,0, 1,1,1,1,1,1,0]);
+    let mut game = game_with_rules(&counts, 0, Rules::kalah().with_capture(CapturePolicy::Always));
+    game.choose(0);
+    assert_eq!(game.pits, vec![0,0,1,1,1,1,1, 1,1,1,1,0,1,0, 1,1,1,1,1,1,0]);
+}
+
+#[test]
+fn relay_sowing() {
+    let mut game = game_with_rules(&[0,0,0,1,1,0,0, 1,1,1,1,1,1,0, 1,1,1,1,1,1,0], 0, Rules::kalah().with_relay());
+    // lands on the 1 in pond 4 and carries on with both seeds, the last one into the bank
+    assert_eq!(game.choose(3), MultiTurn::Seat(0));
+    assert_eq!(game.pits, vec![0,0,0,0,0,1,1, 1,1,1,1,1,1,0, 1,1,1,1,1,1,0]);
+}
+
+#[test]
+fn sweep_to_who_ran_out() {
+    let rules = Rules::kalah().with_sweep(SweepPolicy::PlayerWhoRanOut);
+    let mut game = game_with_rules(&[0,0,0,0,0,1,3, 1,0,0,0,0,0,5, 0,0,0,0,2,0,4], 0, rules);
+    assert_eq!(game.choose(5), MultiTurn::Finished(Ranking::from_scores(&[7, 5, 4]), Ending::SideEmpty));
+}
+
+#[test]
+fn majority() {
+    let rules = Rules::kalah().with_end_on_majority(true);
+    let mut game = game_with_rules(&[1,0,0,0,0,1,9, 1,0,0,0,0,0,0, 0,0,0,0,2,1,0], 0, rules);
+    // 10 of the 15 seeds, with seeds still on every row
+    assert_eq!(game.choose(5), MultiTurn::Finished(Ranking::from_scores(&[10, 0, 0]), Ending::Majority));
+    assert_eq!(game.pits[0], 1);
+}
+
+#[test]
+fn finish_ranks_everyone() {
+    let mut game = game_from_counts(&[0,0,0,0,0,1,3, 1,0,0,0,0,0,5, 0,0,0,0,2,0,4], 0);
+    assert_eq!(game.choose(5), MultiTurn::Finished(Ranking::from_scores(&[4, 6, 6]), Ending::SideEmpty));
+    assert_eq!(game.banks(), vec![4, 6, 6]);
+    assert_eq!(game.turn().ranking().winners(), vec![1, 2]);
+    assert_eq!(game.valid_move(0), Err(Error::GameFinished));
+}
+
+#[test]
+fn play_three_and_four_seats() {
+    for &seats in &[3, 4] {
+        let mut game = MultiKalaha::new(seats);
+        let ais: Vec<&dyn MultiAI> = vec![&FirstValid {}; seats];
+        let ranking = game.play(&ais, false).unwrap();
+        assert_eq!(ranking.standings.len(), seats);
+        let total: u32 = ranking.standings.iter().map(|standing| standing.score).sum();
+        assert_eq!(total, seats as u32 * 6 * ::INIT_COUNT);
+    }
+}
+
+// With two seats the ring is the ordinary board, under any rules it can play
+#[test]
+fn two_seats_same_as_kalaha() {
+    let rules = [
+        Rules::kalah(),
+        Rules::kalah().with_capture(CapturePolicy::OppositeNotEmpty).with_sweep(SweepPolicy::PlayerWhoRanOut),
+        Rules::kalah().with_capture(CapturePolicy::Never).with_relay(),
+        Rules::kalah().with_max_laps(3).with_end_on_majority(true),
+    ];
+    for (seed, &rules) in (1..100).zip(rules.iter().cycle()) {
+        let mut rng = Rng::new(seed);
+        let mut multi = MultiKalaha::with_rules(2, rules).unwrap();
+        let mut game = Kalaha::with_rules(rules);
+        while !game.is_finished() {
+            let moves = game.legal_moves();
+            let mv = moves[rng.below(moves.len())];
+            game.choose(mv);
+            multi.choose(mv.pond().unwrap());
+            assert_eq!(multi.banks(), vec![game.bank(&Player::A), game.bank(&Player::B)]);
+            assert_eq!(multi.ponds(0), game.ponds(&Player::A));
+            assert_eq!(multi.ponds(1), game.ponds(&Player::B));
+            if !game.is_finished() {
+                assert_eq!(multi.current_seat(), game.seat(game.current_player()));
+            }
+        }
+        let ending = match *game.game_result() {
+            GameResult::Winner { ending, .. } | GameResult::Draw { ending, .. } => ending,
+            GameResult::Forfeit { .. } => unreachable!(),
+        };
+        assert_eq!(multi.turn(), MultiTurn::Finished(game.game_result().ranking(), ending), "{:?}", rules);
+    }
+}
@@ -0,0 +1,54 @@
+use std::ops;
+
+use super::Kalaha;
+
+#[cfg(test)]
+mod tests;
+
+// Counts for the moves made at the last ply of a perft search
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct PerftCounts {
+    // positions reached after exactly depth moves
+    pub leaves: u64,
+    // moves that landed in the player's own bank
+    pub extra_turns: u64,
+    pub captures: u64,
+    // moves that ended the game
+    pub finished: u64,
+}
+
+impl ops::AddAssign for PerftCounts {
+    fn add_assign(&mut self, other: PerftCounts) {
+        self.leaves += other.leaves;
+        self.extra_turns += other.extra_turns;
+        self.captures += other.captures;
+        self.finished += other.finished;
+    }
+}
+
+// Walks every sequence of depth moves from game, where a move is a single pond choice
+// or a swap (so an extra turn uses up a ply). Games that finish early don't reach a leaf.
+pub fn perft(game: &Kalaha, depth: u32) -> PerftCounts {
+    perft_in_place(&mut game.clone(), depth)
+}
+
+fn perft_in_place(game: &mut Kalaha, depth: u32) -> PerftCounts {
+    let mut counts = PerftCounts::default();
+    if depth == 0 {
+        counts.leaves = 1;
+        return counts;
+    }
+    for mv in game.legal_moves() {
+        let (outcome, undo) = game.make_move(mv);
+        if depth == 1 {
+            counts.leaves += 1;
+            counts.extra_turns += outcome.extra_turn as u64;
+            counts.captures += (outcome.captured > 0) as u64;
+            counts.finished += outcome.turn.is_finished() as u64;
+        } else {
+            counts += perft_in_place(game, depth - 1);
+        }
+        game.unmake_move(undo);
+    }
+    counts
+}
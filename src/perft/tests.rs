@@ -0,0 +1,94 @@
+use super::*;
+
+// Reference counts. If one of these changes, the rules of the game have changed.
+const START: [(u64, u64, u64, u64); 7] = [
+    (6, 1, 0, 0),
+    (35, 0, 0, 0),
+    (190, 0, 0, 0),
+    (1056, 8, 1, 0),
+    (5882, 245, 51, 0),
+    (32243, 1221, 591, 0),
+    (177804, 10597, 4904, 0),
+];
+
+const ENDGAME: [(u64, u64, u64, u64); 6] = [
+    (4, 0, 3, 0),
+    (17, 4, 1, 0),
+    (66, 2, 25, 0),
+    (284, 5, 39, 1),
+    (1195, 113, 428, 2),
+    (4919, 383, 746, 9),
+];
+
+const OWARE: [(u64, u64, u64, u64); 6] = [
+    (6, 0, 0, 0),
+    (36, 0, 0, 0),
+    (190, 0, 0, 0),
+    (1014, 0, 89, 0),
+    (5219, 0, 401, 0),
+    (27332, 0, 5264, 0),
+];
+
+fn assert_counts(game: &Kalaha, expected: &[(u64, u64, u64, u64)]) {
+    for (depth, &(leaves, extra_turns, captures, finished)) in expected.iter().enumerate() {
+        assert_eq!(
+            perft(game, depth as u32 + 1),
+            PerftCounts { leaves, extra_turns, captures, finished },
+            "depth {}", depth + 1
+        );
+    }
+}
+
+// A position 23 moves into a game where every move is the first legal one,
+// close enough to the end that some lines finish the game
+fn endgame() -> Kalaha {
+    let mut game = Kalaha::new();
+    for _ in 0..23 {
+        let pond = game.legal_moves()[0];
+        game.choose(pond);
+    }
+    game
+}
+
+#[test]
+fn depth_zero() {
+    assert_eq!(perft(&Kalaha::new(), 0), PerftCounts { leaves: 1, ..PerftCounts::default() });
+}
+
+#[test]
+fn start_position() {
+    assert_counts(&Kalaha::new(), &START);
+}
+
+#[test]
+fn endgame_position() {
+    assert_counts(&endgame(), &ENDGAME);
+}
+
+#[test]
+fn finished_game_has_no_leaves() {
+    let mut game = Kalaha::new();
+    while !game.is_finished() {
+        let pond = game.legal_moves()[0];
+        game.choose(pond);
+    }
+    assert_eq!(perft(&game, 2), PerftCounts::default());
+}
+
+#[test]
+fn oware_start_position() {
+    assert_counts(&Kalaha::with_rules(::Rules::oware()), &OWARE);
+}
+
+#[test]
+fn pie_rule_adds_swaps() {
+    let game = Kalaha::with_rules(::Rules::kalah().with_pie_rule(true));
+    // every first move but the extra turn from pond 0 can be answered with a swap
+    assert_eq!(perft(&game, 2), PerftCounts { leaves: START[1].0 + 5, ..PerftCounts::default() });
+}
+
+#[test]
+fn player_choice_doubles_moves() {
+    let game = Kalaha::with_rules(::Rules::kalah().with_sowing(::SowingPolicy::PlayerChoice));
+    assert_eq!(perft(&game, 1).leaves, 12);
+}
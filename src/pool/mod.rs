@@ -45,6 +45,13 @@ impl Pool {
         }
     }
 
+    pub fn count_mut(&mut self) -> &mut u32 {
+        match *self {
+            Pool::Pond(ref mut x) => &mut x.count,
+            Pool::Bank(ref mut x) => &mut x.count,
+        }
+    }
+
     pub fn take(&mut self) -> u32 {
         match *self {
             Pool::Pond(ref mut pond) => {
@@ -20,6 +20,13 @@ fn pool_new_bank() {
     assert_eq!(pool.count(), 0);
 }
 
+#[test]
+fn pool_count_mut() {
+    let mut pool = Pool::Bank(Bank { player: Player::A, count: 4 });
+    *pool.count_mut() += 2;
+    assert_eq!(pool.count(), 6);
+}
+
 #[test]
 fn pool_take_pond() {
     let mut pool = Pool::Pond(Pond { player: Player::A, count: 4 });
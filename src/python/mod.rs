@@ -0,0 +1,282 @@
+// A Python module behind the `python` feature, for driving the engine from notebooks and
+// reinforcement learning code. Build it with maturin, which picks up pyproject.toml:
+//
+//   maturin develop --release
+//
+// and then
+//
+//   import kalaha
+//   game = kalaha.Kalaha("oware")
+//   game.choose(kalaha.AI("alphabeta", 6).choose(game))
+//
+// Boards, turns and results come back as dicts shaped like the HTTP API's JSON. Illegal
+// moves raise IllegalMoveError, a ValueError, with the Error's name as its message.
+use pyo3::create_exception;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+
+use super::ai;
+use super::json::{Json, ToJson};
+use super::kalaha::Kalaha;
+use super::moves::Move;
+use super::player::Player;
+use super::rng::Rng;
+use super::rules::Rules;
+use super::setup::{Setup, SetupError};
+
+#[cfg(test)]
+mod tests;
+
+create_exception!(kalaha, IllegalMoveError, PyValueError);
+
+fn to_python(py: Python, json: &Json) -> PyResult<Py<PyAny>> {
+    Ok(match *json {
+        Json::Null => py.None(),
+        Json::Bool(value) => value.into_pyobject(py)?.to_owned().into_any().unbind(),
+        Json::Number(number) => number.into_pyobject(py)?.into_any().unbind(),
+        Json::String(ref string) => string.into_pyobject(py)?.into_any().unbind(),
+        Json::Array(ref values) => {
+            let values = values.iter().map(|value| to_python(py, value)).collect::<PyResult<Vec<_>>>()?;
+            PyList::new(py, values)?.into_any().unbind()
+        },
+        Json::Object(ref fields) => {
+            let dict = PyDict::new(py);
+            for (key, value) in fields {
+                dict.set_item(key, to_python(py, value)?)?;
+            }
+            dict.into_any().unbind()
+        },
+    })
+}
+
+fn parse_player(player: &str) -> PyResult<Player> {
+    match player {
+        "A" | "a" => Ok(Player::A),
+        "B" | "b" => Ok(Player::B),
+        _ => Err(PyValueError::new_err(format!("'{}' is not a player, use 'A' or 'B'", player))),
+    }
+}
+
+fn parse_move(mv: &str) -> PyResult<Move> {
+    mv.parse().map_err(PyValueError::new_err)
+}
+
+fn parse_rules(rules: Option<&str>) -> PyResult<Rules> {
+    rules.map_or(Ok(Rules::default()), |rules| rules.parse().map_err(PyValueError::new_err))
+}
+
+// A game, which copy.copy and copy.deepcopy clone
+#[pyclass(name = "Kalaha", module = "kalaha", eq, skip_from_py_object)]
+#[derive(Debug, PartialEq, Clone)]
+pub struct PyKalaha {
+    game: Kalaha,
+}
+
+#[pymethods]
+impl PyKalaha {
+    // From the usual start under rules as in Rules::from_str, or from a Setup
+    #[new]
+    #[pyo3(signature = (rules = None, setup = None))]
+    fn new(rules: Option<&str>, setup: Option<&str>) -> PyResult<PyKalaha> {
+        let rules = parse_rules(rules)?;
+        let game = match setup {
+            Some(setup) => {
+                let setup: Setup = setup.parse().map_err(|error: SetupError| PyValueError::new_err(error.to_string()))?;
+                Kalaha::from_setup_any_total(rules, &setup).map_err(|error| PyValueError::new_err(error.to_string()))?
+            },
+            None => Kalaha::with_rules(rules),
+        };
+        Ok(PyKalaha { game })
+    }
+
+    #[getter]
+    fn rules(&self) -> String {
+        self.game.rules().to_string()
+    }
+
+    // moves as in Move::from_str, e.g. "3", "3cw" or "swap"
+    fn legal_moves(&self) -> Vec<String> {
+        self.game.legal_moves().iter().map(Move::to_string).collect()
+    }
+
+    // raises IllegalMoveError if mv isn't allowed
+    fn valid_move(&self, mv: &str) -> PyResult<()> {
+        self.game.valid_move(parse_move(mv)?).map_err(|error| IllegalMoveError::new_err(error.name()))
+    }
+
+    // Makes the move, returning what it did as a dict
+    fn choose(&mut self, py: Python, mv: &str) -> PyResult<Py<PyAny>> {
+        self.valid_move(mv)?;
+        let outcome = self.game.choose(parse_move(mv)?);
+        to_python(py, &outcome.to_json())
+    }
+
+    fn clone(&self) -> PyKalaha {
+        Clone::clone(self)
+    }
+
+    fn __copy__(&self) -> PyKalaha {
+        Clone::clone(self)
+    }
+
+    fn __deepcopy__(&self, _memo: &Bound<PyAny>) -> PyKalaha {
+        Clone::clone(self)
+    }
+
+    fn ponds(&self, player: &str) -> PyResult<Vec<u32>> {
+        Ok(self.game.ponds(&parse_player(player)?).to_vec())
+    }
+
+    fn bank(&self, player: &str) -> PyResult<u32> {
+        Ok(self.game.bank(&parse_player(player)?))
+    }
+
+    // Every count as a flat list, A's ponds, A's bank, B's ponds then B's bank, which suits
+    // feeding to a model
+    fn pits(&self) -> Vec<u32> {
+        self.game.pits().to_vec()
+    }
+
+    // "A" or "B", or None once the game has finished
+    #[getter]
+    fn current_player(&self) -> Option<&'static str> {
+        match (self.game.is_finished(), self.game.current_player()) {
+            (true, _) => None,
+            (false, &Player::A) => Some("A"),
+            (false, &Player::B) => Some("B"),
+        }
+    }
+
+    #[getter]
+    fn is_finished(&self) -> bool {
+        self.game.is_finished()
+    }
+
+    // the GameResult as a dict, or None while the game is going
+    fn result(&self, py: Python) -> PyResult<Option<Py<PyAny>>> {
+        if !self.game.is_finished() {
+            return Ok(None);
+        }
+        to_python(py, &self.game.game_result().to_json()).map(Some)
+    }
+
+    // the whole game as a dict, as the HTTP API shows it
+    fn state(&self, py: Python) -> PyResult<Py<PyAny>> {
+        to_python(py, &self.game.to_json())
+    }
+
+    fn __repr__(&self) -> String {
+        match self.game.setup() {
+            Some(setup) => format!("Kalaha('{}', '{}')", self.game.rules(), setup),
+            None => format!("<finished Kalaha: {}>", self.game.game_result()),
+        }
+    }
+
+    fn __str__(&self) -> String {
+        self.game.to_string()
+    }
+}
+
+// One of the built in AIs by name: first, last, minmax, alphabeta or caching. The searching
+// ones look depth moves ahead.
+#[pyclass(name = "AI", module = "kalaha", frozen, skip_from_py_object)]
+#[derive(Debug, Clone)]
+pub struct PyAI {
+    name: String,
+    depth: u32,
+}
+
+impl PyAI {
+    fn ai(&self) -> Box<dyn ai::AI> {
+        ai::by_name(&self.name, self.depth).expect("checked when made")
+    }
+}
+
+#[pymethods]
+impl PyAI {
+    #[new]
+    #[pyo3(signature = (name = "alphabeta", depth = 6))]
+    fn new(name: &str, depth: u32) -> PyResult<PyAI> {
+        if depth == 0 || ai::by_name(name, depth).is_none() {
+            return Err(PyValueError::new_err(format!("no AI '{}' with depth {}", name, depth)));
+        }
+        Ok(PyAI { name: name.to_string(), depth })
+    }
+
+    #[getter]
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[getter]
+    fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    fn choose(&self, game: &PyKalaha) -> PyResult<String> {
+        self.ai().choose(&game.game).map(|mv| mv.to_string()).map_err(|error| PyRuntimeError::new_err(error.to_string()))
+    }
+
+    // the move and the score the AI expects for the player to move, None if it doesn't search
+    fn analyse(&self, game: &PyKalaha) -> PyResult<(String, Option<i32>)> {
+        self.ai().analyse(&game.game)
+            .map(|analysis| (analysis.mv.to_string(), analysis.score))
+            .map_err(|error| PyRuntimeError::new_err(error.to_string()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("AI('{}', {})", self.name, self.depth)
+    }
+}
+
+// Plays games between two AIs, or one AI against itself, opening each with random_moves
+// random legal moves so the games differ. Each game comes back as a dict of its "moves",
+// "result" and whether the players "swapped" under the pie rule, when ai_b plays side A.
+// The GIL is released while the games are played.
+#[pyfunction]
+#[pyo3(signature = (ai_a, ai_b = None, games = 1, rules = None, random_moves = 0, seed = None))]
+fn self_play(py: Python, ai_a: &PyAI, ai_b: Option<&PyAI>, games: usize, rules: Option<&str>, random_moves: usize,
+             seed: Option<u64>) -> PyResult<Vec<Py<PyAny>>>
+{
+    let rules = parse_rules(rules)?;
+    let (ai_a, ai_b) = (ai_a.clone(), ai_b.cloned());
+    let records = py.detach(|| -> Result<Vec<Json>, String> {
+        // made here as AIs can't be shared between threads
+        let ai_a = ai_a.ai();
+        let ai_b = ai_b.as_ref().map(PyAI::ai);
+        let ais = [&ai_a, ai_b.as_ref().unwrap_or(&ai_a)];
+        let mut rng = seed.map_or_else(Rng::from_time, Rng::new);
+        let mut records = Vec::with_capacity(games);
+        for _ in 0..games {
+            let mut game = Kalaha::with_rules(rules);
+            let mut moves = Vec::new();
+            while !game.is_finished() {
+                let mv = if moves.len() < random_moves {
+                    let legal = game.legal_moves();
+                    legal[rng.below(legal.len())]
+                } else {
+                    ais[game.seat(game.current_player())].choose(&game).map_err(|error| error.to_string())?
+                };
+                game.choose(mv);
+                moves.push(Json::from(mv.to_string()));
+            }
+            records.push(Json::object(vec![
+                ("moves", Json::Array(moves)),
+                ("result", game.game_result().to_json()),
+                ("swapped", Json::from(game.swapped())),
+            ]));
+        }
+        Ok(records)
+    }).map_err(PyRuntimeError::new_err)?;
+    records.iter().map(|record| to_python(py, record)).collect()
+}
+
+#[pymodule]
+fn kalaha(m: &Bound<PyModule>) -> PyResult<()> {
+    m.add_class::<PyKalaha>()?;
+    m.add_class::<PyAI>()?;
+    m.add_function(wrap_pyfunction!(self::self_play, m)?)?;
+    m.add("IllegalMoveError", m.py().get_type::<IllegalMoveError>())?;
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use std::ffi::CString;
+
+use pyo3::types::PyModule;
+
+use super::*;
+
+// Runs a Python script with the module importable as kalaha, failing on any exception
+fn run(script: &str) {
+    Python::initialize();
+    Python::attach(|py| {
+        let module = PyModule::new(py, "kalaha").unwrap();
+        kalaha(&module).unwrap();
+        py.import("sys").unwrap().getattr("modules").unwrap().set_item("kalaha", module).unwrap();
+        let code = CString::new(script).unwrap();
+        if let Err(error) = py.run(&code, None, None) {
+            panic!("{}", error);
+        }
+    });
+}
+
+#[test]
+fn playing() {
+    run(r#"
+import kalaha
+game = kalaha.Kalaha()
+assert game.rules == "kalah"
+assert game.current_player == "A"
+assert game.legal_moves() == ["0", "1", "2", "3", "4", "5"]
+assert game.pits() == [6, 6, 6, 6, 6, 6, 0, 6, 6, 6, 6, 6, 6, 0]
+outcome = game.choose("0")
+assert outcome["extra_turn"], outcome
+assert game.current_player == "A"
+assert game.ponds("A") == [0, 7, 7, 7, 7, 7]
+assert game.bank("a") == 1
+assert game.result() is None
+assert game.state()["turn"] == {"state": "move", "player": "A"}
+"#);
+}
+
+#[test]
+fn illegal_moves() {
+    run(r#"
+import kalaha
+game = kalaha.Kalaha(setup="0,6,6,6,6,6/0/6,6,6,6,6,6/0/A")
+try:
+    game.choose("0")
+    raise AssertionError("moved from an empty pond")
+except kalaha.IllegalMoveError as error:
+    assert str(error) == "empty_pool"
+assert issubclass(kalaha.IllegalMoveError, ValueError)
+for bad in [lambda: game.choose("pass"), lambda: game.ponds("C"), lambda: kalaha.Kalaha("chess")]:
+    try:
+        bad()
+        raise AssertionError("no error")
+    except ValueError:
+        pass
+"#);
+}
+
+#[test]
+fn cloning() {
+    run(r#"
+import copy, kalaha
+game = kalaha.Kalaha("oware sowing=choice")
+for other in [game.clone(), copy.copy(game), copy.deepcopy(game)]:
+    assert other == game
+    other.choose("1cw")
+    assert other != game
+assert game.pits()[1] == 4
+assert repr(game) == "Kalaha('oware sowing=choice', '4,4,4,4,4,4/0/4,4,4,4,4,4/0/A')"
+"#);
+}
+
+#[test]
+fn ais() {
+    run(r#"
+import kalaha
+game = kalaha.Kalaha()
+assert kalaha.AI("first").choose(game) == "0"
+assert kalaha.AI("last", 1).choose(game) == "5"
+mv, score = kalaha.AI("alphabeta", 4).analyse(game)
+assert mv in game.legal_moves() and isinstance(score, int)
+assert repr(kalaha.AI()) == "AI('alphabeta', 6)"
+try:
+    kalaha.AI("random")
+    raise AssertionError("no error")
+except ValueError:
+    pass
+"#);
+}
+
+#[test]
+fn self_play() {
+    run(r#"
+import kalaha
+games = kalaha.self_play(kalaha.AI("first"), kalaha.AI("last"), games=3, random_moves=2, seed=7)
+assert len(games) == 3
+for record in games:
+    game = kalaha.Kalaha()
+    for mv in record["moves"]:
+        game.choose(mv)
+    assert game.result() == record["result"]
+assert games == kalaha.self_play(kalaha.AI("first"), kalaha.AI("last"), games=3, random_moves=2, seed=7)
+"#);
+}
+
+#[test]
+fn self_play_pie_rule() {
+    run(r#"
+import kalaha
+first, last = kalaha.AI("first"), kalaha.AI("last")
+[record] = kalaha.self_play(first, last, rules="kalah pie")
+# the last legal move is the swap, so B takes it and carries on choosing for side A
+assert record["swapped"]
+game = kalaha.Kalaha("kalah pie")
+for mv in record["moves"]:
+    swapped = game.state()["swapped"]
+    ai = first if (game.current_player == "A") != swapped else last
+    assert mv == ai.choose(game), (mv, record)
+    game.choose(mv)
+assert game.result() == record["result"]
+"#);
+}
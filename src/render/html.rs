@@ -0,0 +1,265 @@
+// A game as a single HTML page to step through: the diagram of every position with what each
+// move did, an evaluation bar from an AI and the result at the end. The page needs nothing but
+// a browser, the diagrams are inline SVG and a few lines of script page through them.
+//
+//   let report = game.clone().play(&ai_one, &ai_two, IllegalMovePolicy::Forfeit, false);
+//   let mut replay = Replay::new(&game, &report.moves)?.with_result(report.game_result);
+//   replay.evaluate(&AlphaBeta::new(6));
+//   fs::write("replay.html", replay.to_html())
+use std::fmt::Write;
+
+use ai::{AI, Analysis};
+use kalaha::Kalaha;
+use moves::Move;
+use player::Player;
+use turn::GameResult;
+
+use super::{escape, Diagram};
+
+const STYLE: &str = "
+    body { font-family: sans-serif; margin: 20px auto; max-width: 1000px; color: #222222; }
+    h1 { font-size: 24px; margin-bottom: 4px; }
+    .rules, .result { margin: 4px 0; }
+    .result { font-weight: bold; }
+    main { display: flex; gap: 20px; }
+    .frame { display: none; }
+    .frame.current { display: block; }
+    .annotation { font-size: 18px; margin: 8px 0; }
+    .bar { position: relative; width: 720px; height: 24px; background: #333333; border: 1px solid #333333; }
+    .bar .a { height: 100%; background: #f4e6c8; }
+    .bar .middle { position: absolute; left: 50%; top: 0; bottom: 0; border-left: 1px dashed #d03030; }
+    .evaluation { margin: 4px 0; color: #555555; }
+    .controls { margin: 12px 0; display: flex; gap: 8px; align-items: center; }
+    .controls input { flex: 1; }
+    #moves { list-style: none; padding: 0; margin: 0; max-height: 420px; overflow-y: auto; min-width: 200px; }
+    #moves button { width: 100%; text-align: left; border: none; background: none; padding: 3px 6px; cursor: pointer; }
+    #moves button.current { background: #f0a020; }
+";
+
+const SCRIPT: &str = "
+    const frames = document.querySelectorAll('.frame');
+    const moves = document.querySelectorAll('#moves button');
+    const slider = document.getElementById('slider');
+    const play = document.getElementById('play');
+    let current = 0;
+    let timer = null;
+    function show(i) {
+        i = Math.max(0, Math.min(frames.length - 1, i));
+        frames[current].classList.remove('current');
+        moves[current].classList.remove('current');
+        current = i;
+        frames[current].classList.add('current');
+        moves[current].classList.add('current');
+        moves[current].scrollIntoView({ block: 'nearest' });
+        slider.value = current;
+        if (current == frames.length - 1) stop();
+    }
+    function stop() {
+        clearInterval(timer);
+        timer = null;
+        play.textContent = 'Play';
+    }
+    play.onclick = () => {
+        if (timer) return stop();
+        if (current == frames.length - 1) show(0);
+        timer = setInterval(() => show(current + 1), 1000);
+        play.textContent = 'Pause';
+    };
+    document.getElementById('first').onclick = () => show(0);
+    document.getElementById('previous').onclick = () => show(current - 1);
+    document.getElementById('next').onclick = () => show(current + 1);
+    document.getElementById('last').onclick = () => show(frames.length - 1);
+    slider.oninput = () => show(Number(slider.value));
+    moves.forEach((button, i) => button.onclick = () => show(i));
+    document.onkeydown = (event) => {
+        if (event.key == 'ArrowLeft') show(current - 1);
+        else if (event.key == 'ArrowRight') show(current + 1);
+        else if (event.key == 'Home') show(0);
+        else if (event.key == 'End') show(frames.length - 1);
+        else return;
+        event.preventDefault();
+    };
+";
+
+// One position of the replay and the move that led to it
+#[derive(Debug, PartialEq, Clone)]
+struct Frame {
+    diagram: Diagram,
+    // who moved and what, None for the start
+    played: Option<(Player, Move)>,
+    // what the move did, such as going again or capturing
+    notes: Vec<String>,
+    // what the AI makes of the position, from player A's side
+    evaluation: Option<Evaluation>,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Evaluation {
+    // bank difference A expects, None if the AI doesn't score its moves
+    score: Option<i32>,
+    // the AI's move, None once the game has finished
+    best: Option<Move>,
+}
+
+// A game to write out as a page, see the top of the file
+#[derive(Debug, PartialEq, Clone)]
+pub struct Replay {
+    frames: Vec<Frame>,
+    result: Option<GameResult>,
+    title: String,
+    evaluator: Option<String>,
+}
+
+impl Replay {
+    // The game made by playing moves from start, which can be part way through
+    pub fn new(start: &Kalaha, moves: &[Move]) -> Result<Replay, String> {
+        let mut frames = vec![Frame { diagram: Diagram::new(start), played: None, notes: Vec::new(), evaluation: None }];
+        let mut game = start.clone();
+        for (i, &mv) in moves.iter().enumerate() {
+            game.valid_move(mv).map_err(|error| format!("move {}, {}, is illegal: {}", i + 1, mv, error.name()))?;
+            let player = game.current_player().clone();
+            let diagram = Diagram::after_move(&game, mv);
+            let outcome = game.choose(mv);
+            let mut notes = Vec::new();
+            if mv == Move::Swap {
+                notes.push("swaps sides".to_string());
+            }
+            if outcome.captured > 0 {
+                notes.push(format!("captures {}", outcome.captured));
+            }
+            if outcome.extra_turn && !game.is_finished() {
+                notes.push("goes again".to_string());
+            }
+            frames.push(Frame { diagram, played: Some((player, mv)), notes, evaluation: None });
+        }
+        let result = if game.is_finished() { Some(game.game_result().clone()) } else { None };
+        Ok(Replay { frames, result, title: "Kalaha".to_string(), evaluator: None })
+    }
+
+    // How the game ended when the moves don't say, such as a forfeit from Kalaha::play
+    pub fn with_result(self, result: GameResult) -> Replay {
+        Replay { result: Some(result), ..self }
+    }
+
+    pub fn with_title(self, title: &str) -> Replay {
+        Replay { title: title.to_string(), ..self }
+    }
+
+    pub fn result(&self) -> Option<&GameResult> {
+        self.result.as_ref()
+    }
+
+    // The position after each move, starting with the one moves were played from
+    pub fn positions(&self) -> impl Iterator<Item = &Kalaha> + '_ {
+        self.frames.iter().map(|frame| frame.diagram.game())
+    }
+
+    // Asks ai for its move and score in every position, for the evaluation bar. Finished
+    // positions score the banks as they are.
+    pub fn evaluate(&mut self, ai: &dyn AI) {
+        for frame in &mut self.frames {
+            let game = frame.diagram.game();
+            frame.evaluation = Some(if game.is_finished() {
+                let score = game.bank(&Player::A) as i32 - game.bank(&Player::B) as i32;
+                Evaluation { score: Some(score), best: None }
+            } else {
+                match ai.analyse(game) {
+                    Ok(Analysis { mv, score }) => {
+                        // analyse scores for the player to move
+                        let score = if *game.current_player() == Player::A { score } else { score.map(|score| -score) };
+                        Evaluation { score, best: Some(mv) }
+                    },
+                    Err(_) => Evaluation { score: None, best: None },
+                }
+            });
+        }
+        self.evaluator = Some(format!("{:?}", ai));
+    }
+
+    pub fn to_html(&self) -> String {
+        let last = self.frames.len() - 1;
+        let rules = self.frames[0].diagram.game().rules().to_string();
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        let _ = writeln!(html, "<title>{}</title>", escape(&self.title));
+        let _ = writeln!(html, "<style>{}</style>", STYLE);
+        html.push_str("</head>\n<body>\n");
+        let _ = writeln!(html, "<h1>{}</h1>", escape(&self.title));
+        let mut details = format!("Rules: {}, {} moves", rules, last);
+        if let Some(ref evaluator) = self.evaluator {
+            details.push_str(&format!(", evaluated by {}", evaluator));
+        }
+        let _ = writeln!(html, "<p class=\"rules\">{}</p>", escape(&details));
+        if let Some(ref result) = self.result {
+            let _ = writeln!(html, "<p class=\"result\">{}</p>", escape(&result.to_string()));
+        }
+        html.push_str("<main>\n<div>\n");
+        for (i, frame) in self.frames.iter().enumerate() {
+            let class = if i == 0 { "frame current" } else { "frame" };
+            let _ = writeln!(html, "<section class=\"{}\" id=\"frame-{}\">", class, i);
+            let _ = writeln!(html, "<p class=\"annotation\">{}</p>", escape(&self.annotation(i)));
+            html.push_str(&frame.diagram.to_svg());
+            if let Some(evaluation) = frame.evaluation {
+                Replay::write_evaluation(&mut html, frame.diagram.game(), evaluation);
+            }
+            html.push_str("</section>\n");
+        }
+        html.push_str("<div class=\"controls\">\n\
+                       <button id=\"first\">&#x23EE;</button>\n\
+                       <button id=\"previous\">&#x25C0;</button>\n\
+                       <button id=\"play\">Play</button>\n\
+                       <button id=\"next\">&#x25B6;</button>\n\
+                       <button id=\"last\">&#x23ED;</button>\n");
+        let _ = writeln!(html, "<input id=\"slider\" type=\"range\" min=\"0\" max=\"{}\" value=\"0\">", last);
+        html.push_str("</div>\n</div>\n<ol id=\"moves\">\n");
+        for (i, frame) in self.frames.iter().enumerate() {
+            let text = match frame.played {
+                Some((ref player, mv)) => format!("{}. {:?} {}", i, player, mv),
+                None => "Start".to_string(),
+            };
+            let class = if i == 0 { " class=\"current\"" } else { "" };
+            let _ = writeln!(html, "<li><button{}>{}</button></li>", class, escape(&text));
+        }
+        html.push_str("</ol>\n</main>\n");
+        let _ = writeln!(html, "<script>{}</script>", SCRIPT);
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+
+    // What the move leading to frame i did, and the result on the last frame
+    fn annotation(&self, i: usize) -> String {
+        let frame = &self.frames[i];
+        let mut text = match frame.played {
+            Some((ref player, mv)) => format!("Move {}: {:?} plays {}", i, player, mv),
+            None => "Start".to_string(),
+        };
+        for note in &frame.notes {
+            text.push_str(", ");
+            text.push_str(note);
+        }
+        if i == self.frames.len() - 1 {
+            if let Some(ref result) = self.result {
+                text.push_str(&format!(". {}", result));
+            }
+        }
+        text
+    }
+
+    // A bar filled with A's share of the evaluation, even at the dashed line in the middle
+    fn write_evaluation(html: &mut String, game: &Kalaha, evaluation: Evaluation) {
+        let total: u32 = game.pits().iter().sum();
+        let (share, score) = match evaluation.score {
+            // ahead by half the seeds is as good as won
+            Some(score) => {
+                let half = (total as f64 / 2.0).max(1.0);
+                let share = 50.0 + 50.0 * (f64::from(score) / half).clamp(-1.0, 1.0);
+                (share, format!("{:+} for A", score))
+            },
+            None => (50.0, "no score".to_string()),
+        };
+        let _ = writeln!(html, "<div class=\"bar\" title=\"{}\"><div class=\"a\" style=\"width: {:.1}%\"></div>\
+                                <div class=\"middle\"></div></div>", escape(&score), share);
+        let best = evaluation.best.map_or(String::new(), |best| format!(", best move {}", best));
+        let _ = writeln!(html, "<p class=\"evaluation\">{}{}</p>", escape(&score), best);
+    }
+}
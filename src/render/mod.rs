@@ -0,0 +1,234 @@
+// Draws positions as SVG, for diagrams in write-ups. A diagram shows every pond and bank with
+// its seed count, the players' sides and whose move it is, and how the game ended once it has.
+// A diagram made after a move also marks the pond it was sown from, an arrow along the seeds'
+// path and the pits a capture (or the end of the game) took seeds from and put them in. A
+// Replay puts the diagrams of a whole game on one HTML page, see html.rs.
+//
+//   fs::write("board.svg", Diagram::after_move(&game, Move::Pond(2)).with_title("A sows 2").to_svg())
+use std::fmt::Write;
+
+use board::Sow;
+use kalaha::Kalaha;
+use moves::Move;
+use player::Player;
+
+mod html;
+#[cfg(test)]
+mod tests;
+
+pub use self::html::Replay;
+
+const WIDTH: i32 = 720;
+const HEIGHT: i32 = 300;
+// room for the title above everything else
+const TITLE_HEIGHT: i32 = 40;
+const POND_RADIUS: i32 = 34;
+
+const STYLE: &str = "
+    text { font-family: sans-serif; text-anchor: middle; dominant-baseline: central; }
+    .board { fill: #c8a165; stroke: #8b5a2b; stroke-width: 3; }
+    .pit { fill: #8b5a2b; }
+    .sown { fill: #a8743f; }
+    .origin { stroke: #f0a020; stroke-width: 5; }
+    .captured { stroke: #d03030; stroke-width: 5; stroke-dasharray: 8 4; }
+    .count { fill: #ffffff; font-size: 22px; font-weight: bold; }
+    .label { fill: #5a3a1a; font-size: 12px; }
+    .player { fill: #333333; font-size: 16px; }
+    .to-move { font-weight: bold; }
+    .title { fill: #000000; font-size: 20px; font-weight: bold; }
+    .sowing { fill: none; stroke: #f0a020; stroke-width: 3; stroke-opacity: 0.8; stroke-linejoin: round; }
+";
+
+// Where a pit is drawn, by its index in Kalaha::pits: A's ponds along the bottom from left to
+// right, B's along the top from right to left, with B's bank on the left and A's on the right
+fn centre(idx: usize) -> (i32, i32) {
+    let column = |column: usize| 135 + 90 * column as i32;
+    match idx {
+        0..=5 => (column(idx), 195),
+        6 => (670, 150),
+        7..=12 => (column(12 - idx), 105),
+        _ => (50, 150),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// What the last move did, for drawing it
+#[derive(Debug, PartialEq, Clone)]
+struct LastMove {
+    steps: Vec<Sow>,
+    // pits whose count a capture or the end of the game changed after sowing
+    captured: Vec<usize>,
+}
+
+// A position to draw, see the top of the file
+#[derive(Debug, PartialEq, Clone)]
+pub struct Diagram {
+    game: Kalaha,
+    last_move: Option<LastMove>,
+    title: Option<String>,
+}
+
+impl Diagram {
+    pub fn new(game: &Kalaha) -> Diagram {
+        Diagram { game: game.clone(), last_move: None, title: None }
+    }
+
+    // The position after mv is made in before, showing the move
+    // panics if mv is not a valid_move
+    pub fn after_move(before: &Kalaha, mv: Move) -> Diagram {
+        let steps = before.sowing(mv);
+        let mut sown = before.pits();
+        for step in &steps {
+            match *step {
+                Sow::PickUp(idx) => sown[idx] = 0,
+                Sow::Drop(idx) => sown[idx] += 1,
+            }
+        }
+        let mut game = before.clone();
+        game.choose(mv);
+        let after = game.pits();
+        let captured = (0..::TOTAL_POOLS).filter(|&idx| after[idx] != sown[idx]).collect();
+        Diagram { game, last_move: Some(LastMove { steps, captured }), title: None }
+    }
+
+    pub fn with_title(self, title: &str) -> Diagram {
+        Diagram { title: Some(title.to_string()), ..self }
+    }
+
+    pub fn game(&self) -> &Kalaha {
+        &self.game
+    }
+
+    pub fn to_svg(&self) -> String {
+        let top = if self.title.is_some() { TITLE_HEIGHT } else { 0 };
+        let mut svg = String::new();
+        svg.push_str(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+            WIDTH, HEIGHT + top,
+        ));
+        svg.push_str(&format!("<style>{}</style>\n", STYLE));
+        svg.push_str("<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"8\" refY=\"5\" markerWidth=\"5\" \
+                      markerHeight=\"5\" orient=\"auto-start-reverse\"><path d=\"M 0 0 L 10 5 L 0 10 z\" \
+                      fill=\"#f0a020\"/></marker></defs>\n");
+        svg.push_str(&format!("<rect width=\"{}\" height=\"{}\" fill=\"#ffffff\"/>\n", WIDTH, HEIGHT + top));
+        if let Some(ref title) = self.title {
+            svg.push_str(&format!("<text class=\"title\" x=\"{}\" y=\"22\">{}</text>\n", WIDTH / 2, escape(title)));
+        }
+        svg.push_str(&format!("<g transform=\"translate(0 {})\">\n", top));
+        svg.push_str("<rect class=\"board\" x=\"10\" y=\"45\" width=\"700\" height=\"210\" rx=\"40\"/>\n");
+        self.write_pits(&mut svg);
+        self.write_sowing(&mut svg);
+        self.write_counts(&mut svg);
+        self.write_players(&mut svg);
+        svg.push_str("</g>\n</svg>\n");
+        svg
+    }
+
+    fn write_pits(&self, svg: &mut String) {
+        let (origin, sown, captured) = match self.last_move {
+            Some(ref last_move) => {
+                let origin = match last_move.steps.first() {
+                    Some(&Sow::PickUp(idx)) => Some(idx),
+                    _ => None,
+                };
+                let sown: Vec<usize> = last_move.steps.iter()
+                    .filter_map(|step| match *step {
+                        Sow::Drop(idx) => Some(idx),
+                        Sow::PickUp(_) => None,
+                    })
+                    .collect();
+                (origin, sown, last_move.captured.clone())
+            },
+            None => (None, Vec::new(), Vec::new()),
+        };
+        for idx in 0..::TOTAL_POOLS {
+            let (x, y) = centre(idx);
+            let mut class = String::from("pit");
+            if sown.contains(&idx) {
+                class.push_str(" sown");
+            }
+            if captured.contains(&idx) {
+                class.push_str(" captured");
+            } else if origin == Some(idx) {
+                class.push_str(" origin");
+            }
+            if idx == 6 || idx == 13 {
+                let _ = writeln!(svg, "<rect class=\"{}\" x=\"{}\" y=\"{}\" width=\"64\" height=\"180\" rx=\"32\"/>",
+                                 class, x - 32, y - 90);
+            } else {
+                let _ = writeln!(svg, "<circle class=\"{}\" cx=\"{}\" cy=\"{}\" r=\"{}\"/>", class, x, y, POND_RADIUS);
+                // pond numbers as moves name them, outside the row
+                let label_y = if idx < 6 { y + POND_RADIUS + 10 } else { y - POND_RADIUS - 10 };
+                let _ = writeln!(svg, "<text class=\"label\" x=\"{}\" y=\"{}\">{}</text>", x, label_y, idx % 7);
+            }
+        }
+    }
+
+    // on top of the sowing arrows, so they stay readable
+    fn write_counts(&self, svg: &mut String) {
+        for (idx, count) in self.game.pits().iter().enumerate() {
+            let (x, y) = centre(idx);
+            let _ = writeln!(svg, "<text class=\"count\" x=\"{}\" y=\"{}\">{}</text>", x, y, count);
+        }
+    }
+
+    // A line through the pits each lap of the last move sowed, ending in an arrow
+    fn write_sowing(&self, svg: &mut String) {
+        let steps = match self.last_move {
+            Some(ref last_move) => &last_move.steps,
+            None => return,
+        };
+        let mut laps: Vec<Vec<usize>> = Vec::new();
+        for step in steps {
+            match *step {
+                Sow::PickUp(idx) => laps.push(vec![idx]),
+                Sow::Drop(idx) => laps.last_mut().expect("sowing starts with a pick up").push(idx),
+            }
+        }
+        for lap in laps {
+            let points: Vec<String> = lap.iter()
+                .map(|&idx| {
+                    let (x, y) = centre(idx);
+                    format!("{},{}", x, y)
+                })
+                .collect();
+            let _ = writeln!(svg, "<polyline class=\"sowing\" points=\"{}\" marker-end=\"url(#arrow)\"/>", points.join(" "));
+        }
+    }
+
+    fn write_players(&self, svg: &mut String) {
+        for &(ref player, y) in &[(Player::B, 25), (Player::A, 280)] {
+            let to_move = !self.game.is_finished() && self.game.current_player() == player;
+            let mut text = format!("Player {:?}", player);
+            if self.game.swapped() {
+                text.push_str(" (swapped)");
+            }
+            if to_move {
+                text.push_str(if self.game.swap_offered() { " to move or swap" } else { " to move" });
+            }
+            let class = if to_move { "player to-move" } else { "player" };
+            let _ = writeln!(svg, "<text class=\"{}\" x=\"{}\" y=\"{}\">{}</text>", class, WIDTH / 2, y, text);
+        }
+        if self.game.is_finished() {
+            let _ = writeln!(svg, "<text class=\"player to-move\" x=\"{}\" y=\"150\">{}</text>",
+                             WIDTH / 2, escape(&self.game.game_result().to_string()));
+        }
+    }
+}
+
+// A diagram of start and of the position after each of moves, titled with the move made
+pub fn replay(start: &Kalaha, moves: &[Move]) -> Result<Vec<Diagram>, String> {
+    let mut diagrams = vec![Diagram::new(start).with_title("Start")];
+    let mut game = start.clone();
+    for (i, &mv) in moves.iter().enumerate() {
+        game.valid_move(mv).map_err(|error| format!("move {}, {}, is illegal: {}", i + 1, mv, error.name()))?;
+        let player = game.current_player().clone();
+        let diagram = Diagram::after_move(&game, mv).with_title(&format!("Move {}: {:?} plays {}", i + 1, player, mv));
+        game = diagram.game().clone();
+        diagrams.push(diagram);
+    }
+    Ok(diagrams)
+}
@@ -0,0 +1,152 @@
+use super::*;
+use ai::AlphaBeta;
+use turn::GameResult;
+use rules::Rules;
+use setup::Setup;
+
+fn from_setup(setup: &str) -> Kalaha {
+    Kalaha::from_setup_any_total(Rules::kalah(), &setup.parse::<Setup>().unwrap()).unwrap()
+}
+
+fn count(svg: &str, fragment: &str) -> usize {
+    svg.matches(fragment).count()
+}
+
+#[test]
+fn start() {
+    let svg = Diagram::new(&Kalaha::new()).to_svg();
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"720\" height=\"300\""), "{}", svg);
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(count(&svg, "<circle class=\"pit\""), 12);
+    assert_eq!(count(&svg, "<rect class=\"pit\""), 2);
+    assert_eq!(count(&svg, "<text class=\"count\""), 14);
+    assert_eq!(count(&svg, ">6</text>"), 12);
+    assert!(svg.contains(">Player A to move</text>"));
+    assert!(svg.contains(">Player B</text>"));
+    assert!(!svg.contains("<polyline"));
+}
+
+#[test]
+fn pits_are_laid_out_around_the_board() {
+    let svg = Diagram::new(&from_setup("1,2,3,4,5,6/7/8,9,10,11,12,13/14/B")).to_svg();
+    // A's first pond bottom left, B's first pond top right, B's bank on the left
+    assert!(svg.contains("<text class=\"count\" x=\"135\" y=\"195\">1</text>"), "{}", svg);
+    assert!(svg.contains("<text class=\"count\" x=\"585\" y=\"105\">8</text>"), "{}", svg);
+    assert!(svg.contains("<text class=\"count\" x=\"670\" y=\"150\">7</text>"), "{}", svg);
+    assert!(svg.contains("<text class=\"count\" x=\"50\" y=\"150\">14</text>"), "{}", svg);
+    assert!(svg.contains(">Player B to move</text>"));
+}
+
+#[test]
+fn last_move() {
+    let svg = Diagram::after_move(&Kalaha::new(), Move::Pond(2)).to_svg();
+    assert!(svg.contains("<circle class=\"pit origin\" cx=\"315\" cy=\"195\""), "{}", svg);
+    assert_eq!(count(&svg, "class=\"pit sown\""), 6);
+    // from pond 2 along A's side, into the bank and on to B's first two ponds
+    assert!(svg.contains("<polyline class=\"sowing\" points=\"315,195 405,195 495,195 585,195 670,150 585,105 495,105\""), "{}", svg);
+    assert!(svg.contains(">Player B to move</text>"));
+}
+
+#[test]
+fn captures() {
+    let diagram = Diagram::after_move(&from_setup("1,0,0,0,0,0/0/0,0,0,0,5,0/0/A"), Move::Pond(0));
+    let svg = diagram.to_svg();
+    // the seed and the 5 opposite went to A's bank, which ended the game
+    assert!(svg.contains("<circle class=\"pit sown captured\" cx=\"225\""), "{}", svg);
+    assert!(svg.contains("<circle class=\"pit captured\" cx=\"225\" cy=\"105\""), "{}", svg);
+    assert!(svg.contains("<rect class=\"pit captured\" x=\"638\""), "{}", svg);
+    assert!(svg.contains(&format!(">{}</text>", diagram.game().game_result())), "{}", svg);
+    assert!(!svg.contains("to move"));
+}
+
+#[test]
+fn relay_laps() {
+    let svg = Diagram::after_move(&from_setup("0,0,0,1,1,0/0/1,0,0,0,0,0/0/A"), Move::Pond(3)).to_svg();
+    assert_eq!(count(&svg, "<polyline"), 1);
+    // under relay sowing the seed landing in pond 4 picks its seeds up and carries on
+    let rules = "kalah relay".parse().unwrap();
+    let relay = Kalaha::from_setup_any_total(rules, &"0,0,0,1,1,0/0/1,0,0,0,0,0/0/A".parse().unwrap()).unwrap();
+    let svg = Diagram::after_move(&relay, Move::Pond(3)).to_svg();
+    assert_eq!(count(&svg, "<polyline"), 2, "{}", svg);
+    assert!(svg.contains("points=\"495,195 585,195 670,150\""), "{}", svg);
+}
+
+#[test]
+fn titles() {
+    let svg = Diagram::new(&Kalaha::new()).with_title("Kalah <opening>").to_svg();
+    assert!(svg.contains("height=\"340\""));
+    assert!(svg.contains(">Kalah &lt;opening&gt;</text>"));
+    assert!(svg.contains("<g transform=\"translate(0 40)\">"));
+}
+
+#[test]
+fn replays() {
+    let moves = [Move::Pond(0), Move::Pond(3), Move::Swap];
+    let game = Kalaha::with_rules(Rules::kalah().with_pie_rule(true));
+    let diagrams = replay(&game, &moves[..2]).unwrap();
+    assert_eq!(diagrams.len(), 3);
+    assert_eq!(diagrams[0].title, Some("Start".to_string()));
+    assert_eq!(diagrams[2].title, Some("Move 2: A plays 3".to_string()));
+    let mut expected = game.clone();
+    expected.choose(0);
+    expected.choose(3);
+    assert_eq!(diagrams[2].game(), &expected);
+    assert!(diagrams[2].to_svg().contains("Player B to move or swap"));
+    let swapped = replay(&game, &moves).unwrap();
+    assert!(swapped[3].to_svg().contains("Player B (swapped) to move"));
+    assert!(!swapped[3].to_svg().contains("<polyline"));
+    assert_eq!(replay(&game, &[Move::Pond(0), Move::Pond(0)]), Err("move 2, 0, is illegal: empty_pool".to_string()));
+}
+
+#[test]
+fn replay_pages() {
+    let game = Kalaha::with_rules(Rules::kalah().with_pie_rule(true));
+    let replay = Replay::new(&game, &[Move::Pond(2), Move::Swap, Move::Pond(1)]).unwrap().with_title("A & B");
+    assert_eq!(replay.positions().count(), 4);
+    assert_eq!(replay.result(), None);
+    let html = replay.to_html();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<h1>A &amp; B</h1>"), "{}", html);
+    assert!(html.contains("<p class=\"rules\">Rules: kalah pie, 3 moves</p>"), "{}", html);
+    assert_eq!(count(&html, "<svg"), 4);
+    assert_eq!(count(&html, "<section class=\"frame"), 4);
+    assert_eq!(count(&html, "class=\"frame current\""), 1);
+    assert!(html.contains(">Move 1: A plays 2</p>"), "{}", html);
+    assert!(html.contains(">Move 2: B plays swap, swaps sides</p>"), "{}", html);
+    assert!(html.contains("<li><button>3. B 1</button></li>"), "{}", html);
+    assert!(html.contains("max=\"3\""));
+    assert!(!html.contains("class=\"bar\""));
+    let html = Replay::new(&game, &[Move::Pond(0)]).unwrap().to_html();
+    assert!(html.contains(">Move 1: A plays 0, goes again</p>"), "{}", html);
+    assert_eq!(Replay::new(&game, &[Move::Pond(6)]), Err("move 1, 6, is illegal: invalid_index".to_string()));
+}
+
+#[test]
+fn replay_results() {
+    let start = from_setup("1,0,0,0,0,0/0/0,0,0,0,5,0/0/A");
+    let replay = Replay::new(&start, &[Move::Pond(0)]).unwrap();
+    let result = replay.positions().last().unwrap().game_result().clone();
+    assert_eq!(replay.result(), Some(&result));
+    let html = replay.to_html();
+    assert!(html.contains(&format!(">Move 1: A plays 0, captures 6. {}</p>", result)), "{}", html);
+    assert!(html.contains(&format!("<p class=\"result\">{}</p>", result)));
+    let forfeit = GameResult::Forfeit { player: Player::A, score_a: 0, score_b: 0 };
+    let replay = Replay::new(&Kalaha::new(), &[]).unwrap().with_result(forfeit.clone());
+    assert!(replay.to_html().contains(&format!(">Start. {}</p>", forfeit)));
+}
+
+#[test]
+fn replay_evaluation() {
+    let start = from_setup("1,0,0,0,0,0/0/0,0,0,0,5,0/0/A");
+    let mut replay = Replay::new(&start, &[Move::Pond(0)]).unwrap();
+    replay.evaluate(&AlphaBeta::new(4));
+    let html = replay.to_html();
+    // A is about to win all 6 seeds, which fills the bar before and after the move
+    assert_eq!(count(&html, "<div class=\"a\" style=\"width: 100.0%\">"), 2, "{}", html);
+    assert!(html.contains("+6 for A, best move 0</p>"), "{}", html);
+    assert!(html.contains(", evaluated by AlphaBeta { depth: 4 }</p>"), "{}", html);
+    // B to move scores the other way round
+    let mut replay = Replay::new(&from_setup("0,0,0,0,5,0/0/1,0,0,0,0,0/0/B"), &[]).unwrap();
+    replay.evaluate(&AlphaBeta::new(4));
+    assert!(replay.to_html().contains("<div class=\"a\" style=\"width: 0.0%\">"));
+}
@@ -0,0 +1,30 @@
+use super::*;
+
+#[test]
+fn same_seed_same_sequence() {
+    let mut one = Rng::new(42);
+    let mut two = Rng::new(42);
+    for _ in 0..10 {
+        assert_eq!(one.next_u64(), two.next_u64());
+    }
+}
+
+#[test]
+fn zero_seed_not_stuck() {
+    let mut rng = Rng::new(0);
+    assert_ne!(rng.next_u64(), rng.next_u64());
+}
+
+#[test]
+fn below_in_range() {
+    let mut rng = Rng::new(7);
+    for _ in 0..100 {
+        assert!(rng.below(6) < 6);
+    }
+}
+
+#[test]
+#[should_panic(expected = "Empty range")]
+fn below_zero() {
+    Rng::new(7).below(0);
+}
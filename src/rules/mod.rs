@@ -0,0 +1,257 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(test)]
+mod tests;
+
+// The family of rules the game is played by. Both use the same board of six ponds
+// a side plus a bank each, but sow and capture differently.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Variant {
+    // Kalah: sow through your own bank, capture by landing in an empty pond of your own
+    Kalah,
+    // Oware (Abapa): banks only hold captures, capture 2s and 3s on the opponent's side
+    Oware,
+}
+
+// When landing the last seed in an empty pond of your own captures it (Kalah only)
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum CapturePolicy {
+    // capture even if the opposite pond is empty, moving just the landed seed to the bank
+    Always,
+    // only capture if there is something in the opposite pond
+    OppositeNotEmpty,
+    // never capture
+    Never,
+}
+
+impl CapturePolicy {
+    // whether landing in an empty pond of your own captures, given the seeds facing it
+    pub fn captures(&self, opposite: u32) -> bool {
+        match *self {
+            CapturePolicy::Always => true,
+            CapturePolicy::OppositeNotEmpty => opposite > 0,
+            CapturePolicy::Never => false,
+        }
+    }
+}
+
+// Whose bank the seeds left on the board go to once a player runs out (Kalah only)
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum SweepPolicy {
+    // the player who still has seeds keeps them
+    OwnSide,
+    // the player who ran out gets them
+    PlayerWhoRanOut,
+}
+
+// Which way seeds go round the board, looking down on it with player A at the bottom.
+// Either way a player sows into their own bank but not their opponent's.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum Direction {
+    // along player A's ponds towards A's bank, the usual way
+    CounterClockwise,
+    // along player A's ponds away from A's bank
+    Clockwise,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum SowingPolicy {
+    // every move sows this way
+    Fixed(Direction),
+    // each move picks a direction, see Move::Directed
+    PlayerChoice,
+}
+
+// Enough for any relay that ends, stopping the ones that would go round forever
+const RELAY_LAP_LIMIT: u32 = 1000;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct Rules {
+    pub variant: Variant,
+    pub capture: CapturePolicy,
+    pub sweep: SweepPolicy,
+    // end the game as soon as a player has more than half the seeds in their bank.
+    // Oware always does this.
+    pub end_on_majority: bool,
+    // once player A's first move is done, player B may take over A's side instead of
+    // moving, so A has a reason not to open with the strongest move
+    pub pie_rule: bool,
+    pub sowing: SowingPolicy,
+    // Relay sowing: if the last seed lands in a pond that wasn't empty, pick that pond up
+    // and keep sowing, for at most this many laps. 1 is ordinary sowing. (Kalah only)
+    pub max_laps: u32,
+}
+
+impl Rules {
+    pub fn kalah() -> Rules {
+        Rules {
+            variant: Variant::Kalah,
+            capture: CapturePolicy::Always,
+            sweep: SweepPolicy::OwnSide,
+            end_on_majority: false,
+            pie_rule: false,
+            sowing: SowingPolicy::Fixed(Direction::CounterClockwise),
+            max_laps: 1,
+        }
+    }
+
+    pub fn oware() -> Rules {
+        Rules { variant: Variant::Oware, end_on_majority: true, ..Rules::kalah() }
+    }
+
+    pub fn with_capture(self, capture: CapturePolicy) -> Rules {
+        Rules { capture, ..self }
+    }
+
+    pub fn with_sweep(self, sweep: SweepPolicy) -> Rules {
+        Rules { sweep, ..self }
+    }
+
+    pub fn with_end_on_majority(self, end_on_majority: bool) -> Rules {
+        Rules { end_on_majority, ..self }
+    }
+
+    pub fn with_pie_rule(self, pie_rule: bool) -> Rules {
+        Rules { pie_rule, ..self }
+    }
+
+    pub fn with_sowing(self, sowing: SowingPolicy) -> Rules {
+        Rules { sowing, ..self }
+    }
+
+    // at most RELAY_LAP_LIMIT, which already lets any relay that ends run its course
+    pub fn with_max_laps(self, max_laps: u32) -> Rules {
+        Rules { max_laps: max_laps.min(RELAY_LAP_LIMIT), ..self }
+    }
+
+    // relay sowing with a limit generous enough not to change any game that ends
+    pub fn with_relay(self) -> Rules {
+        self.with_max_laps(RELAY_LAP_LIMIT)
+    }
+
+    // The directions a move may sow in
+    pub fn directions(&self) -> &'static [Direction] {
+        match self.sowing {
+            SowingPolicy::Fixed(Direction::CounterClockwise) => &[Direction::CounterClockwise],
+            SowingPolicy::Fixed(Direction::Clockwise) => &[Direction::Clockwise],
+            SowingPolicy::PlayerChoice => &[Direction::CounterClockwise, Direction::Clockwise],
+        }
+    }
+
+    pub fn seeds_per_pond(&self) -> u32 {
+        match self.variant {
+            Variant::Kalah => super::INIT_COUNT,
+            Variant::Oware => super::OWARE_INIT_COUNT,
+        }
+    }
+
+    pub fn total_seeds(&self) -> u32 {
+        self.seeds_per_pond() * 2 * super::PONDS_PER_PLAYER as u32
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Direction::CounterClockwise => f.write_str("counter-clockwise"),
+            Direction::Clockwise => f.write_str("clockwise"),
+        }
+    }
+}
+
+// Written as the variant followed by whatever differs from its defaults, e.g.
+// `kalah capture=opposite sowing=choice relay pie`. The options are capture=always|opposite|never,
+// sweep=own|ran_out, sowing=ccw|cw|choice, relay or laps=N, majority or no_majority, and pie.
+// Oware only takes sowing, majority and pie, since it always ends on a majority and has its own
+// captures, sweep and sowing.
+impl FromStr for Rules {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Rules, String> {
+        let mut words = s.split_whitespace();
+        let mut rules = match words.next() {
+            Some("kalah") => Rules::kalah(),
+            Some("oware") => Rules::oware(),
+            _ => return Err(format!("unknown rules '{}'", s.trim())),
+        };
+        for word in words {
+            let kalah_only = word.starts_with("capture=") || word.starts_with("sweep=") || word.starts_with("laps=")
+                || word == "relay" || word == "no_majority";
+            if kalah_only && rules.variant == Variant::Oware {
+                return Err(format!("'{}' only applies to kalah", word));
+            }
+            rules = match word {
+                "capture=always" => rules.with_capture(CapturePolicy::Always),
+                "capture=opposite" => rules.with_capture(CapturePolicy::OppositeNotEmpty),
+                "capture=never" => rules.with_capture(CapturePolicy::Never),
+                "sweep=own" => rules.with_sweep(SweepPolicy::OwnSide),
+                "sweep=ran_out" => rules.with_sweep(SweepPolicy::PlayerWhoRanOut),
+                "sowing=ccw" => rules.with_sowing(SowingPolicy::Fixed(Direction::CounterClockwise)),
+                "sowing=cw" => rules.with_sowing(SowingPolicy::Fixed(Direction::Clockwise)),
+                "sowing=choice" => rules.with_sowing(SowingPolicy::PlayerChoice),
+                "relay" => rules.with_relay(),
+                "majority" => rules.with_end_on_majority(true),
+                "no_majority" => rules.with_end_on_majority(false),
+                "pie" => rules.with_pie_rule(true),
+                _ if word.starts_with("laps=") => match word[5..].parse() {
+                    Ok(laps) if laps > 0 && laps <= RELAY_LAP_LIMIT => rules.with_max_laps(laps),
+                    _ => return Err(format!("'{}' is not a number of laps from 1 to {}", &word[5..], RELAY_LAP_LIMIT)),
+                },
+                _ => return Err(format!("unknown rule '{}'", word)),
+            };
+        }
+        Ok(rules)
+    }
+}
+
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let base = match self.variant {
+            Variant::Kalah => Rules::kalah(),
+            Variant::Oware => Rules::oware(),
+        };
+        let mut words = vec![match self.variant {
+            Variant::Kalah => "kalah".to_string(),
+            Variant::Oware => "oware".to_string(),
+        }];
+        if self.capture != base.capture {
+            words.push(match self.capture {
+                CapturePolicy::Always => "capture=always",
+                CapturePolicy::OppositeNotEmpty => "capture=opposite",
+                CapturePolicy::Never => "capture=never",
+            }.to_string());
+        }
+        if self.sweep != base.sweep {
+            words.push(match self.sweep {
+                SweepPolicy::OwnSide => "sweep=own",
+                SweepPolicy::PlayerWhoRanOut => "sweep=ran_out",
+            }.to_string());
+        }
+        if self.sowing != base.sowing {
+            words.push(match self.sowing {
+                SowingPolicy::Fixed(Direction::CounterClockwise) => "sowing=ccw",
+                SowingPolicy::Fixed(Direction::Clockwise) => "sowing=cw",
+                SowingPolicy::PlayerChoice => "sowing=choice",
+            }.to_string());
+        }
+        if self.max_laps == RELAY_LAP_LIMIT {
+            words.push("relay".to_string());
+        } else if self.max_laps != base.max_laps {
+            words.push(format!("laps={}", self.max_laps));
+        }
+        if self.end_on_majority != base.end_on_majority {
+            words.push(if self.end_on_majority { "majority" } else { "no_majority" }.to_string());
+        }
+        if self.pie_rule {
+            words.push("pie".to_string());
+        }
+        f.write_str(&words.join(" "))
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Rules {
+        Rules::kalah()
+    }
+}
@@ -0,0 +1,69 @@
+use super::*;
+
+#[test]
+fn default_is_kalah() {
+    assert_eq!(Rules::default(), Rules::kalah());
+}
+
+#[test]
+fn total_seeds() {
+    assert_eq!(Rules::kalah().total_seeds(), 72);
+    assert_eq!(Rules::oware().total_seeds(), 48);
+}
+
+#[test]
+fn directions() {
+    assert_eq!(Rules::kalah().directions(), &[Direction::CounterClockwise]);
+    assert_eq!(
+        Rules::kalah().with_sowing(SowingPolicy::Fixed(Direction::Clockwise)).directions(),
+        &[Direction::Clockwise]
+    );
+    assert_eq!(Rules::oware().with_sowing(SowingPolicy::PlayerChoice).directions().len(), 2);
+}
+
+#[test]
+fn from_str() {
+    assert_eq!("kalah".parse(), Ok(Rules::kalah()));
+    assert_eq!(
+        "kalah sowing=choice laps=3 pie".parse(),
+        Ok(Rules::kalah().with_sowing(SowingPolicy::PlayerChoice).with_max_laps(3).with_pie_rule(true))
+    );
+    assert_eq!(
+        "oware sowing=choice majority pie".parse(),
+        Ok(Rules::oware().with_sowing(SowingPolicy::PlayerChoice).with_pie_rule(true))
+    );
+    assert_eq!("kalah capture=sometimes".parse::<Rules>(), Err("unknown rule 'capture=sometimes'".to_string()));
+    assert_eq!("kalah laps=0".parse::<Rules>(), Err("'0' is not a number of laps from 1 to 1000".to_string()));
+    assert_eq!("kalah laps=1001".parse::<Rules>(), Err("'1001' is not a number of laps from 1 to 1000".to_string()));
+    assert_eq!(Rules::kalah().with_max_laps(u32::MAX), Rules::kalah().with_relay());
+    assert_eq!("chess".parse::<Rules>(), Err("unknown rules 'chess'".to_string()));
+}
+
+#[test]
+fn from_str_kalah_only() {
+    for word in &["capture=never", "sweep=ran_out", "relay", "laps=3", "no_majority"] {
+        assert_eq!(format!("kalah {}", word).parse::<Rules>().map(|_| ()), Ok(()));
+        assert_eq!(format!("oware {}", word).parse::<Rules>(), Err(format!("'{}' only applies to kalah", word)));
+    }
+}
+
+#[test]
+fn display() {
+    assert_eq!(Rules::kalah().to_string(), "kalah");
+    assert_eq!(Rules::oware().to_string(), "oware");
+    assert_eq!(Rules::kalah().with_relay().with_capture(CapturePolicy::Never).to_string(), "kalah capture=never relay");
+    assert_eq!(Rules::kalah().with_end_on_majority(true).to_string(), "kalah majority");
+}
+
+#[test]
+fn display_round_trip() {
+    let rules = [
+        Rules::kalah().with_sweep(SweepPolicy::PlayerWhoRanOut).with_end_on_majority(true).with_pie_rule(true),
+        Rules::kalah().with_sowing(SowingPolicy::Fixed(Direction::Clockwise)).with_max_laps(5),
+        Rules::kalah().with_sowing(SowingPolicy::PlayerChoice).with_capture(CapturePolicy::OppositeNotEmpty),
+        Rules::oware().with_sowing(SowingPolicy::Fixed(Direction::Clockwise)).with_pie_rule(true),
+    ];
+    for rules in &rules {
+        assert_eq!(rules.to_string().parse(), Ok(*rules));
+    }
+}
@@ -0,0 +1,223 @@
+// Serde support behind the `serde` feature. Players, turns, results and games have the
+// same shapes as in the HTTP API (see json::ToJson): rules as a Rules string, pits as
+// arrays, turns tagged with their state and results with who won. A game has the API's
+// rules, board, turn and swapped, leaves out the legal moves and setup the API works out,
+// and adds whether player A is still making the opening move and the moves since the last
+// Oware capture, which the API doesn't show, e.g.
+//
+//   {"rules": "kalah pie", "board": {"ponds_a": [6, 6, 6, 6, 6, 6], "bank_a": 0,
+//                                    "ponds_b": [6, 6, 6, 6, 6, 6], "bank_b": 0},
+//    "turn": {"state": "move", "player": "A"}, "swapped": false, "opening": true, "quiet_moves": 0}
+//
+// Reading a game checks it could have come from play, see Kalaha::from_parts. A Board on its
+// own has no game to take its rules and quiet moves from, so it carries them itself.
+use std::convert::TryFrom;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use board::Board;
+use kalaha::Kalaha;
+use player::Player;
+use rules::Rules;
+use turn::{Turn, GameResult, Ending};
+
+#[cfg(test)]
+mod tests;
+
+const ENDINGS: [Ending; 4] = [Ending::SideEmpty, Ending::Majority, Ending::NoMoves, Ending::NoCaptures];
+
+impl Serialize for Rules {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rules {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Rules, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(::serde::de::Error::custom)
+    }
+}
+
+// The seeds on a board, as the HTTP API shows a board
+#[derive(Serialize, Deserialize)]
+pub struct PitsRepr {
+    ponds_a: [u32; 6],
+    bank_a: u32,
+    ponds_b: [u32; 6],
+    bank_b: u32,
+}
+
+impl From<&Board> for PitsRepr {
+    fn from(board: &Board) -> PitsRepr {
+        PitsRepr {
+            ponds_a: board.pond_counts(&Player::A),
+            bank_a: board.bank(&Player::A),
+            ponds_b: board.pond_counts(&Player::B),
+            bank_b: board.bank(&Player::B),
+        }
+    }
+}
+
+impl PitsRepr {
+    // the board these pits make under rules, which packs fewer than 256 seeds
+    fn board(&self, rules: Rules, quiet_moves: u32) -> Result<Board, String> {
+        let mut counts = Vec::with_capacity(::TOTAL_POOLS);
+        counts.extend_from_slice(&self.ponds_a);
+        counts.push(self.bank_a);
+        counts.extend_from_slice(&self.ponds_b);
+        counts.push(self.bank_b);
+        // every count and the total have to fit in a u8, checked as they're added so no count
+        // from the input can overflow the total
+        let too_many = || format!("a board holds at most {} seeds", u8::MAX);
+        let mut pits = [0; ::TOTAL_POOLS];
+        let mut total: u8 = 0;
+        for (pit, &count) in pits.iter_mut().zip(counts.iter()) {
+            *pit = u8::try_from(count).map_err(|_| too_many())?;
+            total = total.checked_add(*pit).ok_or_else(too_many)?;
+        }
+        let quiet_moves = u8::try_from(quiet_moves).map_err(|_| "too many quiet moves".to_string())?;
+        let mut board = Board::from_pits(rules, pits);
+        board.set_quiet_moves(quiet_moves);
+        Ok(board)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BoardRepr {
+    rules: Rules,
+    #[serde(flatten)]
+    pits: PitsRepr,
+    #[serde(default)]
+    quiet_moves: u32,
+}
+
+impl From<Board> for BoardRepr {
+    fn from(board: Board) -> BoardRepr {
+        BoardRepr { rules: *board.rules(), pits: PitsRepr::from(&board), quiet_moves: u32::from(board.quiet_moves()) }
+    }
+}
+
+impl TryFrom<BoardRepr> for Board {
+    type Error = String;
+
+    fn try_from(repr: BoardRepr) -> Result<Board, String> {
+        repr.pits.board(repr.rules, repr.quiet_moves)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GameRepr {
+    rules: Rules,
+    board: PitsRepr,
+    turn: Turn,
+    swapped: bool,
+    opening: bool,
+    #[serde(default)]
+    quiet_moves: u32,
+}
+
+impl From<Kalaha> for GameRepr {
+    fn from(game: Kalaha) -> GameRepr {
+        let (board, turn, opening, swapped) = game.parts();
+        GameRepr {
+            rules: *board.rules(),
+            board: PitsRepr::from(board),
+            turn: turn.clone(),
+            swapped,
+            opening,
+            quiet_moves: u32::from(board.quiet_moves()),
+        }
+    }
+}
+
+impl TryFrom<GameRepr> for Kalaha {
+    type Error = String;
+
+    fn try_from(repr: GameRepr) -> Result<Kalaha, String> {
+        let board = repr.board.board(repr.rules, repr.quiet_moves)?;
+        Kalaha::from_parts(board, repr.turn, repr.opening, repr.swapped)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum TurnRepr {
+    Move { player: Player },
+    SwapOffered { player: Player },
+    Finished { result: GameResult },
+}
+
+impl From<Turn> for TurnRepr {
+    fn from(turn: Turn) -> TurnRepr {
+        match turn {
+            Turn::Player(player) => TurnRepr::Move { player },
+            Turn::SwapOffered(player) => TurnRepr::SwapOffered { player },
+            Turn::Finished(result) => TurnRepr::Finished { result },
+        }
+    }
+}
+
+impl From<TurnRepr> for Turn {
+    fn from(repr: TurnRepr) -> Turn {
+        match repr {
+            TurnRepr::Move { player } => Turn::Player(player),
+            TurnRepr::SwapOffered { player } => Turn::SwapOffered(player),
+            TurnRepr::Finished { result } => Turn::Finished(result),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GameResultRepr {
+    // null for a draw
+    winner: Option<Player>,
+    score_a: u32,
+    score_b: u32,
+    // an Ending's name, or forfeit
+    ending: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    forfeited: Option<Player>,
+}
+
+impl From<GameResult> for GameResultRepr {
+    fn from(result: GameResult) -> GameResultRepr {
+        match result {
+            GameResult::Winner { player, score_a, score_b, ending } =>
+                GameResultRepr { winner: Some(player), score_a, score_b, ending: ending.name().to_string(), forfeited: None },
+            GameResult::Draw { score, ending } =>
+                GameResultRepr { winner: None, score_a: score, score_b: score, ending: ending.name().to_string(), forfeited: None },
+            GameResult::Forfeit { player, score_a, score_b } => GameResultRepr {
+                winner: Some(player.next()),
+                score_a,
+                score_b,
+                ending: "forfeit".to_string(),
+                forfeited: Some(player),
+            },
+        }
+    }
+}
+
+impl TryFrom<GameResultRepr> for GameResult {
+    type Error = String;
+
+    // the winner has to be the one the scores or forfeit say
+    fn try_from(repr: GameResultRepr) -> Result<GameResult, String> {
+        let result = if repr.ending == "forfeit" {
+            let player = repr.forfeited.ok_or("a forfeit needs the player who forfeited")?;
+            GameResult::Forfeit { player, score_a: repr.score_a, score_b: repr.score_b }
+        } else {
+            let ending = ENDINGS.iter().find(|ending| ending.name() == repr.ending)
+                .ok_or_else(|| format!("unknown ending '{}'", repr.ending))?;
+            Turn::new_finished(repr.score_a, repr.score_b, *ending).game_result().clone()
+        };
+        let winner = match result {
+            GameResult::Winner { ref player, .. } => Some(player.clone()),
+            GameResult::Draw { .. } => None,
+            GameResult::Forfeit { ref player, .. } => Some(player.next()),
+        };
+        if winner != repr.winner {
+            return Err(format!("the winner doesn't match the result {}", result));
+        }
+        Ok(result)
+    }
+}
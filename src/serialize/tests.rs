@@ -0,0 +1,148 @@
+use super::*;
+use serde_json;
+use json::ToJson;
+use kalaha::Kalaha;
+use moves::Move;
+
+fn round_trip<T: Serialize + for<'de> Deserialize<'de> + PartialEq + ::std::fmt::Debug>(value: &T) -> String {
+    let json = serde_json::to_string(value).unwrap();
+    assert_eq!(&serde_json::from_str::<T>(&json).unwrap(), value, "{}", json);
+    json
+}
+
+#[test]
+fn player() {
+    assert_eq!(round_trip(&Player::A), "\"A\"");
+    assert_eq!(round_trip(&Player::B), "\"B\"");
+}
+
+#[test]
+fn board() {
+    let mut board = Board::with_rules(Rules::oware().with_pie_rule(true));
+    board.choose(&Player::A, 2, ::Direction::CounterClockwise);
+    board.set_quiet_moves(7);
+    assert_eq!(
+        round_trip(&board),
+        concat!(
+            r#"{"rules":"oware pie","ponds_a":[4,4,0,5,5,5],"bank_a":0,"ponds_b":[5,4,4,4,4,4],"bank_b":0,"#,
+            r#""quiet_moves":7}"#
+        )
+    );
+}
+
+#[test]
+fn board_limits() {
+    let too_many = r#"{"rules":"kalah","ponds_a":[200,6,6,6,6,6],"bank_a":0,"ponds_b":[6,6,6,6,6,6],"bank_b":0}"#;
+    assert!(serde_json::from_str::<Board>(too_many).unwrap_err().to_string().contains("at most 255 seeds"));
+    // counts that would overflow a u32 total, or wrap round to a small one
+    for ponds in &["[4294967295,1,0,0,0,0]", "[256,0,0,0,0,0]"] {
+        let board = format!(r#"{{"rules":"kalah","ponds_a":{},"bank_a":0,"ponds_b":[0,0,0,0,0,0],"bank_b":0}}"#, ponds);
+        assert!(serde_json::from_str::<Board>(&board).unwrap_err().to_string().contains("at most 255 seeds"));
+    }
+    let bad_rules = r#"{"rules":"chess","ponds_a":[6,6,6,6,6,6],"bank_a":0,"ponds_b":[6,6,6,6,6,6],"bank_b":0}"#;
+    assert!(serde_json::from_str::<Board>(bad_rules).unwrap_err().to_string().contains("unknown rules 'chess'"));
+    // quiet_moves can be left out
+    let board = r#"{"rules":"kalah","ponds_a":[6,6,6,6,6,6],"bank_a":0,"ponds_b":[6,6,6,6,6,6],"bank_b":0}"#;
+    assert_eq!(serde_json::from_str::<Board>(board).unwrap(), Board::with_rules(Rules::kalah()));
+}
+
+#[test]
+fn turns_match_the_http_api() {
+    let turns = [
+        Turn::Player(Player::B),
+        Turn::SwapOffered(Player::B),
+        Turn::new_finished(40, 32, Ending::SideEmpty),
+        Turn::new_finished(24, 24, Ending::NoCaptures),
+        Turn::Finished(GameResult::Forfeit { player: Player::A, score_a: 10, score_b: 3 }),
+    ];
+    for turn in &turns {
+        assert_eq!(round_trip(turn), turn.to_json().to_string());
+    }
+}
+
+#[test]
+fn inconsistent_results() {
+    let results = [
+        (r#"{"winner":"B","score_a":40,"score_b":32,"ending":"side_empty"}"#, "the winner doesn't match"),
+        (r#"{"winner":null,"score_a":20,"score_b":28,"ending":"majority"}"#, "the winner doesn't match"),
+        (r#"{"winner":"A","score_a":1,"score_b":0,"ending":"forfeit"}"#, "needs the player who forfeited"),
+        (r#"{"winner":"A","score_a":1,"score_b":0,"ending":"forfeit","forfeited":"A"}"#, "the winner doesn't match"),
+        (r#"{"winner":"A","score_a":1,"score_b":0,"ending":"resigned"}"#, "unknown ending 'resigned'"),
+    ];
+    for &(json, reason) in &results {
+        let error = serde_json::from_str::<GameResult>(json).unwrap_err().to_string();
+        assert!(error.contains(reason), "{}: {}", json, error);
+    }
+}
+
+#[test]
+fn games() {
+    let mut game = Kalaha::with_rules(Rules::kalah().with_pie_rule(true));
+    round_trip(&game);
+    game.choose(Move::Pond(1));
+    game.choose(Move::Swap);
+    assert_eq!(
+        round_trip(&game),
+        concat!(
+            r#"{"rules":"kalah pie","board":{"ponds_a":[6,0,7,7,7,7],"bank_a":1,"ponds_b":[7,6,6,6,6,6],"bank_b":0},"#,
+            r#""turn":{"state":"move","player":"B"},"swapped":true,"opening":false,"quiet_moves":0}"#
+        )
+    );
+    while !game.is_finished() {
+        let mv = game.legal_moves()[0];
+        game.choose(mv);
+    }
+    round_trip(&game);
+}
+
+// Every position of some games reads back, and the keys the HTTP API shows look the same
+#[test]
+fn games_match_the_http_api() {
+    let rules = [Rules::kalah().with_pie_rule(true), Rules::oware(), "kalah sowing=choice relay".parse().unwrap()];
+    for (seed, rules) in (0..30).zip(rules.iter().cycle()) {
+        let mut rng = ::rng::Rng::new(seed);
+        let mut game = Kalaha::with_rules(*rules);
+        loop {
+            let json = serde_json::from_str::<serde_json::Value>(&round_trip(&game)).unwrap();
+            let api = serde_json::from_str::<serde_json::Value>(&game.to_json().to_string()).unwrap();
+            for key in &["rules", "board", "turn", "swapped"] {
+                assert_eq!(json[key], api[key], "{}", key);
+            }
+            if game.is_finished() {
+                break;
+            } else if rng.below(40) == 0 {
+                game.forfeit(game.current_player().clone());
+            } else {
+                let moves = game.legal_moves();
+                game.choose(moves[rng.below(moves.len())]);
+            }
+        }
+    }
+}
+
+#[test]
+fn impossible_games() {
+    let game = |rules: &str, board: &str, turn: &str, swapped: bool, opening: bool| format!(
+        r#"{{"rules":"{}","board":{},"turn":{},"swapped":{},"opening":{}}}"#, rules, board, turn, swapped, opening
+    );
+    let start = r#"{"ponds_a":[6,6,6,6,6,6],"bank_a":0,"ponds_b":[6,6,6,6,6,6],"bank_b":0}"#;
+    let stuck = r#"{"ponds_a":[1,0,0,0,0,0],"bank_a":30,"ponds_b":[0,0,0,0,0,0],"bank_b":41}"#;
+    let over = r#"{"ponds_a":[6,6,6,6,6,6],"bank_a":36,"ponds_b":[0,0,0,0,0,0],"bank_b":0}"#;
+    let (a_to_move, b_to_move) = (r#"{"state":"move","player":"A"}"#, r#"{"state":"move","player":"B"}"#);
+    let finished = r#"{"state":"finished","result":{"winner":"A","score_a":40,"score_b":32,"ending":"side_empty"}}"#;
+    let games = [
+        // Oware's A can't feed B, so has no move
+        (game("oware", stuck, a_to_move, false, false), "A is to move but has no legal moves"),
+        (game("kalah", stuck, b_to_move, false, false), "B is to move but has no legal moves"),
+        (game("kalah", start, r#"{"state":"swap_offered","player":"B"}"#, false, false),
+         "a swap is only offered to player B after the opening"),
+        (game("kalah pie", start, b_to_move, false, true), "player B can't move during player A's opening"),
+        (game("kalah", start, a_to_move, true, false), "only the pie rule swaps sides"),
+        (game("kalah", start, finished, false, false), "doesn't match the banks"),
+        (game("kalah", over, a_to_move, false, false), "the game should have ended"),
+    ];
+    for &(ref json, reason) in &games {
+        let error = serde_json::from_str::<Kalaha>(json).unwrap_err().to_string();
+        assert!(error.contains(reason), "{}: {}", json, error);
+    }
+}
@@ -0,0 +1,320 @@
+// Hosting games over TCP, so bots on different machines can play each other. The protocol
+// is text, one message per line. A client connects and sends
+//
+//   join <name>
+//
+// and once a second client has joined the server starts the game, sending each of them
+//
+//   player A|B           the side they play. Sent again when the pie rule swaps sides.
+//   rules <rules>        as in Rules::from_str
+//   position <...>       the board before every move, as in the engine protocol
+//   turn                 their move, to be answered with `move <move>`
+//   illegal <reason>     that move isn't allowed, so send another. The reason is the rule it
+//                        broke as in Error::name, e.g. empty_pool, or not_a_move for a line
+//                        that isn't `move <move>`
+//   moved <move>         the move just made, to both players
+//   result <score A> <score B> <how>
+//                        the game is over, where how is side_empty, majority, no_moves,
+//                        no_captures, or `forfeit A` or `forfeit B` for a player who made
+//                        too many illegal moves, took too long or went away
+//
+// and then closes the connection.
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::Duration;
+
+use super::ai::AI;
+use super::engine::{parse_position, position_command};
+use super::kalaha::{Kalaha, seat_for};
+use super::moves::Move;
+use super::player::Player;
+use super::rules::Rules;
+use super::turn::{Turn, GameResult, Ending};
+
+#[cfg(test)]
+mod tests;
+
+// illegal moves a player can send in a row before they forfeit
+const MAX_ILLEGAL_MOVES: u32 = 3;
+
+// The result line for a finished game
+pub fn result_line(result: &GameResult) -> String {
+    let (score_a, score_b, how) = match *result {
+        GameResult::Winner { score_a, score_b, ending, .. } => (score_a, score_b, ending.name().to_string()),
+        GameResult::Draw { score, ending } => (score, score, ending.name().to_string()),
+        GameResult::Forfeit { ref player, score_a, score_b } =>
+            (score_a, score_b, format!("forfeit {}", player_word(player))),
+    };
+    format!("result {} {} {}", score_a, score_b, how)
+}
+
+// Reads the arguments of a result line back into the GameResult
+pub fn parse_result(args: &str) -> Result<GameResult, String> {
+    let words: Vec<&str> = args.split_whitespace().collect();
+    if words.len() < 3 {
+        return Err(format!("'{}' is not a result", args));
+    }
+    let score = |word: &str| word.parse::<u32>().map_err(|_| format!("'{}' is not a score", word));
+    let (score_a, score_b) = (score(words[0])?, score(words[1])?);
+    let ending = match words[2..] {
+        ["side_empty"] => Ending::SideEmpty,
+        ["majority"] => Ending::Majority,
+        ["no_moves"] => Ending::NoMoves,
+        ["no_captures"] => Ending::NoCaptures,
+        ["forfeit", player] => {
+            let player = parse_player(player)?;
+            return Ok(GameResult::Forfeit { player, score_a, score_b });
+        },
+        _ => return Err(format!("'{}' is not how a game ends", words[2..].join(" "))),
+    };
+    Ok(Turn::new_finished(score_a, score_b, ending).game_result().clone())
+}
+
+fn parse_player(word: &str) -> Result<Player, String> {
+    match word {
+        "A" => Ok(Player::A),
+        "B" => Ok(Player::B),
+        _ => Err(format!("'{}' is not a player", word)),
+    }
+}
+
+fn player_word(player: &Player) -> &'static str {
+    match *player {
+        Player::A => "A",
+        Player::B => "B",
+    }
+}
+
+// One connected client, the one that joined first starting as player A
+struct Seat {
+    name: String,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Seat {
+    fn new(stream: TcpStream, timeout: Option<Duration>) -> io::Result<Seat> {
+        stream.set_read_timeout(timeout)?;
+        stream.set_nodelay(true)?;
+        Ok(Seat { name: String::new(), reader: BufReader::new(stream.try_clone()?), writer: stream })
+    }
+
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        writeln!(self.writer, "{}", line)
+    }
+
+    // the next line, or an error if the client has gone
+    fn receive(&mut self) -> io::Result<String> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "client left"));
+        }
+        Ok(line.trim().to_string())
+    }
+}
+
+// A game the server has played, with the names the players joined with
+#[derive(Debug, PartialEq, Clone)]
+pub struct HostedGame {
+    // whoever finished on side A, after any swap
+    pub player_a: String,
+    pub player_b: String,
+    pub game_result: GameResult,
+}
+
+impl fmt::Display for HostedGame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.write_str(&format!("{} (A) vs {} (B): {}", self.player_a, self.player_b, self.game_result))
+    }
+}
+
+// Plays games between pairs of clients in the order they connect
+pub struct Server {
+    listener: TcpListener,
+    game: Kalaha,
+    timeout: Option<Duration>,
+}
+
+impl Server {
+    // Listens on addr for games starting from game
+    pub fn bind<A: ToSocketAddrs>(addr: A, game: Kalaha) -> io::Result<Server> {
+        Ok(Server { listener: TcpListener::bind(addr)?, game, timeout: None })
+    }
+
+    // how long to wait for a client before they forfeit, forever by default
+    pub fn with_timeout(self, timeout: Option<Duration>) -> Server {
+        Server { timeout, ..self }
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // Hosts each pair of clients that connects in its own thread, stopping once games have
+    // been played if given, and returns them in the order they started. Prints each result
+    // as it comes in if verbose.
+    pub fn run(&self, games: Option<usize>, verbose: bool) -> io::Result<Vec<HostedGame>> {
+        let mut threads = Vec::new();
+        let mut waiting = None;
+        for stream in self.listener.incoming() {
+            // one client failing to connect is no reason to stop hosting everyone else
+            let seat = match stream.and_then(|stream| Seat::new(stream, self.timeout)) {
+                Ok(seat) => seat,
+                Err(error) => {
+                    eprintln!("Couldn't accept a client: {}", error);
+                    continue;
+                },
+            };
+            let first = match waiting.take() {
+                Some(first) => first,
+                None => {
+                    waiting = Some(seat);
+                    continue;
+                },
+            };
+            let game = self.game.clone();
+            let number = threads.len() + 1;
+            threads.push(thread::spawn(move || host(number, game, [first, seat], verbose)));
+            if games == Some(threads.len()) {
+                break;
+            }
+        }
+        Ok(threads.into_iter().map(|thread| thread.join().expect("Game thread panicked")).collect())
+    }
+}
+
+fn host(number: usize, mut game: Kalaha, mut seats: [Seat; 2], verbose: bool) -> HostedGame {
+    if let Err(seat) = play_out(&mut game, &mut seats) {
+        // going away after the last move doesn't change the result
+        if !game.is_finished() {
+            let side = if game.seat(&Player::A) == seat { Player::A } else { Player::B };
+            game.forfeit(side);
+        }
+    }
+    let result = game.game_result().clone();
+    for seat in seats.iter_mut() {
+        // a client that has gone doesn't need telling
+        let _ = seat.send(&result_line(&result));
+    }
+    let hosted = HostedGame {
+        player_a: seats[game.seat(&Player::A)].name.clone(),
+        player_b: seats[game.seat(&Player::B)].name.clone(),
+        game_result: result,
+    };
+    if verbose {
+        println!("Game {}: {}", number, hosted);
+    }
+    hosted
+}
+
+// Plays the game until it finishes, or until one of the seats does something wrong, which
+// is returned
+fn play_out(game: &mut Kalaha, seats: &mut [Seat; 2]) -> Result<(), usize> {
+    for (idx, seat) in seats.iter_mut().enumerate() {
+        let line = seat.receive().map_err(|_| idx)?;
+        seat.name = match line.strip_prefix("join ") {
+            Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+            _ => return Err(idx),
+        };
+    }
+    for side in &[Player::A, Player::B] {
+        let idx = seat_for(side, false);
+        seats[idx].send(&format!("player {}", player_word(side))).map_err(|_| idx)?;
+        seats[idx].send(&format!("rules {}", game.rules())).map_err(|_| idx)?;
+    }
+    while let Some(position) = position_command(game) {
+        for (idx, seat) in seats.iter_mut().enumerate() {
+            seat.send(&position).map_err(|_| idx)?;
+        }
+        let idx = game.seat(game.current_player());
+        let mv = ask_for_move(game, &mut seats[idx]).map_err(|_| idx)?;
+        let swapped = game.swapped();
+        game.choose(mv);
+        for (idx, seat) in seats.iter_mut().enumerate() {
+            seat.send(&format!("moved {}", mv)).map_err(|_| idx)?;
+        }
+        if game.swapped() != swapped {
+            for side in &[Player::A, Player::B] {
+                let idx = seat_for(side, true);
+                seats[idx].send(&format!("player {}", player_word(side))).map_err(|_| idx)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// a legal move from the seat, or an error once they've had too many goes
+fn ask_for_move(game: &Kalaha, seat: &mut Seat) -> io::Result<Move> {
+    seat.send("turn")?;
+    for _ in 0..MAX_ILLEGAL_MOVES {
+        let line = seat.receive()?;
+        let reason = match line.strip_prefix("move ").map(str::parse::<Move>) {
+            Some(Ok(mv)) => match game.valid_move(mv) {
+                Ok(()) => return Ok(mv),
+                Err(error) => error.name(),
+            },
+            Some(Err(_)) | None => "not_a_move",
+        };
+        seat.send(&format!("illegal {}", reason))?;
+    }
+    Err(io::Error::new(io::ErrorKind::InvalidData, "too many illegal moves"))
+}
+
+// How a game played through connect went for the client
+#[derive(Debug, PartialEq, Clone)]
+pub struct RemoteResult {
+    // the side the client finished on
+    pub side: Player,
+    pub game_result: GameResult,
+}
+
+fn invalid_data(reason: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+// Joins a game on the server at addr and plays it with ai
+pub fn connect<A: ToSocketAddrs>(addr: A, name: &str, ai: &dyn AI, verbose: bool) -> io::Result<RemoteResult> {
+    let stream = TcpStream::connect(addr)?;
+    stream.set_nodelay(true)?;
+    let mut writer = stream.try_clone()?;
+    writeln!(writer, "join {}", name)?;
+    let mut rules = Rules::default();
+    let mut side = None;
+    let mut game = None;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        let (command, args) = match line.find(' ') {
+            Some(space) => (&line[..space], line[space + 1..].trim()),
+            None => (&line[..], ""),
+        };
+        match command {
+            "player" => side = Some(parse_player(args).map_err(invalid_data)?),
+            "rules" => rules = args.parse().map_err(invalid_data)?,
+            "position" => {
+                let position = parse_position(rules, args).map_err(invalid_data)?;
+                if verbose {
+                    println!("{}", position);
+                }
+                game = Some(position);
+            },
+            "turn" => {
+                let game = game.as_ref().ok_or_else(|| invalid_data("asked to move before a position".to_string()))?;
+                let mv = ai.choose(game).map_err(|error| invalid_data(error.to_string()))?;
+                writeln!(writer, "move {}", mv)?;
+            },
+            "moved" => if verbose {
+                println!("Chose {}\n", args);
+            },
+            "illegal" => return Err(invalid_data(format!("the server didn't accept the move: {}", args))),
+            "result" => {
+                let game_result = parse_result(args).map_err(invalid_data)?;
+                let side = side.ok_or_else(|| invalid_data("never told which side to play".to_string()))?;
+                return Ok(RemoteResult { side, game_result });
+            },
+            _ => return Err(invalid_data(format!("unexpected message '{}'", line))),
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::UnexpectedEof, "server closed the connection"))
+}
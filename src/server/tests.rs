@@ -0,0 +1,43 @@
+use super::*;
+
+#[test]
+fn result_round_trip() {
+    let results = [
+        Turn::new_finished(40, 32, Ending::SideEmpty).game_result().clone(),
+        Turn::new_finished(20, 28, Ending::Majority).game_result().clone(),
+        Turn::new_finished(24, 24, Ending::NoCaptures).game_result().clone(),
+        GameResult::Forfeit { player: Player::B, score_a: 3, score_b: 10 },
+    ];
+    for result in &results {
+        let line = result_line(result);
+        assert_eq!(parse_result(line.strip_prefix("result ").unwrap()), Ok(result.clone()), "{}", line);
+    }
+}
+
+#[test]
+fn result_lines() {
+    assert_eq!(
+        result_line(&Turn::new_finished(24, 24, Ending::NoMoves).game_result().clone()),
+        "result 24 24 no_moves"
+    );
+    assert_eq!(
+        result_line(&GameResult::Forfeit { player: Player::A, score_a: 0, score_b: 1 }),
+        "result 0 1 forfeit A"
+    );
+}
+
+#[test]
+fn bad_results() {
+    assert_eq!(parse_result("1 2"), Err("'1 2' is not a result".to_string()));
+    assert_eq!(parse_result("1 x majority"), Err("'x' is not a score".to_string()));
+    assert_eq!(parse_result("1 2 forfeit C"), Err("'C' is not a player".to_string()));
+    assert_eq!(parse_result("1 2 resigned"), Err("'resigned' is not how a game ends".to_string()));
+}
+
+#[test]
+fn seats_follow_swaps() {
+    assert_eq!(seat_for(&Player::A, false), 0);
+    assert_eq!(seat_for(&Player::B, false), 1);
+    assert_eq!(seat_for(&Player::A, true), 1);
+    assert_eq!(seat_for(&Player::B, true), 0);
+}
@@ -0,0 +1,156 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::player::Player;
+use super::rules::Rules;
+use super::turn::Ending;
+
+#[cfg(test)]
+mod tests;
+
+// Most seeds the board can hold, since it stores each count in a byte
+const MAX_SEEDS: u32 = 255;
+
+// A position to start a game from, for handicaps or studying a particular spot.
+// Written as player A's ponds, A's bank, B's ponds, B's bank and the player to move,
+// separated by slashes, e.g. the Kalah start is `6,6,6,6,6,6/0/6,6,6,6,6,6/0/A`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Setup {
+    pub ponds_a: [u32; 6],
+    pub bank_a: u32,
+    pub ponds_b: [u32; 6],
+    pub bank_b: u32,
+    pub to_move: Player,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SetupError {
+    // couldn't read the setup string, with what was wrong
+    Parse(String),
+    // the seeds don't add up to what the rules start with
+    WrongTotal { expected: u32, found: u32 },
+    TooManySeeds(u32),
+    // the player to move would have no legal move
+    NoMoves,
+    // the game would already have ended, and how
+    Over(Ending),
+}
+
+impl Setup {
+    // The usual starting position for the rules
+    pub fn start(rules: &Rules) -> Setup {
+        let seeds = rules.seeds_per_pond();
+        Setup {
+            ponds_a: [seeds; 6],
+            bank_a: 0,
+            ponds_b: [seeds; 6],
+            bank_b: 0,
+            to_move: Player::A,
+        }
+    }
+
+    // saturates rather than overflowing, since the fields can hold counts no board could
+    pub fn total(&self) -> u32 {
+        self.ponds_a.iter().chain(self.ponds_b.iter()).chain([self.bank_a, self.bank_b].iter())
+            .fold(0u32, |total, &count| total.saturating_add(count))
+    }
+
+    // Checks the seeds add up to the total for the rules, unless any_total is set, and
+    // that they fit on the board. Whether there is a move and the game isn't already over is
+    // left to Kalaha::from_setup.
+    pub fn check_total(&self, rules: &Rules, any_total: bool) -> Result<(), SetupError> {
+        let found = self.total();
+        if found > MAX_SEEDS {
+            Err(SetupError::TooManySeeds(found))
+        } else if !any_total && found != rules.total_seeds() {
+            Err(SetupError::WrongTotal { expected: rules.total_seeds(), found })
+        } else {
+            Ok(())
+        }
+    }
+
+    // pits in the board's order: A's ponds, A's bank, B's ponds, B's bank
+    pub(crate) fn pits(&self) -> [u8; super::TOTAL_POOLS] {
+        let mut pits = [0; super::TOTAL_POOLS];
+        for (pit, &count) in pits.iter_mut().zip(self.ponds_a.iter()) {
+            *pit = count as u8;
+        }
+        pits[6] = self.bank_a as u8;
+        for (pit, &count) in pits[7..].iter_mut().zip(self.ponds_b.iter()) {
+            *pit = count as u8;
+        }
+        pits[13] = self.bank_b as u8;
+        pits
+    }
+}
+
+fn parse_count(field: &str) -> Result<u32, SetupError> {
+    let count: u32 = field.trim().parse()
+        .map_err(|_| SetupError::Parse(format!("'{}' is not a seed count", field.trim())))?;
+    if count > MAX_SEEDS {
+        return Err(SetupError::TooManySeeds(count));
+    }
+    Ok(count)
+}
+
+fn parse_ponds(field: &str) -> Result<[u32; 6], SetupError> {
+    let counts = field.split(',').map(parse_count).collect::<Result<Vec<u32>, SetupError>>()?;
+    if counts.len() != 6 {
+        return Err(SetupError::Parse(format!("expected 6 ponds, found {}", counts.len())));
+    }
+    let mut ponds = [0; 6];
+    ponds.copy_from_slice(&counts);
+    Ok(ponds)
+}
+
+impl FromStr for Setup {
+    type Err = SetupError;
+
+    fn from_str(s: &str) -> Result<Setup, SetupError> {
+        let fields: Vec<&str> = s.trim().split('/').collect();
+        if fields.len() != 5 {
+            return Err(SetupError::Parse(format!("expected 5 fields separated by '/', found {}", fields.len())));
+        }
+        let to_move = match fields[4].trim() {
+            "A" | "a" => Player::A,
+            "B" | "b" => Player::B,
+            other => return Err(SetupError::Parse(format!("'{}' is not a player", other))),
+        };
+        Ok(Setup {
+            ponds_a: parse_ponds(fields[0])?,
+            bank_a: parse_count(fields[1])?,
+            ponds_b: parse_ponds(fields[2])?,
+            bank_b: parse_count(fields[3])?,
+            to_move,
+        })
+    }
+}
+
+fn join(ponds: &[u32; 6]) -> String {
+    ponds.iter().map(|count| count.to_string()).collect::<Vec<String>>().join(",")
+}
+
+impl fmt::Display for Setup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let to_move = match self.to_move {
+            Player::A => "A",
+            Player::B => "B",
+        };
+        f.write_str(&format!("{}/{}/{}/{}/{}",
+                             join(&self.ponds_a), self.bank_a, join(&self.ponds_b), self.bank_b, to_move))
+    }
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            SetupError::Parse(ref reason) => f.write_str(&format!("Invalid setup: {}", reason)),
+            SetupError::WrongTotal { expected, found } =>
+                f.write_str(&format!("Setup has {} seeds but the rules start with {}", found, expected)),
+            SetupError::TooManySeeds(found) =>
+                f.write_str(&format!("Setup has {} seeds, at most {} fit on the board", found, MAX_SEEDS)),
+            SetupError::NoMoves => f.write_str("The player to move has no legal moves"),
+            SetupError::Over(ending) => f.write_str(&format!("The game is already over: {}", ending)),
+        }
+    }
+}
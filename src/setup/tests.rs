@@ -0,0 +1,67 @@
+use super::*;
+
+#[test]
+fn start() {
+    let setup = Setup::start(&Rules::kalah());
+    assert_eq!(setup.to_string(), "6,6,6,6,6,6/0/6,6,6,6,6,6/0/A");
+    assert_eq!(setup.total(), 72);
+    assert_eq!(Setup::start(&Rules::oware()).total(), 48);
+}
+
+#[test]
+fn parse_round_trip() {
+    let text = "0,1,2,3,4,5/10/5,4,3,2,1,0/17/B";
+    let setup: Setup = text.parse().unwrap();
+    assert_eq!(setup.ponds_a, [0, 1, 2, 3, 4, 5]);
+    assert_eq!(setup.bank_a, 10);
+    assert_eq!(setup.ponds_b, [5, 4, 3, 2, 1, 0]);
+    assert_eq!(setup.bank_b, 17);
+    assert_eq!(setup.to_move, Player::B);
+    assert_eq!(setup.to_string(), text);
+}
+
+#[test]
+fn parse_allows_spaces() {
+    let setup: Setup = " 6, 6,6,6,6,6 / 0 /6,6,6,6,6,6/0/ a ".parse().unwrap();
+    assert_eq!(setup, Setup::start(&Rules::kalah()));
+}
+
+#[test]
+fn parse_errors() {
+    assert_eq!(
+        "6,6,6,6,6,6/0/6,6,6,6,6,6/0".parse::<Setup>(),
+        Err(SetupError::Parse("expected 5 fields separated by '/', found 4".to_string()))
+    );
+    assert_eq!(
+        "6,6,6,6,6/0/6,6,6,6,6,6/0/A".parse::<Setup>(),
+        Err(SetupError::Parse("expected 6 ponds, found 5".to_string()))
+    );
+    assert_eq!(
+        "6,6,6,-1,6,6/0/6,6,6,6,6,6/0/A".parse::<Setup>(),
+        Err(SetupError::Parse("'-1' is not a seed count".to_string()))
+    );
+    assert_eq!(
+        "6,6,6,6,6,6/0/6,6,6,6,6,6/0/C".parse::<Setup>(),
+        Err(SetupError::Parse("'C' is not a player".to_string()))
+    );
+    assert_eq!(
+        "4294967295,1,0,0,0,0/0/0,0,0,0,0,0/0/A".parse::<Setup>(),
+        Err(SetupError::TooManySeeds(4294967295))
+    );
+}
+
+#[test]
+fn check_total() {
+    let mut setup = Setup::start(&Rules::kalah());
+    assert_eq!(setup.check_total(&Rules::kalah(), false), Ok(()));
+    assert_eq!(
+        setup.check_total(&Rules::oware(), false),
+        Err(SetupError::WrongTotal { expected: 48, found: 72 })
+    );
+    assert_eq!(setup.check_total(&Rules::oware(), true), Ok(()));
+    setup.bank_b = 200;
+    assert_eq!(setup.check_total(&Rules::kalah(), true), Err(SetupError::TooManySeeds(272)));
+    // counts too big to add up
+    setup.bank_a = u32::MAX;
+    assert_eq!(setup.check_total(&Rules::kalah(), true), Err(SetupError::TooManySeeds(u32::MAX)));
+}
@@ -0,0 +1,185 @@
+// Draws the App: the board with the game's details on the left, and the move list and what the
+// AI is thinking on the right
+use std::time::Instant;
+
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::Frame;
+
+use engine::SearchLimit;
+use player::Player;
+use rules::{Direction, SowingPolicy};
+
+use super::{App, Seat};
+
+const BANK_A: usize = 6;
+const BANK_B: usize = 13;
+
+fn seat_name(seat: &Seat) -> String {
+    match *seat {
+        Seat::Human => "human".to_string(),
+        Seat::AI(ref name, SearchLimit::Depth(depth)) => format!("{} (depth {})", name, depth),
+        Seat::AI(ref name, SearchLimit::MoveTime(time)) => format!("{} ({:.1}s a move)", name, time.as_secs_f64()),
+    }
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::CounterClockwise => "counter-clockwise",
+        Direction::Clockwise => "clockwise",
+    }
+}
+
+pub(super) fn draw(frame: &mut Frame, app: &App) {
+    let [main, help] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+    let [left, right] = Layout::horizontal([Constraint::Min(46), Constraint::Length(30)]).areas(main);
+    let [board, status] = Layout::vertical([Constraint::Length(11), Constraint::Min(3)]).areas(left);
+    let [moves, search] = Layout::vertical([Constraint::Min(3), Constraint::Length(6)]).areas(right);
+    draw_board(frame, app, board);
+    draw_status(frame, app, status);
+    draw_moves(frame, app, moves);
+    draw_search(frame, app, search);
+    frame.render_widget(Paragraph::new(help_line(app)).style(Style::new().fg(Color::DarkGray)), help);
+}
+
+fn pit_style(app: &App, idx: usize) -> Style {
+    let view = &app.view;
+    if view.active == Some(idx) {
+        return Style::new().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD);
+    }
+    if view.captured.contains(&idx) {
+        return Style::new().fg(Color::Red).add_modifier(Modifier::BOLD);
+    }
+    if app.human_to_move() && idx != BANK_A && idx != BANK_B {
+        let side = if idx < BANK_A { Player::A } else { Player::B };
+        let pond = idx % (BANK_A + 1);
+        if side == *app.game.current_player() && app.playable(pond) {
+            let style = Style::new().fg(Color::Green);
+            return if pond == app.cursor { style.add_modifier(Modifier::REVERSED | Modifier::BOLD) } else { style };
+        }
+    }
+    Style::new()
+}
+
+fn pit<'a>(app: &App, idx: usize) -> Span<'a> {
+    Span::styled(format!("[{:2}]", app.view.pits[idx]), pit_style(app, idx))
+}
+
+// A row of ponds between the banks' columns, left to right on screen
+fn pond_row<'a>(app: &App, idxs: &[usize]) -> Line<'a> {
+    let mut spans = vec![Span::raw("      ")];
+    for &idx in idxs {
+        spans.push(pit(app, idx));
+        spans.push(Span::raw(" "));
+    }
+    Line::from(spans)
+}
+
+fn pond_labels<'a>(ponds: &[usize]) -> Line<'a> {
+    let labels: String = ponds.iter().map(|pond| format!("  {}  ", pond)).collect();
+    Line::styled(format!("      {}", labels), Style::new().fg(Color::DarkGray))
+}
+
+fn side_line<'a>(app: &App, side: Player) -> Line<'a> {
+    let to_move = !app.game.is_finished() && *app.game.current_player() == side;
+    let label = format!(" {:?}: {}", side, seat_name(app.seat(&side)));
+    let style = if to_move { Style::new().add_modifier(Modifier::BOLD) } else { Style::new() };
+    let mut spans = vec![Span::styled(label, style)];
+    if to_move {
+        spans.push(Span::styled("  to move", Style::new().fg(Color::Cyan)));
+    }
+    Line::from(spans)
+}
+
+fn draw_board(frame: &mut Frame, app: &App, area: Rect) {
+    let mut title = format!(" Kalaha: {} ", app.game.rules());
+    if app.game.swapped() {
+        title.push_str("(swapped) ");
+    }
+    let banks = Line::from(vec![
+        Span::raw(" "),
+        pit(app, BANK_B),
+        Span::raw(" ".repeat(30)),
+        pit(app, BANK_A),
+    ]);
+    let lines = vec![
+        side_line(app, Player::B),
+        Line::raw(""),
+        pond_labels(&[5, 4, 3, 2, 1, 0]),
+        pond_row(app, &[12, 11, 10, 9, 8, 7]),
+        banks,
+        pond_row(app, &[0, 1, 2, 3, 4, 5]),
+        pond_labels(&[0, 1, 2, 3, 4, 5]),
+        Line::raw(""),
+        side_line(app, Player::A),
+    ];
+    frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(title)), area);
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: Rect) {
+    let mut lines = Vec::new();
+    if app.game.is_finished() {
+        lines.push(Line::styled(app.game.game_result().to_string(), Style::new().add_modifier(Modifier::BOLD)));
+    } else if app.game.swap_offered() {
+        lines.push(Line::raw(format!("{:?} may swap sides instead of moving", app.game.current_player())));
+    } else if app.human_to_move() {
+        lines.push(Line::raw(format!("{:?} to move, pick a pond and press enter", app.game.current_player())));
+    }
+    if app.game.rules().sowing == SowingPolicy::PlayerChoice && app.human_to_move() {
+        lines.push(Line::raw(format!("Sowing {}", direction_name(app.direction))));
+    }
+    if !app.message.is_empty() {
+        lines.push(Line::styled(app.message.clone(), Style::new().fg(Color::Yellow)));
+    }
+    frame.render_widget(Paragraph::new(lines).block(Block::bordered()), area);
+}
+
+fn draw_moves(frame: &mut Frame, app: &App, area: Rect) {
+    let lines: Vec<Line> = app.played.iter().enumerate().map(|(i, played)| {
+        let mut text = format!("{:3}. {:?} {}", i + 1, played.player, played.mv);
+        if played.outcome.extra_turn {
+            text.push_str("  again");
+        }
+        if played.outcome.captured > 0 {
+            text.push_str(&format!("  takes {}", played.outcome.captured));
+        }
+        Line::raw(text)
+    }).collect();
+    // the latest moves, as many as fit inside the border
+    let shown = usize::from(area.height.saturating_sub(2));
+    let skip = lines.len().saturating_sub(shown);
+    let lines: Vec<Line> = lines.into_iter().skip(skip).collect();
+    frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" Moves ")), area);
+}
+
+fn draw_search(frame: &mut Frame, app: &App, area: Rect) {
+    let mut lines = Vec::new();
+    if let Some(ref search) = app.search {
+        let took = search.took.unwrap_or_else(|| Instant::now() - search.started);
+        lines.push(Line::raw(match search.took {
+            Some(_) => format!("{} moved", search.ai),
+            None => format!("{} is thinking", search.ai),
+        }));
+        if let Some((depth, analysis)) = search.depth {
+            let score = analysis.score.map_or(String::new(), |score| format!("  score {:+}", score));
+            lines.push(Line::raw(format!("depth {}{}", depth, score)));
+            lines.push(Line::raw(format!("best {}", analysis.mv)));
+        }
+        lines.push(Line::raw(format!("{:.1}s", took.as_secs_f64())));
+    }
+    frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(" AI ")), area);
+}
+
+fn help_line(app: &App) -> String {
+    let mut keys = vec!["←/→ pick", "enter sow"];
+    if app.game.rules().directions().len() > 1 {
+        keys.push("tab direction");
+    }
+    if app.game.rules().pie_rule {
+        keys.push("s swap");
+    }
+    keys.extend_from_slice(&["u undo", "p pause", "n new game", "q quit"]);
+    format!(" {}", keys.join("  "))
+}
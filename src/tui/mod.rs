@@ -0,0 +1,414 @@
+// A full-screen terminal UI behind the `tui` feature, run by the kalaha-tui binary. Each side
+// is a person at the keyboard or one of the built in AIs, so it covers playing against an AI,
+// two people sharing a keyboard and watching two AIs play.
+//
+// Moves are sown on screen a seed at a time, see Board::sowing. AIs search in a thread with
+// engine::deepen so the screen keeps up while they think, and shows each depth as it finishes.
+use std::collections::VecDeque;
+use std::io;
+use std::panic;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::crossterm::terminal::{self, EnterAlternateScreen};
+use ratatui::crossterm::ExecutableCommand;
+use ratatui::{DefaultTerminal, Terminal};
+
+use ai::{self, AIError, Analysis};
+use board::Sow;
+use engine::{self, SearchLimit};
+use error::Error;
+use kalaha::Kalaha;
+use moves::Move;
+use player::Player;
+use rules::{Direction, SowingPolicy};
+use turn::MoveOutcome;
+
+mod draw;
+#[cfg(test)]
+mod tests;
+
+// How often the screen is redrawn while nothing is happening
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// Who plays a side
+#[derive(Debug, PartialEq, Clone)]
+pub enum Seat {
+    Human,
+    // one of ai::by_name's AIs, searching as far as the limit allows
+    AI(String, SearchLimit),
+}
+
+impl Seat {
+    fn is_human(&self) -> bool {
+        *self == Seat::Human
+    }
+}
+
+// A move as the move list shows it
+#[derive(Debug, Clone)]
+struct Played {
+    player: Player,
+    mv: Move,
+    outcome: MoveOutcome,
+}
+
+// The pits as they are on screen, which runs behind the game while a move is sown
+#[derive(Debug, PartialEq, Clone)]
+struct View {
+    pits: [u32; ::TOTAL_POOLS],
+    // the pit a seed was just picked up from or dropped into
+    active: Option<usize>,
+    // pits a capture or the end of the game took seeds from or put them in
+    captured: Vec<usize>,
+}
+
+enum Search {
+    Depth(u32, Analysis),
+    Done(Result<Analysis, AIError>),
+}
+
+// What an AI is thinking, or last thought
+#[derive(Debug, Clone)]
+struct SearchInfo {
+    ai: String,
+    // the deepest search finished so far and what it found
+    depth: Option<(u32, Analysis)>,
+    started: Instant,
+    // None while the AI is still thinking
+    took: Option<Duration>,
+}
+
+struct App {
+    start: Kalaha,
+    game: Kalaha,
+    seats: [Seat; 2],
+    // how long each seed stays on screen
+    step: Duration,
+    // the game before each move in played, for undo
+    history: Vec<Kalaha>,
+    played: Vec<Played>,
+    view: View,
+    frames: VecDeque<View>,
+    next_frame: Instant,
+    // the pond the current human player has picked, and the direction under SowingPolicy::PlayerChoice
+    cursor: usize,
+    direction: Direction,
+    search: Option<SearchInfo>,
+    searching: Option<Receiver<Search>>,
+    // set to make the running search give up, so cancelled searches don't carry on using the CPU
+    stop: Option<Arc<AtomicBool>>,
+    // AIs wait to move, for watching an AI game one move at a time
+    paused: bool,
+    message: String,
+    quit: bool,
+}
+
+fn describe(error: &Error) -> &'static str {
+    match *error {
+        Error::EmptyPool => "that pond is empty",
+        Error::MustFeed => "that leaves the opponent without seeds",
+        Error::WrongDirection => "seeds can't be sown that way",
+        Error::CannotSwap => "there's no swap on offer",
+        _ => error.name(),
+    }
+}
+
+impl App {
+    fn new(game: Kalaha, seats: [Seat; 2], step: Duration, now: Instant) -> App {
+        let mut app = App {
+            start: game.clone(),
+            view: View { pits: game.pits(), active: None, captured: Vec::new() },
+            direction: game.rules().directions()[0],
+            game,
+            seats,
+            step,
+            history: Vec::new(),
+            played: Vec::new(),
+            frames: VecDeque::new(),
+            next_frame: now,
+            cursor: 0,
+            search: None,
+            searching: None,
+            stop: None,
+            paused: false,
+            message: String::new(),
+            quit: false,
+        };
+        app.reset_cursor();
+        app
+    }
+
+    // After a swap the player who moved second plays side A
+    fn seat(&self, side: &Player) -> &Seat {
+        &self.seats[self.game.seat(side)]
+    }
+
+    fn animating(&self) -> bool {
+        !self.frames.is_empty()
+    }
+
+    fn human_to_move(&self) -> bool {
+        !self.game.is_finished() && !self.animating() && self.seat(self.game.current_player()).is_human()
+    }
+
+    fn cursor_move(&self) -> Move {
+        match self.game.rules().sowing {
+            SowingPolicy::Fixed(_) => Move::Pond(self.cursor),
+            SowingPolicy::PlayerChoice => Move::Directed(self.cursor, self.direction),
+        }
+    }
+
+    // ponds with a legal move, whichever way they're sown
+    fn playable(&self, pond: usize) -> bool {
+        self.game.legal_moves().iter().any(|mv| mv.pond() == Some(pond))
+    }
+
+    fn reset_cursor(&mut self) {
+        self.cursor = (0..::PONDS_PER_PLAYER).find(|&pond| self.playable(pond)).unwrap_or(0);
+    }
+
+    // Moves to the next playable pond to the left or right on screen, where B's ponds run
+    // backwards
+    fn move_cursor(&mut self, right: bool) {
+        let up = right == (*self.game.current_player() == Player::A);
+        let mut pond = self.cursor;
+        for _ in 1..::PONDS_PER_PLAYER {
+            pond = if up { (pond + 1) % ::PONDS_PER_PLAYER } else { (pond + ::PONDS_PER_PLAYER - 1) % ::PONDS_PER_PLAYER };
+            if self.playable(pond) {
+                self.cursor = pond;
+                return;
+            }
+        }
+    }
+
+    fn key(&mut self, code: KeyCode, now: Instant) {
+        self.message.clear();
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Left | KeyCode::Char('h') => self.move_cursor(false),
+            KeyCode::Right | KeyCode::Char('l') => self.move_cursor(true),
+            KeyCode::Char(digit @ '0'..='5') => self.cursor = digit as usize - '0' as usize,
+            KeyCode::Tab | KeyCode::Char('d') => {
+                let directions = self.game.rules().directions();
+                if directions.len() > 1 {
+                    self.direction = if self.direction == directions[0] { directions[1] } else { directions[0] };
+                }
+            },
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                let mv = self.cursor_move();
+                self.human_move(mv, now);
+            },
+            KeyCode::Char('s') => self.human_move(Move::Swap, now),
+            KeyCode::Char('u') => self.undo(),
+            KeyCode::Char('p') => {
+                self.paused = !self.paused;
+                if self.paused {
+                    self.message = "Paused, p to carry on".to_string();
+                }
+            },
+            KeyCode::Char('n') => self.restart(),
+            _ => {},
+        }
+    }
+
+    fn human_move(&mut self, mv: Move, now: Instant) {
+        if !self.human_to_move() {
+            return;
+        }
+        match self.game.valid_move(mv) {
+            Ok(()) => self.play(mv, now),
+            Err(error) => self.message = format!("Can't play {}: {}", mv, describe(&error)),
+        }
+    }
+
+    fn play(&mut self, mv: Move, now: Instant) {
+        let steps = self.game.sowing(mv);
+        let mut sown = self.game.pits();
+        self.history.push(self.game.clone());
+        let player = self.game.current_player().clone();
+        let outcome = self.game.choose(mv);
+        self.played.push(Played { player, mv, outcome });
+        for step in steps {
+            let active = match step {
+                Sow::PickUp(idx) => {
+                    sown[idx] = 0;
+                    idx
+                },
+                Sow::Drop(idx) => {
+                    sown[idx] += 1;
+                    idx
+                },
+            };
+            self.frames.push_back(View { pits: sown, active: Some(active), captured: Vec::new() });
+        }
+        let settled = self.game.pits();
+        let captured = (0..::TOTAL_POOLS).filter(|&idx| settled[idx] != sown[idx]).collect();
+        self.frames.push_back(View { pits: settled, active: None, captured });
+        self.next_frame = now;
+        self.reset_cursor();
+    }
+
+    fn cancel(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            stop.store(true, Ordering::Relaxed);
+        }
+        self.searching = None;
+        self.search = None;
+        self.frames.clear();
+        self.view = View { pits: self.game.pits(), active: None, captured: Vec::new() };
+    }
+
+    // Takes back moves until a person is to move, or just the last move if AIs play both sides
+    fn undo(&mut self) {
+        if self.history.is_empty() {
+            self.message = "Nothing to undo".to_string();
+            return;
+        }
+        let any_human = self.seats.iter().any(Seat::is_human);
+        while let Some(game) = self.history.pop() {
+            self.game = game;
+            self.played.pop();
+            if !any_human || self.seat(self.game.current_player()).is_human() {
+                break;
+            }
+        }
+        if !any_human {
+            self.paused = true;
+            self.message = "Paused, p to carry on".to_string();
+        }
+        self.cancel();
+        self.reset_cursor();
+    }
+
+    fn restart(&mut self) {
+        self.game = self.start.clone();
+        self.history.clear();
+        self.played.clear();
+        self.cancel();
+        self.reset_cursor();
+    }
+
+    fn think(&mut self, name: String, limit: SearchLimit, now: Instant) {
+        let (sender, receiver) = mpsc::channel();
+        let game = self.game.clone();
+        self.search = Some(SearchInfo { ai: name.clone(), depth: None, started: now, took: None });
+        self.searching = Some(receiver);
+        let stop = Arc::new(AtomicBool::new(false));
+        self.stop = Some(Arc::clone(&stop));
+        thread::spawn(move || {
+            let make_ai = |depth| ai::by_name(&name, depth).expect("checked when the seat was made");
+            // cancel sets stop and closes the channel, so there's no one to tell
+            let mut report = |depth, analysis: &Analysis, _: &[Move]| {
+                sender.send(Search::Depth(depth, *analysis)).map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+            };
+            if let Ok(result) = engine::deepen(&make_ai, &game, limit, Some(stop), &mut report) {
+                let _ = sender.send(Search::Done(result));
+            }
+        });
+    }
+
+    fn receive(&self) -> Option<Search> {
+        match self.searching.as_ref()?.try_recv() {
+            Ok(search) => Some(search),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Search::Done(Err(AIError::Failed("the search stopped".to_string())))),
+        }
+    }
+
+    // Moves the game along: shows the next seed, picks up what an AI found and starts AIs
+    // thinking
+    fn tick(&mut self, now: Instant) {
+        while self.next_frame <= now {
+            match self.frames.pop_front() {
+                Some(view) => {
+                    self.view = view;
+                    self.next_frame += self.step;
+                },
+                None => break,
+            }
+        }
+        while let Some(search) = self.receive() {
+            match search {
+                Search::Depth(depth, analysis) => {
+                    if let Some(ref mut search) = self.search {
+                        search.depth = Some((depth, analysis));
+                    }
+                },
+                Search::Done(result) => {
+                    self.searching = None;
+                    self.stop = None;
+                    if let Some(ref mut search) = self.search {
+                        search.took = Some(now - search.started);
+                    }
+                    match result {
+                        Ok(analysis) => self.play(analysis.mv, now),
+                        Err(error) => {
+                            self.paused = true;
+                            self.message = format!("The AI failed: {}", error);
+                        },
+                    }
+                },
+            }
+        }
+        if self.searching.is_none() && !self.animating() && !self.paused && !self.game.is_finished() {
+            if let Seat::AI(ref name, limit) = *self.seat(self.game.current_player()) {
+                let name = name.clone();
+                self.think(name, limit, now);
+            }
+        }
+    }
+}
+
+// Runs the UI until the player quits, playing game with seats[0] as the player who starts as
+// A. Each seed stays on screen for step while a move is sown.
+pub fn run(game: Kalaha, seats: [Seat; 2], step: Duration) -> io::Result<()> {
+    // a panic in the UI puts the terminal back before the message is printed, so it can be
+    // read. A search that panics only fails that search, and the UI carries on.
+    let hook = Arc::new(panic::take_hook());
+    let previous = Arc::clone(&hook);
+    let ui = thread::current().id();
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().id() == ui {
+            ratatui::restore();
+        }
+        previous(info);
+    }));
+    let app = App::new(game, seats, step, Instant::now());
+    let result = start().and_then(|mut terminal| event_loop(&mut terminal, app));
+    ratatui::restore();
+    drop(panic::take_hook());
+    if let Ok(hook) = Arc::try_unwrap(hook) {
+        panic::set_hook(hook);
+    }
+    result
+}
+
+// ratatui::init without its panic hook, which restores the terminal whichever thread panics
+fn start() -> io::Result<DefaultTerminal> {
+    terminal::enable_raw_mode()?;
+    io::stdout().execute(EnterAlternateScreen)?;
+    Terminal::new(CrosstermBackend::new(io::stdout()))
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, mut app: App) -> io::Result<()> {
+    while !app.quit {
+        terminal.draw(|frame| draw::draw(frame, &app))?;
+        let now = Instant::now();
+        let wait = if app.animating() && app.next_frame > now { (app.next_frame - now).min(POLL_INTERVAL) } else { POLL_INTERVAL };
+        if event::poll(wait)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.key(key.code, Instant::now());
+                }
+            }
+        }
+        app.tick(Instant::now());
+    }
+    Ok(())
+}
@@ -0,0 +1,183 @@
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+
+use super::*;
+use rules::Rules;
+use setup::Setup;
+
+const STEP: Duration = Duration::from_millis(100);
+
+fn humans(game: Kalaha, now: Instant) -> App {
+    App::new(game, [Seat::Human, Seat::Human], STEP, now)
+}
+
+fn from_setup(setup: &str) -> Kalaha {
+    Kalaha::from_setup_any_total(Rules::kalah(), &setup.parse::<Setup>().unwrap()).unwrap()
+}
+
+// Ticks until nothing is left to show or think about
+fn settle(app: &mut App, mut now: Instant) -> Instant {
+    let give_up = Instant::now() + Duration::from_secs(10);
+    while app.animating() || app.searching.is_some() {
+        assert!(Instant::now() < give_up, "the app never settled");
+        now += STEP;
+        app.tick(now);
+        thread::sleep(Duration::from_millis(1));
+    }
+    now
+}
+
+fn render(app: &App) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+    terminal.draw(|frame| draw::draw(frame, app)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let mut text = String::new();
+    for y in 0..buffer.area.height {
+        for x in 0..buffer.area.width {
+            text.push_str(buffer[(x, y)].symbol());
+        }
+        text.push('\n');
+    }
+    text
+}
+
+#[test]
+fn sowing_a_seed_at_a_time() {
+    let now = Instant::now();
+    let mut app = humans(Kalaha::new(), now);
+    app.key(KeyCode::Right, now);
+    app.key(KeyCode::Enter, now);
+    // the game has moved on but the screen hasn't
+    assert_eq!(app.game.ponds(&Player::A), [6, 0, 7, 7, 7, 7]);
+    assert_eq!(app.view.pits[1], 6);
+    app.tick(now);
+    assert_eq!(app.view.active, Some(1));
+    assert_eq!(app.view.pits[1], 0);
+    app.tick(now + STEP);
+    assert_eq!((app.view.active, app.view.pits[2]), (Some(2), 7));
+    // the last seed lands in B's first pond
+    app.tick(now + STEP * 6);
+    assert_eq!((app.view.active, app.view.pits[7]), (Some(7), 7));
+    app.tick(now + STEP * 7);
+    assert!(!app.animating());
+    assert_eq!(app.view, View { pits: app.game.pits(), active: None, captured: Vec::new() });
+}
+
+#[test]
+fn captures_are_highlighted() {
+    let now = Instant::now();
+    let mut app = humans(from_setup("1,0,0,0,0,0/0/0,0,0,0,5,0/0/A"), now);
+    app.key(KeyCode::Enter, now);
+    settle(&mut app, now);
+    // the seed landing in A's pond 1 takes the 5 opposite, in B's pond 4
+    assert_eq!(app.played[0].outcome.captured, 6);
+    assert_eq!(app.view.captured, vec![1, 6, 11]);
+}
+
+#[test]
+fn illegal_moves() {
+    let now = Instant::now();
+    let mut app = humans(from_setup("0,6,6,6,6,6/0/6,6,6,6,6,6/0/A"), now);
+    assert_eq!(app.cursor, 1);
+    app.key(KeyCode::Char('0'), now);
+    app.key(KeyCode::Enter, now);
+    assert_eq!(app.message, "Can't play 0: that pond is empty");
+    assert!(app.played.is_empty());
+    app.key(KeyCode::Char('s'), now);
+    assert_eq!(app.message, "Can't play swap: there's no swap on offer");
+}
+
+#[test]
+fn cursor_skips_empty_ponds() {
+    let now = Instant::now();
+    let mut app = humans(from_setup("1,0,0,4,0,0/0/6,6,6,6,6,6/0/A"), now);
+    app.key(KeyCode::Right, now);
+    assert_eq!(app.cursor, 3);
+    app.key(KeyCode::Right, now);
+    assert_eq!(app.cursor, 0);
+    app.key(KeyCode::Left, now);
+    assert_eq!(app.cursor, 3);
+}
+
+#[test]
+fn choosing_the_direction() {
+    let now = Instant::now();
+    let rules = Rules::oware().with_sowing(SowingPolicy::PlayerChoice);
+    let mut app = humans(Kalaha::with_rules(rules), now);
+    app.key(KeyCode::Tab, now);
+    app.key(KeyCode::Enter, now);
+    assert_eq!(app.played[0].mv, Move::Directed(0, Direction::Clockwise));
+}
+
+#[test]
+fn undo_takes_back_the_ai_reply_too() {
+    let now = Instant::now();
+    let seats = [Seat::Human, Seat::AI("first".to_string(), SearchLimit::Depth(1))];
+    let mut app = App::new(Kalaha::new(), seats, STEP, now);
+    app.key(KeyCode::Char('2'), now);
+    app.key(KeyCode::Enter, now);
+    let now = settle(&mut app, now);
+    // the AI started thinking once the move had been sown, and replied
+    let now = settle(&mut app, now + STEP);
+    assert_eq!(app.played.len(), 2);
+    assert_eq!(app.search.as_ref().unwrap().ai, "first");
+    app.key(KeyCode::Char('u'), now);
+    assert!(app.played.is_empty());
+    assert_eq!(app.game, Kalaha::new());
+    assert_eq!(app.view.pits, Kalaha::new().pits());
+    app.key(KeyCode::Char('u'), now);
+    assert_eq!(app.message, "Nothing to undo");
+}
+
+#[test]
+fn watching_ais_play() {
+    let now = Instant::now();
+    let seats = [
+        Seat::AI("first".to_string(), SearchLimit::Depth(1)),
+        Seat::AI("alphabeta".to_string(), SearchLimit::Depth(2)),
+    ];
+    let mut app = App::new(Kalaha::with_rules(Rules::kalah().with_pie_rule(true)), seats, Duration::from_millis(0), now);
+    let give_up = Instant::now() + Duration::from_secs(10);
+    while !app.game.is_finished() {
+        assert!(Instant::now() < give_up, "the game never finished");
+        app.tick(Instant::now());
+    }
+    let played = app.played.len();
+    // undo with only AIs playing takes back one move and pauses
+    app.key(KeyCode::Char('u'), now);
+    assert_eq!(app.played.len(), played - 1);
+    assert!(app.paused);
+    app.tick(Instant::now());
+    assert!(app.searching.is_none());
+}
+
+// starting again stops the search that was running rather than leaving it to finish
+#[test]
+fn restart_stops_the_search() {
+    let now = Instant::now();
+    let seats = [Seat::AI("minmax".to_string(), SearchLimit::Depth(30)), Seat::Human];
+    let mut app = App::new(Kalaha::new(), seats, STEP, now);
+    app.tick(now);
+    let stop = app.stop.clone().expect("the AI is thinking");
+    app.key(KeyCode::Char('n'), now);
+    assert!(stop.load(Ordering::Relaxed));
+    app.tick(now);
+    assert!(app.stop.as_ref().is_some_and(|next| !Arc::ptr_eq(next, &stop)), "the AI thinks again from the start");
+    app.cancel();
+}
+
+#[test]
+fn screen() {
+    let now = Instant::now();
+    let seats = [Seat::Human, Seat::AI("alphabeta".to_string(), SearchLimit::Depth(6))];
+    let mut app = App::new(Kalaha::new(), seats, STEP, now);
+    app.key(KeyCode::Enter, now);
+    settle(&mut app, now);
+    let text = render(&app);
+    assert!(text.contains(" Kalaha: kalah "), "{}", text);
+    assert!(text.contains(" B: alphabeta (depth 6)"), "{}", text);
+    assert!(text.contains(" A: human  to move"), "{}", text);
+    assert!(text.contains("[ 0] [ 7] [ 7] [ 7] [ 7] [ 7]"), "{}", text);
+    assert!(text.contains("  1. A 0  again"), "{}", text);
+    assert!(text.contains("u undo"), "{}", text);
+}
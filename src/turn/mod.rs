@@ -6,70 +6,219 @@ use super::player::Player;
 #[cfg(test)]
 mod tests;
 
-#[derive(Debug, PartialEq, Clone)]
+// Why the game ended
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Ending {
+    // a player ran out of seeds and the rest were swept into a bank
+    SideEmpty,
+    // a player had more than half the seeds in their bank
+    Majority,
+    // the player to move had no legal move (Oware)
+    NoMoves,
+    // too many moves went by without a capture (Oware)
+    NoCaptures,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "::serialize::GameResultRepr", try_from = "::serialize::GameResultRepr"))]
 pub enum GameResult {
-    Winner { player: Player, score_a: u32, score_b: u32 },
-    Draw { score: u32 },
+    Winner { player: Player, score_a: u32, score_b: u32, ending: Ending },
+    Draw { score: u32, ending: Ending },
+    // `player` forfeited, the scores are the banks at the time
+    Forfeit { player: Player, score_a: u32, score_b: u32 },
 }
 
-#[derive(Debug, PartialEq, Clone)]
+// Where one seat finished. Seats are numbered from 0, and seat 0 is player A.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Standing {
+    pub seat: usize,
+    pub score: u32,
+    // 1 for the winner(s), players with the same score share a place
+    pub place: usize,
+}
+
+// Every seat's standing at the end of a game, best first
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Ranking {
+    pub standings: Vec<Standing>,
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(into = "::serialize::TurnRepr", from = "::serialize::TurnRepr"))]
 pub enum Turn {
     Player(Player),
+    // the player may either move or swap sides, see Rules::pie_rule
+    SwapOffered(Player),
     Finished(GameResult),
 }
 
+// What happened when a move was made
+#[derive(Debug, PartialEq, Clone)]
+pub struct MoveOutcome {
+    pub turn: Turn,
+    // the last seed landed in the player's own bank so they move again
+    pub extra_turn: bool,
+    // seeds moved into the bank by a capture, 0 if there wasn't one
+    pub captured: u32,
+    // how many times seeds were picked up and sown, more than 1 only with relay
+    // sowing and 0 for a swap
+    pub laps: u32,
+}
+
+impl Ending {
+    // a short name for the ending, as in protocols
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Ending::SideEmpty => "side_empty",
+            Ending::Majority => "majority",
+            Ending::NoMoves => "no_moves",
+            Ending::NoCaptures => "no_captures",
+        }
+    }
+}
+
+impl GameResult {
+    pub fn ranking(&self) -> Ranking {
+        match *self {
+            GameResult::Winner { score_a, score_b, .. } => Ranking::from_scores(&[score_a, score_b]),
+            GameResult::Draw { score, .. } => Ranking::from_scores(&[score, score]),
+            // the player who didn't forfeit wins whatever the scores
+            GameResult::Forfeit { ref player, score_a, score_b } => {
+                let scores = [score_a, score_b];
+                let (loser, winner) = match *player {
+                    Player::A => (0, 1),
+                    Player::B => (1, 0),
+                };
+                Ranking {
+                    standings: vec![
+                        Standing { seat: winner, score: scores[winner], place: 1 },
+                        Standing { seat: loser, score: scores[loser], place: 2 },
+                    ],
+                }
+            }
+        }
+    }
+}
+
+impl Ranking {
+    // Ranks seats by score, with scores[seat] for each seat
+    pub fn from_scores(scores: &[u32]) -> Ranking {
+        let mut seats: Vec<usize> = (0..scores.len()).collect();
+        // stable, so tied seats stay in seat order
+        seats.sort_by(|&a, &b| scores[b].cmp(&scores[a]));
+        let mut standings: Vec<Standing> = Vec::with_capacity(seats.len());
+        for (i, &seat) in seats.iter().enumerate() {
+            let place = match standings.last() {
+                Some(last) if last.score == scores[seat] => last.place,
+                _ => i + 1,
+            };
+            standings.push(Standing { seat, score: scores[seat], place });
+        }
+        Ranking { standings }
+    }
+
+    // Every seat in first place
+    pub fn winners(&self) -> Vec<usize> {
+        self.standings.iter().filter(|standing| standing.place == 1).map(|standing| standing.seat).collect()
+    }
+}
+
+// Player A, Player B, ... for seats 0, 1, ...
+pub fn seat_name(seat: usize) -> String {
+    format!("Player {}", (b'A' + seat as u8) as char)
+}
+
 impl Turn {
-    pub fn new_finished(score_a: u32, score_b: u32) -> Turn {
+    pub fn new_finished(score_a: u32, score_b: u32, ending: Ending) -> Turn {
         let game_result = match score_a.cmp(&score_b) {
-            Ordering::Greater => GameResult::Winner { player: Player::A, score_a, score_b },
-            Ordering::Less => GameResult::Winner { player: Player::B, score_a, score_b },
-            Ordering::Equal => GameResult::Draw { score: score_a },
+            Ordering::Greater => GameResult::Winner { player: Player::A, score_a, score_b, ending },
+            Ordering::Less => GameResult::Winner { player: Player::B, score_a, score_b, ending },
+            Ordering::Equal => GameResult::Draw { score: score_a, ending },
         };
         Turn::Finished(game_result)
     }
 
     pub fn is_finished(&self) -> bool {
         match *self {
-            Turn::Player(_) => false,
+            Turn::Player(_) | Turn::SwapOffered(_) => false,
             Turn::Finished(_) => true,
         }
     }
 
     pub fn player(&self) -> &Player {
         match *self {
-            Turn::Player(ref player) => player,
+            Turn::Player(ref player) | Turn::SwapOffered(ref player) => player,
             Turn::Finished(_) => panic!("Game has finished"),
         }
     }
 
     pub fn game_result(&self) -> &GameResult {
         match *self {
-            Turn::Player(_) => panic!("Game has not finished"),
+            Turn::Player(_) | Turn::SwapOffered(_) => panic!("Game has not finished"),
             Turn::Finished(ref game_result) => game_result,
         }
     }
+
+    // The same turn with the players swapped, for a mirrored board
+    pub fn mirrored(&self) -> Turn {
+        match *self {
+            Turn::Player(ref player) => Turn::Player(player.next()),
+            Turn::SwapOffered(ref player) => Turn::SwapOffered(player.next()),
+            Turn::Finished(GameResult::Winner { ref player, score_a, score_b, ending }) =>
+                Turn::Finished(GameResult::Winner { player: player.next(), score_a: score_b, score_b: score_a, ending }),
+            Turn::Finished(GameResult::Draw { score, ending }) => Turn::Finished(GameResult::Draw { score, ending }),
+            Turn::Finished(GameResult::Forfeit { ref player, score_a, score_b }) =>
+                Turn::Finished(GameResult::Forfeit { player: player.next(), score_a: score_b, score_b: score_a }),
+        }
+    }
+}
+
+impl fmt::Display for Ending {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Ending::SideEmpty => f.write_str("a side ran out of seeds"),
+            Ending::Majority => f.write_str("more than half the seeds banked"),
+            Ending::NoMoves => f.write_str("no moves left"),
+            Ending::NoCaptures => f.write_str("no captures for too long"),
+        }
+    }
 }
 
 impl fmt::Display for GameResult {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match *self {
-            GameResult::Winner {player: Player::A, score_a, score_b} =>
-                f.write_str(&format!("Player A wins {} to {}", score_a, score_b)),
-            GameResult::Winner {player: Player::B, score_a, score_b} =>
-                f.write_str(&format!("Player B wins {} to {}", score_b, score_a)),
-            GameResult::Draw { score } =>
-                f.write_str(&format!("Draw {} to {}", score, score)),
+            GameResult::Winner {player: Player::A, score_a, score_b, ending} =>
+                f.write_str(&format!("Player A wins {} to {} ({})", score_a, score_b, ending)),
+            GameResult::Winner {player: Player::B, score_a, score_b, ending} =>
+                f.write_str(&format!("Player B wins {} to {} ({})", score_b, score_a, ending)),
+            GameResult::Draw { score, ending } =>
+                f.write_str(&format!("Draw {} to {} ({})", score, score, ending)),
+            GameResult::Forfeit { ref player, score_a, score_b } =>
+                f.write_str(&format!("{} wins by forfeit ({} forfeited at {} to {})",
+                                     player.next(), player, score_a, score_b)),
         }
     }
 }
 
+impl fmt::Display for Ranking {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        let places: Vec<String> = self.standings.iter()
+            .map(|standing| format!("{}. {} {}", standing.place, seat_name(standing.seat), standing.score))
+            .collect();
+        f.write_str(&places.join(", "))
+    }
+}
+
 impl fmt::Display for Turn {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match *self {
             Turn::Player(ref player) => f.write_str(&format!("Next turn: {}", player)),
+            Turn::SwapOffered(ref player) =>
+                f.write_str(&format!("Next turn: {} (may swap)", player)),
             Turn::Finished(ref game_result) =>
                 f.write_str(&format!("Game finished: {}", game_result)),
         }
     }
 }
-
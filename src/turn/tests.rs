@@ -3,49 +3,66 @@ use super::*;
 #[test]
 fn new_finished_a() {
     assert_eq!(
-        Turn::new_finished(20, 10),
-        Turn::Finished(GameResult::Winner { player: Player::A, score_a: 20, score_b: 10 })
+        Turn::new_finished(20, 10, Ending::SideEmpty),
+        Turn::Finished(GameResult::Winner { player: Player::A, score_a: 20, score_b: 10, ending: Ending::SideEmpty })
     );
 }
 
 #[test]
 fn new_finished_b() {
     assert_eq!(
-        Turn::new_finished(20, 30),
-        Turn::Finished(GameResult::Winner { player: Player::B, score_a: 20, score_b: 30 })
+        Turn::new_finished(20, 30, Ending::Majority),
+        Turn::Finished(GameResult::Winner { player: Player::B, score_a: 20, score_b: 30, ending: Ending::Majority })
     );
 }
 
 #[test]
 fn new_finished_draw() {
     assert_eq!(
-        Turn::new_finished(20, 20),
-        Turn::Finished(GameResult::Draw { score: 20 })
+        Turn::new_finished(20, 20, Ending::SideEmpty),
+        Turn::Finished(GameResult::Draw { score: 20, ending: Ending::SideEmpty })
     );
 }
 
 #[test]
 fn is_finished() {
-    assert!(Turn::Finished(GameResult::Draw { score: 20 }).is_finished());
+    assert!(Turn::Finished(GameResult::Draw { score: 20, ending: Ending::SideEmpty }).is_finished());
     assert!(!Turn::Player(Player::A).is_finished());
+    assert!(!Turn::SwapOffered(Player::B).is_finished());
 }
 
 #[test]
 fn player() {
     assert_eq!(*Turn::Player(Player::A).player(), Player::A);
+    assert_eq!(*Turn::SwapOffered(Player::B).player(), Player::B);
+}
+
+#[test]
+fn mirrored() {
+    assert_eq!(Turn::SwapOffered(Player::B).mirrored(), Turn::SwapOffered(Player::A));
+    assert_eq!(Turn::new_finished(20, 30, Ending::Majority).mirrored(), Turn::new_finished(30, 20, Ending::Majority));
+    assert_eq!(
+        Turn::Finished(GameResult::Forfeit { player: Player::A, score_a: 2, score_b: 5 }).mirrored(),
+        Turn::Finished(GameResult::Forfeit { player: Player::B, score_a: 5, score_b: 2 })
+    );
+}
+
+#[test]
+fn display_swap_offered() {
+    assert_eq!(Turn::SwapOffered(Player::B).to_string(), "Next turn: Player B (may swap)");
 }
 
 #[test]
 #[should_panic(expected = "Game has finished")]
 fn player_panic() {
-    Turn::Finished(GameResult::Draw { score: 20 }).player();
+    Turn::Finished(GameResult::Draw { score: 20, ending: Ending::SideEmpty }).player();
 }
 
 #[test]
 fn game_result() {
     assert_eq!(
-        *Turn::Finished(GameResult::Draw { score: 20 }).game_result(),
-        GameResult::Draw { score: 20 }
+        *Turn::Finished(GameResult::Draw { score: 20, ending: Ending::SideEmpty }).game_result(),
+        GameResult::Draw { score: 20, ending: Ending::SideEmpty }
     );
 }
 
@@ -54,3 +71,47 @@ fn game_result() {
 fn game_result_panic() {
     Turn::Player(Player::A).game_result();
 }
+
+#[test]
+fn display_forfeit() {
+    assert_eq!(
+        GameResult::Forfeit { player: Player::B, score_a: 10, score_b: 12 }.to_string(),
+        "Player A wins by forfeit (Player B forfeited at 10 to 12)"
+    );
+}
+
+#[test]
+fn display_winner() {
+    assert_eq!(
+        GameResult::Winner { player: Player::B, score_a: 30, score_b: 42, ending: Ending::Majority }.to_string(),
+        "Player B wins 42 to 30 (more than half the seeds banked)"
+    );
+}
+
+#[test]
+fn ranking_from_scores() {
+    let ranking = Ranking::from_scores(&[10, 30, 10, 20]);
+    let places: Vec<(usize, usize)> = ranking.standings.iter().map(|s| (s.seat, s.place)).collect();
+    assert_eq!(places, vec![(1, 1), (3, 2), (0, 3), (2, 3)]);
+    assert_eq!(ranking.winners(), vec![1]);
+    assert_eq!(ranking.to_string(), "1. Player B 30, 2. Player D 20, 3. Player A 10, 3. Player C 10");
+}
+
+#[test]
+fn ranking_draw() {
+    let ranking = GameResult::Draw { score: 36, ending: Ending::SideEmpty }.ranking();
+    assert_eq!(ranking.winners(), vec![0, 1]);
+}
+
+#[test]
+fn ranking_forfeit() {
+    let ranking = GameResult::Forfeit { player: Player::A, score_a: 20, score_b: 10 }.ranking();
+    assert_eq!(ranking.winners(), vec![1]);
+    assert_eq!(ranking.to_string(), "1. Player B 10, 2. Player A 20");
+}
+
+#[test]
+fn ranking_winner() {
+    let result = GameResult::Winner { player: Player::B, score_a: 30, score_b: 42, ending: Ending::Majority };
+    assert_eq!(result.ranking(), Ranking::from_scores(&[30, 42]));
+}
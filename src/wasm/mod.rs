@@ -0,0 +1,163 @@
+// Bindings for playing in the browser, behind the `wasm` feature. Build with
+//
+//   cargo build --lib --release --features wasm --target wasm32-unknown-unknown
+//   wasm-bindgen --target web target/wasm32-unknown-unknown/release/kalaha.wasm --out-dir pkg
+//
+// Nothing here panics on bad input: mistakes come back to JavaScript as a thrown GameError
+// with a kind (e.g. `empty_pool`) and a message.
+use wasm_bindgen::prelude::*;
+
+use super::ai;
+use super::json::ToJson;
+use super::kalaha::Kalaha;
+use super::moves::Move;
+use super::player::Player;
+use super::rules::Rules;
+use super::setup::{Setup, SetupError};
+
+#[cfg(test)]
+mod tests;
+
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Clone)]
+pub struct GameError {
+    kind: String,
+    message: String,
+}
+
+#[wasm_bindgen]
+impl GameError {
+    // a short name to check for, an Error's name for illegal moves
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> String {
+        self.kind.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl GameError {
+    fn new(kind: &str, message: String) -> GameError {
+        GameError { kind: kind.to_string(), message }
+    }
+}
+
+fn parse_player(player: &str) -> Result<Player, GameError> {
+    match player {
+        "A" | "a" => Ok(Player::A),
+        "B" | "b" => Ok(Player::B),
+        _ => Err(GameError::new("invalid_player", format!("'{}' is not a player, use A or B", player))),
+    }
+}
+
+fn parse_rules(rules: Option<String>) -> Result<Rules, GameError> {
+    match rules {
+        Some(rules) => rules.parse().map_err(|reason| GameError::new("invalid_rules", reason)),
+        None => Ok(Rules::default()),
+    }
+}
+
+// What a move did
+#[wasm_bindgen]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Outcome {
+    // the same player moves again
+    pub extra_turn: bool,
+    pub captured: u32,
+    pub laps: u32,
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct Game {
+    game: Kalaha,
+}
+
+#[wasm_bindgen]
+impl Game {
+    // A game from the usual start, under rules as in Rules::from_str or kalah by default
+    #[wasm_bindgen(constructor)]
+    pub fn new(rules: Option<String>) -> Result<Game, GameError> {
+        Ok(Game { game: Kalaha::with_rules(parse_rules(rules)?) })
+    }
+
+    // A game from a Setup such as `6,6,6,6,6,6/0/6,6,6,6,6,6/0/A`, with any number of seeds
+    #[wasm_bindgen(js_name = fromSetup)]
+    pub fn from_setup(rules: Option<String>, setup: &str) -> Result<Game, GameError> {
+        let rules = parse_rules(rules)?;
+        let setup: Setup = setup.parse().map_err(|error: SetupError| GameError::new("invalid_setup", error.to_string()))?;
+        Kalaha::from_setup_any_total(rules, &setup)
+            .map(|game| Game { game })
+            .map_err(|error| GameError::new("invalid_setup", error.to_string()))
+    }
+
+    pub fn rules(&self) -> String {
+        self.game.rules().to_string()
+    }
+
+    // the player's ponds, from their first to their last
+    pub fn ponds(&self, player: &str) -> Result<Vec<u32>, GameError> {
+        Ok(self.game.ponds(&parse_player(player)?).to_vec())
+    }
+
+    pub fn bank(&self, player: &str) -> Result<u32, GameError> {
+        Ok(self.game.bank(&parse_player(player)?))
+    }
+
+    // A or B, or undefined once the game has finished
+    #[wasm_bindgen(js_name = currentPlayer)]
+    pub fn current_player(&self) -> Option<String> {
+        if self.game.is_finished() {
+            return None;
+        }
+        match *self.game.current_player() {
+            Player::A => Some("A".to_string()),
+            Player::B => Some("B".to_string()),
+        }
+    }
+
+    #[wasm_bindgen(js_name = isFinished)]
+    pub fn is_finished(&self) -> bool {
+        self.game.is_finished()
+    }
+
+    // moves as in Move::from_str, e.g. `3`, `3cw` or `swap`
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&self) -> Vec<String> {
+        self.game.legal_moves().iter().map(Move::to_string).collect()
+    }
+
+    #[wasm_bindgen(js_name = makeMove)]
+    pub fn make_move(&mut self, mv: &str) -> Result<Outcome, GameError> {
+        let mv: Move = mv.parse().map_err(|reason| GameError::new("invalid_move", reason))?;
+        self.game.valid_move(mv).map_err(|error| GameError::new(error.name(), format!("{} is not allowed", mv)))?;
+        let outcome = self.game.choose(mv);
+        Ok(Outcome { extra_turn: outcome.extra_turn, captured: outcome.captured, laps: outcome.laps })
+    }
+
+    // The move an AI (first, last, minmax, alphabeta or caching) would make, without making it
+    #[wasm_bindgen(js_name = aiMove)]
+    pub fn ai_move(&self, ai: &str, depth: u32) -> Result<String, GameError> {
+        let ai = ai::by_name(ai, depth.max(1)).ok_or_else(|| GameError::new("invalid_ai", format!("unknown AI '{}'", ai)))?;
+        ai.choose(&self.game)
+            .map(|mv| mv.to_string())
+            .map_err(|error| GameError::new("ai_failed", error.to_string()))
+    }
+
+    // how the game ended, or undefined while it's going
+    pub fn result(&self) -> Option<String> {
+        if self.game.is_finished() {
+            Some(self.game.game_result().to_string())
+        } else {
+            None
+        }
+    }
+
+    // the whole game as JSON, as the HTTP API shows it
+    pub fn state(&self) -> String {
+        self.game.to_json().to_string()
+    }
+}
@@ -0,0 +1,65 @@
+use super::*;
+
+fn kind<T: ::std::fmt::Debug>(result: Result<T, GameError>) -> String {
+    result.unwrap_err().kind()
+}
+
+#[test]
+fn new_game() {
+    let game = Game::new(None).unwrap();
+    assert_eq!(game.rules(), "kalah");
+    assert_eq!(game.ponds("A"), Ok(vec![6; 6]));
+    assert_eq!(game.bank("b"), Ok(0));
+    assert_eq!(game.current_player(), Some("A".to_string()));
+    assert_eq!(game.legal_moves(), vec!["0", "1", "2", "3", "4", "5"]);
+    assert_eq!(Game::new(Some("oware sowing=choice".to_string())).unwrap().legal_moves()[..2], ["0ccw", "0cw"]);
+    assert_eq!(kind(Game::new(Some("chess".to_string()))), "invalid_rules");
+    assert_eq!(kind(game.ponds("C")), "invalid_player");
+}
+
+#[test]
+fn from_setup() {
+    let game = Game::from_setup(None, "0,0,0,0,0,1/0/1,0,0,0,0,0/0/B").unwrap();
+    assert_eq!(game.current_player(), Some("B".to_string()));
+    assert_eq!(kind(Game::from_setup(None, "1,2,3")), "invalid_setup");
+    assert_eq!(kind(Game::from_setup(None, "0,0,0,0,0,0/0/1,0,0,0,0,0/0/A")), "invalid_setup");
+}
+
+#[test]
+fn moves() {
+    let mut game = Game::new(None).unwrap();
+    assert_eq!(game.make_move("0"), Ok(Outcome { extra_turn: true, captured: 0, laps: 1 }));
+    assert_eq!(game.ponds("A"), Ok(vec![0, 7, 7, 7, 7, 7]));
+    assert_eq!(kind(game.make_move("0")), "empty_pool");
+    assert_eq!(kind(game.make_move("9")), "invalid_index");
+    assert_eq!(kind(game.make_move("north")), "invalid_move");
+    assert_eq!(game.make_move("0cw").unwrap_err(), GameError::new("wrong_direction", "0cw is not allowed".to_string()));
+}
+
+#[test]
+fn plays_to_the_end() {
+    let mut game = Game::new(Some("kalah pie".to_string())).unwrap();
+    while !game.is_finished() {
+        let mv = game.ai_move("alphabeta", 2).unwrap();
+        game.make_move(&mv).unwrap();
+    }
+    assert_eq!(game.current_player(), None);
+    assert!(game.result().is_some());
+    assert_eq!(game.bank("A").unwrap() + game.bank("B").unwrap(), 72);
+    assert_eq!(kind(game.ai_move("alphabeta", 2)), "ai_failed");
+    assert_eq!(kind(game.make_move("0")), "game_finished");
+}
+
+#[test]
+fn ai_errors() {
+    let game = Game::new(None).unwrap();
+    assert_eq!(kind(game.ai_move("oracle", 4)), "invalid_ai");
+    // a depth of 0 still searches a move ahead
+    assert!(game.ai_move("minmax", 0).is_ok());
+}
+
+#[test]
+fn state() {
+    let game = Game::new(None).unwrap();
+    assert!(game.state().starts_with(r#"{"rules":"kalah","board":{"ponds_a":[6,6,6,6,6,6]"#));
+}
@@ -0,0 +1,116 @@
+/* Exercises the C API, see tests/ffi.rs for how it's built and run */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "kalaha.h"
+
+static int failures = 0;
+
+#define CHECK(condition) do { \
+    if (!(condition)) { \
+        fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #condition); \
+        failures++; \
+    } \
+} while (0)
+
+static KalahaMove pond(uint32_t pond) {
+    KalahaMove mv = { KALAHA_MOVE_POND, pond };
+    return mv;
+}
+
+static void new_game(void) {
+    KalahaGame *game = kalaha_new();
+    uint32_t ponds[6];
+    CHECK(kalaha_ponds(game, KALAHA_PLAYER_A, ponds) == KALAHA_STATUS_OK);
+    for (int i = 0; i < 6; i++) {
+        CHECK(ponds[i] == 6);
+    }
+    CHECK(kalaha_bank(game, KALAHA_PLAYER_B) == 0);
+    CHECK(kalaha_current_player(game) == KALAHA_PLAYER_A);
+    CHECK(!kalaha_is_finished(game));
+    CHECK(kalaha_ponds(game, 7, ponds) == KALAHA_STATUS_INVALID_ARGUMENT);
+    kalaha_free(game);
+}
+
+static void moves(void) {
+    KalahaGame *game = kalaha_new();
+    KalahaOutcome outcome;
+    CHECK(kalaha_valid_move(game, pond(0)) == KALAHA_STATUS_OK);
+    CHECK(kalaha_choose(game, pond(0), &outcome) == KALAHA_STATUS_OK);
+    CHECK(outcome.extra_turn && outcome.captured == 0 && outcome.laps == 1);
+    CHECK(kalaha_bank(game, KALAHA_PLAYER_A) == 1);
+    CHECK(kalaha_current_player(game) == KALAHA_PLAYER_A);
+    CHECK(kalaha_valid_move(game, pond(0)) == KALAHA_STATUS_EMPTY_POOL);
+    CHECK(kalaha_choose(game, pond(6), NULL) == KALAHA_STATUS_INVALID_INDEX);
+    KalahaMove swap = { KALAHA_MOVE_SWAP, 0 };
+    CHECK(kalaha_choose(game, swap, NULL) == KALAHA_STATUS_CANNOT_SWAP);
+    KalahaMove clockwise = { KALAHA_MOVE_CLOCKWISE, 1 };
+    CHECK(kalaha_choose(game, clockwise, NULL) == KALAHA_STATUS_WRONG_DIRECTION);
+    KalahaMove unknown = { 9, 1 };
+    CHECK(kalaha_choose(game, unknown, NULL) == KALAHA_STATUS_INVALID_ARGUMENT);
+    CHECK(kalaha_choose(game, pond(1), NULL) == KALAHA_STATUS_OK);
+    CHECK(kalaha_current_player(game) == KALAHA_PLAYER_B);
+    kalaha_free(game);
+}
+
+static void play_out(const char *rules) {
+    KalahaGame *game = kalaha_with_rules(rules);
+    CHECK(game != NULL);
+    KalahaResult result;
+    CHECK(kalaha_result(game, &result) == KALAHA_STATUS_NOT_FINISHED);
+    int moves = 0;
+    while (!kalaha_is_finished(game) && moves < 1000) {
+        KalahaMove mv;
+        CHECK(kalaha_ai_move(game, "alphabeta", 3, &mv) == KALAHA_STATUS_OK);
+        CHECK(kalaha_choose(game, mv, NULL) == KALAHA_STATUS_OK);
+        moves++;
+    }
+    CHECK(kalaha_is_finished(game));
+    CHECK(kalaha_current_player(game) == KALAHA_PLAYER_NONE);
+    CHECK(kalaha_result(game, &result) == KALAHA_STATUS_OK);
+    CHECK(!result.forfeit);
+    CHECK(result.score_a == kalaha_bank(game, KALAHA_PLAYER_A));
+    if (result.winner == KALAHA_PLAYER_A) {
+        CHECK(result.score_a > result.score_b);
+    } else if (result.winner == KALAHA_PLAYER_B) {
+        CHECK(result.score_b > result.score_a);
+    } else {
+        CHECK(result.score_a == result.score_b);
+    }
+    KalahaMove mv;
+    CHECK(kalaha_ai_move(game, "alphabeta", 3, &mv) == KALAHA_STATUS_GAME_FINISHED);
+    CHECK(kalaha_choose(game, pond(0), NULL) == KALAHA_STATUS_GAME_FINISHED);
+    kalaha_free(game);
+}
+
+static void bad_arguments(void) {
+    CHECK(kalaha_with_rules("chess") == NULL);
+    CHECK(kalaha_with_rules(NULL) == NULL);
+    CHECK(kalaha_valid_move(NULL, pond(0)) == KALAHA_STATUS_NULL_POINTER);
+    CHECK(kalaha_is_finished(NULL));
+    kalaha_free(NULL);
+    KalahaGame *game = kalaha_new();
+    KalahaMove mv;
+    CHECK(kalaha_ai_move(game, "oracle", 3, &mv) == KALAHA_STATUS_INVALID_ARGUMENT);
+    CHECK(kalaha_ai_move(game, NULL, 3, &mv) == KALAHA_STATUS_NULL_POINTER);
+    CHECK(kalaha_ponds(game, KALAHA_PLAYER_A, NULL) == KALAHA_STATUS_NULL_POINTER);
+    CHECK(strcmp(kalaha_status_message(KALAHA_STATUS_EMPTY_POOL), "the pond is empty") == 0);
+    CHECK(strcmp(kalaha_status_message(99), "unknown status") == 0);
+    kalaha_free(game);
+}
+
+int main(void) {
+    new_game();
+    moves();
+    play_out("kalah");
+    play_out("oware sowing=choice");
+    play_out("kalah pie");
+    bad_arguments();
+    if (failures > 0) {
+        fprintf(stderr, "%d checks failed\n", failures);
+        return EXIT_FAILURE;
+    }
+    printf("all checks passed\n");
+    return EXIT_SUCCESS;
+}
@@ -0,0 +1,71 @@
+// Plays against the kalaha binary's engine mode through ExternalEngine
+extern crate kalaha;
+
+use std::io;
+use std::time::{Duration, Instant};
+
+use kalaha::ai::{AI, AIError, AlphaBeta};
+use kalaha::engine::{ExternalEngine, SearchLimit};
+use kalaha::{Kalaha, Move, Rules};
+
+fn engine(ai: &str, limit: SearchLimit) -> ExternalEngine {
+    ExternalEngine::spawn(env!("CARGO_BIN_EXE_kalaha"), &["engine", "--ai", ai], limit).unwrap()
+}
+
+#[test]
+fn same_moves_as_built_in() {
+    let external = engine("alphabeta", SearchLimit::Depth(4));
+    assert_eq!(external.name(), "kalaha alphabeta");
+    let built_in = AlphaBeta::new(4);
+    for rules in &[Rules::kalah(), Rules::oware(), "kalah sowing=choice relay".parse().unwrap()] {
+        let mut game = Kalaha::with_rules(*rules);
+        while !game.is_finished() {
+            let expected = built_in.analyse(&game).unwrap();
+            assert_eq!(external.analyse(&game), Ok(expected), "{} {:?}", rules, game.setup());
+            game.choose(expected.mv);
+        }
+        assert_eq!(external.choose(&game), Err(AIError::GameFinished));
+    }
+}
+
+#[test]
+fn swaps_under_the_pie_rule() {
+    let mut game = Kalaha::with_rules(Rules::kalah().with_pie_rule(true));
+    game.choose(0);
+    game.choose(1);
+    assert_eq!(engine("alphabeta", SearchLimit::Depth(1)).choose(&game), Ok(Move::Swap));
+}
+
+#[test]
+fn timed_search() {
+    let external = engine("alphabeta", SearchLimit::MoveTime(Duration::from_millis(50)));
+    let game = Kalaha::new();
+    assert!(game.valid_move(external.choose(&game).unwrap()).is_ok());
+}
+
+#[test]
+fn engine_that_exits() {
+    assert!(ExternalEngine::spawn("true", &[], SearchLimit::Depth(1)).is_err());
+}
+
+#[test]
+fn engine_that_never_answers() {
+    // cat echoes kei back but never says keiok
+    let start = Instant::now();
+    let spawned = ExternalEngine::spawn_with_timeout("cat", &[], SearchLimit::Depth(1), Duration::from_millis(100));
+    assert_eq!(spawned.unwrap_err().kind(), io::ErrorKind::TimedOut);
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn engine_that_never_moves() {
+    // answers the handshake, then reads everything it's sent without replying
+    let script = "read line; echo keiok; while read line; do :; done";
+    let limit = SearchLimit::MoveTime(Duration::from_millis(50));
+    let external = ExternalEngine::spawn("sh", &["-c", script], limit).unwrap();
+    let start = Instant::now();
+    assert_eq!(external.choose(&Kalaha::new()), Err(AIError::OutOfTime));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    // and it was killed, so there's no more asking it
+    assert!(matches!(external.choose(&Kalaha::new()), Err(AIError::Failed(_))));
+}
@@ -0,0 +1,61 @@
+// Builds tests/c/kalaha_test.c against the cdylib and runs it
+#![cfg(feature = "ffi")]
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Builds the cdylib with just the ffi feature into its own target directory. The one next to
+// this test may have been built with other features, or not at all.
+fn build_library(root: &Path) -> PathBuf {
+    let target = root.join("target").join("ffi-test");
+    let status = Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()))
+        .current_dir(root)
+        .args(["build", "--lib", "--features", "ffi", "--target-dir"])
+        .arg(&target)
+        .status()
+        .expect("Can't run cargo");
+    assert!(status.success(), "the library didn't build");
+    target.join("debug")
+}
+
+// The header the build script generated, from this build's OUT_DIR
+const GENERATED_HEADER: &str = include_str!(concat!(env!("OUT_DIR"), "/kalaha.h"));
+
+// include/kalaha.h is what C programs include, so it has to match the API. Running with
+// UPDATE_HEADER=1 writes the generated one over it.
+#[test]
+fn header_is_up_to_date() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("include/kalaha.h");
+    if env::var_os("UPDATE_HEADER").is_some() {
+        fs::write(&path, GENERATED_HEADER).unwrap();
+    }
+    let checked_in = fs::read_to_string(&path).unwrap();
+    assert!(
+        checked_in == GENERATED_HEADER,
+        "include/kalaha.h is out of date, update it with UPDATE_HEADER=1 cargo test --features ffi --test ffi"
+    );
+}
+
+#[test]
+fn c_program() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let lib = build_library(&root);
+    let program = lib.join("kalaha_test");
+    let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".to_string()))
+        .args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-o"])
+        .arg(&program)
+        .arg(root.join("tests/c/kalaha_test.c"))
+        .arg("-I")
+        .arg(root.join("include"))
+        .arg("-L")
+        .arg(&lib)
+        .arg("-lkalaha")
+        .status()
+        .expect("Can't run the C compiler");
+    assert!(status.success(), "the C test program didn't compile");
+    let output = Command::new(&program).env("LD_LIBRARY_PATH", &lib).env("DYLD_LIBRARY_PATH", &lib).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "all checks passed\n");
+}
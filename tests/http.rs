@@ -0,0 +1,118 @@
+// Drives the kalaha-http binary over localhost
+extern crate kalaha;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+
+use kalaha::json::Json;
+
+// The binary listening on a free port, killed when dropped
+struct Api {
+    child: Child,
+    addr: String,
+}
+
+impl Api {
+    fn start() -> Api {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_kalaha-http"))
+            .args(["--addr", "127.0.0.1:0"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut line = String::new();
+        BufReader::new(child.stdout.as_mut().unwrap()).read_line(&mut line).unwrap();
+        let addr = line.trim().strip_prefix("Listening on http://").expect("no address").to_string();
+        Api { child, addr }
+    }
+
+    // the status and JSON body
+    fn request(&self, method: &str, path: &str, body: &str) -> (u16, Json) {
+        let mut stream = TcpStream::connect(&self.addr).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            method, path, self.addr, body.len(), body
+        ).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response[9..12].parse().unwrap();
+        let body = &response[response.find("\r\n\r\n").unwrap() + 4..];
+        (status, if body.is_empty() { Json::Null } else { Json::parse(body).unwrap() })
+    }
+}
+
+impl Drop for Api {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn text<'a>(json: &'a Json, key: &str) -> &'a str {
+    json.get(key).and_then(Json::as_str).unwrap_or_else(|| panic!("no {} in {}", key, json))
+}
+
+#[test]
+fn plays_a_game_to_the_end() {
+    let api = Api::start();
+    let (status, game) = api.request("POST", "/games", r#"{"rules": "kalah"}"#);
+    assert_eq!(status, 201);
+    let id = game.get("id").and_then(Json::as_i64).unwrap();
+    let mut moves = 0;
+    loop {
+        let (status, game) = api.request("GET", &format!("/games/{}", id), "");
+        assert_eq!(status, 200);
+        let turn = game.get("turn").unwrap();
+        if text(turn, "state") == "finished" {
+            let result = turn.get("result").unwrap();
+            assert_eq!(game.get("legal_moves"), Some(&Json::Array(vec![])));
+            assert_eq!(
+                result.get("score_a").and_then(Json::as_i64).unwrap() + result.get("score_b").and_then(Json::as_i64).unwrap(),
+                72
+            );
+            break;
+        }
+        // player A asks the AI, player B plays its first legal move
+        if text(turn, "player") == "A" {
+            let (status, played) = api.request("POST", &format!("/games/{}/ai", id), r#"{"depth": 3, "play": true}"#);
+            assert_eq!(status, 200, "{}", played);
+        } else {
+            let mv = match game.get("legal_moves") {
+                Some(Json::Array(legal)) => legal[0].clone(),
+                _ => panic!("no legal moves in {}", game),
+            };
+            let body = Json::object(vec![("move", mv)]).to_string();
+            assert_eq!(api.request("POST", &format!("/games/{}/moves", id), &body).0, 200);
+        }
+        moves += 1;
+    }
+    let (_, history) = api.request("GET", &format!("/games/{}/history", id), "");
+    assert!(matches!(history.get("moves"), Some(Json::Array(played)) if played.len() == moves));
+}
+
+#[test]
+fn errors() {
+    let api = Api::start();
+    assert_eq!(api.request("GET", "/games/1", "").0, 404);
+    api.request("POST", "/games", "");
+    let (status, body) = api.request("POST", "/games/1/moves", r#"{"move": "12"}"#);
+    assert_eq!((status, text(&body, "error")), (422, "invalid_index"));
+    assert_eq!(api.request("POST", "/games/1/moves", "not json").0, 400);
+    assert_eq!(api.request("OPTIONS", "/games/1/moves", ""), (204, Json::Null));
+}
+
+#[test]
+fn hostile_requests() {
+    let api = Api::start();
+    // nesting deep enough to overflow a recursive parser's stack
+    let (status, body) = api.request("POST", "/games", &"[".repeat(60000));
+    assert_eq!((status, text(&body, "error")), (400, "invalid JSON: nested too deeply at 64"));
+    let mut stream = TcpStream::connect(&api.addr).unwrap();
+    write!(stream, "GET /games/1 HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "x".repeat(10_000)).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 431 "), "{}", response);
+    // and the server is still there
+    assert_eq!(api.request("POST", "/games", "").0, 201);
+}
@@ -0,0 +1,92 @@
+// Draws games with the kalaha binary's render and replay commands
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn out_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn every_position_of_a_game() {
+    let dir = out_dir("render-game");
+    let output = Command::new(env!("CARGO_BIN_EXE_kalaha"))
+        .args(["render", "--pie", "--moves", "2 swap 1"])
+        .arg("--out")
+        .arg(dir.join("boards"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let mut files: Vec<String> = fs::read_dir(dir.join("boards")).unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    files.sort();
+    assert_eq!(files, ["000.svg", "001.svg", "002.svg", "003.svg"]);
+    let last = fs::read_to_string(dir.join("boards/003.svg")).unwrap();
+    assert!(last.contains(">Move 3: B plays 1</text>"), "{}", last);
+}
+
+#[test]
+fn one_position() {
+    let dir = out_dir("render-position");
+    let path = dir.join("board.svg");
+    let status = Command::new(env!("CARGO_BIN_EXE_kalaha"))
+        .args(["render", "--rules", "oware", "--out"])
+        .arg(&path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let svg = fs::read_to_string(&path).unwrap();
+    let fours = svg.lines().filter(|line| line.starts_with("<text class=\"count\"") && line.ends_with(">4</text>"));
+    assert_eq!(fours.count(), 12);
+}
+
+#[test]
+fn illegal_moves() {
+    let output = Command::new(env!("CARGO_BIN_EXE_kalaha"))
+        .args(["render", "--moves", "0 0", "--out"])
+        .arg(out_dir("render-illegal"))
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "move 2, 0, is illegal: empty_pool\n");
+}
+
+#[test]
+fn replay_a_game_file() {
+    let dir = out_dir("replay-file");
+    fs::write(dir.join("game.txt"), "2\nswap\n1\n").unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_kalaha"))
+        .args(["replay", "--pie", "--no_eval", "--title", "Opening"])
+        .arg("--game")
+        .arg(dir.join("game.txt"))
+        .arg("--out")
+        .arg(dir.join("replay.html"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let html = fs::read_to_string(dir.join("replay.html")).unwrap();
+    assert!(html.contains("<h1>Opening</h1>"), "{}", html);
+    assert_eq!(html.matches("<svg").count(), 4);
+    assert!(html.contains(">Move 2: B plays swap, swaps sides</p>"), "{}", html);
+    assert!(!html.contains("class=\"bar\""));
+}
+
+#[test]
+fn replay_a_game_between_ais() {
+    let dir = out_dir("replay-ais");
+    let status = Command::new(env!("CARGO_BIN_EXE_kalaha"))
+        .args(["replay", "-a", "first", "-b", "last", "--eval_depth", "2", "--out"])
+        .arg(dir.join("replay.html"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let html = fs::read_to_string(dir.join("replay.html")).unwrap();
+    assert!(html.contains("<h1>FirstValid vs LastValid</h1>"), "{}", html);
+    // a bar for every position, and the result once the game is over
+    assert_eq!(html.matches("<svg").count(), html.matches("class=\"bar\"").count());
+    assert!(html.contains("<p class=\"result\">"), "{}", html);
+}
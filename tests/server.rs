@@ -0,0 +1,131 @@
+// Plays games through the TCP server on localhost
+extern crate kalaha;
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::thread::{self, JoinHandle};
+
+use kalaha::ai::{AI, AlphaBeta, FirstValid};
+use kalaha::server::{self, HostedGame, Server};
+use kalaha::{GameResult, Kalaha, Player, Rules};
+
+// A server for one game starting from game, run in the background
+fn start(game: Kalaha) -> (SocketAddr, JoinHandle<HostedGame>) {
+    let server = Server::bind("127.0.0.1:0", game).unwrap();
+    let addr = server.local_addr().unwrap();
+    let handle = thread::spawn(move || server.run(Some(1), false).unwrap().remove(0));
+    (addr, handle)
+}
+
+// A client speaking the protocol by hand
+struct Raw {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Raw {
+    fn join(addr: SocketAddr, name: &str) -> Raw {
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut raw = Raw { reader: BufReader::new(stream.try_clone().unwrap()), writer: stream };
+        raw.send(&format!("join {}", name));
+        raw
+    }
+
+    fn send(&mut self, line: &str) {
+        writeln!(self.writer, "{}", line).unwrap();
+    }
+
+    // skips lines until one starting with prefix, which is returned
+    fn expect(&mut self, prefix: &str) -> String {
+        loop {
+            let mut line = String::new();
+            assert!(self.reader.read_line(&mut line).unwrap() > 0, "closed waiting for '{}'", prefix);
+            if line.starts_with(prefix) {
+                return line.trim().to_string();
+            }
+        }
+    }
+}
+
+#[test]
+fn plays_like_a_local_game() {
+    let ai = AlphaBeta::new(3);
+    let mut local = Kalaha::new();
+    while !local.is_finished() {
+        local.choose(ai.choose(&local).unwrap());
+    }
+    let (addr, server) = start(Kalaha::new());
+    let clients: Vec<_> = ["one", "two"].iter()
+        .map(|name| thread::spawn(move || server::connect(addr, name, &AlphaBeta::new(3), false).unwrap()))
+        .collect();
+    let results: Vec<_> = clients.into_iter().map(|client| client.join().unwrap()).collect();
+    let hosted = server.join().unwrap();
+    assert_eq!(hosted.game_result, *local.game_result());
+    let mut names = vec![hosted.player_a, hosted.player_b];
+    names.sort();
+    assert_eq!(names, vec!["one", "two"]);
+    assert_ne!(results[0].side, results[1].side);
+    for result in &results {
+        assert_eq!(result.game_result, *local.game_result());
+    }
+}
+
+#[test]
+fn illegal_moves_forfeit() {
+    let (addr, server) = start(Kalaha::new());
+    let mut raw = Raw::join(addr, "raw");
+    let client = thread::spawn(move || server::connect(addr, "first", &FirstValid {}, false).unwrap());
+    assert_eq!(raw.expect("player"), "player A");
+    assert_eq!(raw.expect("rules"), "rules kalah");
+    raw.expect("turn");
+    for &(mv, reason) in &[("move 9", "invalid_index"), ("move swap", "cannot_swap"), ("pass", "not_a_move")] {
+        raw.send(mv);
+        assert_eq!(raw.expect("illegal"), format!("illegal {}", reason));
+    }
+    assert_eq!(raw.expect("result"), "result 0 0 forfeit A");
+    let forfeit = GameResult::Forfeit { player: Player::A, score_a: 0, score_b: 0 };
+    let result = client.join().unwrap();
+    assert_eq!((result.side, result.game_result), (Player::B, forfeit.clone()));
+    assert_eq!(server.join().unwrap(), HostedGame {
+        player_a: "raw".to_string(),
+        player_b: "first".to_string(),
+        game_result: forfeit,
+    });
+}
+
+#[test]
+fn leaving_forfeits() {
+    let (addr, server) = start(Kalaha::with_rules(Rules::oware()));
+    let mut raw = Raw::join(addr, "raw");
+    let client = thread::spawn(move || server::connect(addr, "first", &FirstValid {}, false).unwrap());
+    assert_eq!(raw.expect("rules"), "rules oware");
+    raw.expect("turn");
+    drop(raw);
+    let result = client.join().unwrap();
+    assert_eq!(result.game_result, GameResult::Forfeit { player: Player::A, score_a: 0, score_b: 0 });
+    assert_eq!(server.join().unwrap().game_result, result.game_result);
+}
+
+#[test]
+fn swapping_changes_sides() {
+    let (addr, server) = start(Kalaha::with_rules(Rules::kalah().with_pie_rule(true)));
+    let mut first = Raw::join(addr, "first");
+    let mut second = Raw::join(addr, "second");
+    assert_eq!(first.expect("player"), "player A");
+    assert_eq!(second.expect("player"), "player B");
+    first.expect("turn");
+    first.send("move 1");
+    assert_eq!(second.expect("moved"), "moved 1");
+    assert_eq!(second.expect("position"), "position 6,0,7,7,7,7/1/7,6,6,6,6,6/0/B swap");
+    second.expect("turn");
+    second.send("move swap");
+    first.expect("moved swap");
+    assert_eq!(first.expect("player"), "player B");
+    assert_eq!(second.expect("player"), "player A");
+    // B still moves, and the first client now plays B
+    first.expect("turn");
+    drop(first);
+    assert_eq!(second.expect("result"), "result 1 0 forfeit B");
+    let hosted = server.join().unwrap();
+    assert_eq!((hosted.player_a.as_str(), hosted.player_b.as_str()), ("second", "first"));
+}